@@ -1,26 +1,211 @@
+use std::collections::VecDeque;
+
 use anyhow::Result;
 use ffmpeg_next::frame;
 use madvr_parse::MadVRFrame;
 use rayon::prelude::*;
 
-use crate::analysis::histogram::{compute_hue_histogram, nits_to_pq};
+use crate::analysis::histogram::{compute_hue_histogram, nits_to_pq, pq_to_nits, HUE_BINS};
 use crate::analysis::hlg::hlg_signal_to_nits;
+use crate::analysis::log_histogram::LogHistogram;
 use crate::crop::CropRect;
 use crate::ffmpeg_io::TransferFunction;
 
-/// Apply 3x3 median filter to Y-plane data (in-place on a cloned buffer).
+const VARIANCE_TILE_SIZE: usize = 16;
+
+/// Per-16x16-tile luma variance map produced by [`compute_variance_map`]. Drives
+/// content-adaptive denoise strength (flat tiles need little or no filtering; grainy ones need
+/// full filtering) and can optionally down-weight busy/grainy tiles when integrating `avg_pq`.
+pub struct VarianceMap {
+    values: Vec<f64>,
+    tiles_x: usize,
+    tiles_y: usize,
+    crop_x: usize,
+    crop_y: usize,
+}
+
+impl VarianceMap {
+    /// The variance of the tile containing absolute pixel coordinate (x, y).
+    fn variance_at(&self, x: usize, y: usize) -> f64 {
+        if self.tiles_x == 0 || self.tiles_y == 0 {
+            return 0.0;
+        }
+        let tx = (x.saturating_sub(self.crop_x) / VARIANCE_TILE_SIZE).min(self.tiles_x - 1);
+        let ty = (y.saturating_sub(self.crop_y) / VARIANCE_TILE_SIZE).min(self.tiles_y - 1);
+        self.values[ty * self.tiles_x + tx]
+    }
+}
+
+/// Compute per-16x16-tile luma variance (mean and sum-of-squared-deviations over 10-bit code
+/// values) across the active crop area -- the same statistic x265's lowres activity/AQ stage
+/// uses. Used to tell flat regions (little noise, don't smear detail) from grainy ones (apply
+/// full denoise strength).
+fn compute_variance_map(y_data: &[u8], stride: usize, crop_rect: &CropRect) -> VarianceMap {
+    let x_start = crop_rect.x as usize;
+    let y_start = crop_rect.y as usize;
+    let x_end = x_start + crop_rect.width as usize;
+    let y_end = y_start + crop_rect.height as usize;
+
+    let tiles_x = crop_rect.width.div_ceil(VARIANCE_TILE_SIZE as u32).max(1) as usize;
+    let tiles_y = crop_rect.height.div_ceil(VARIANCE_TILE_SIZE as u32).max(1) as usize;
+    let mut values = vec![0.0f64; tiles_x * tiles_y];
+
+    for ty in 0..tiles_y {
+        let tile_y_start = y_start + ty * VARIANCE_TILE_SIZE;
+        let tile_y_end = (tile_y_start + VARIANCE_TILE_SIZE).min(y_end);
+        for tx in 0..tiles_x {
+            let tile_x_start = x_start + tx * VARIANCE_TILE_SIZE;
+            let tile_x_end = (tile_x_start + VARIANCE_TILE_SIZE).min(x_end);
+
+            let mut sum = 0.0f64;
+            let mut count = 0.0f64;
+            for y in tile_y_start..tile_y_end {
+                let row_start = y * stride + tile_x_start * 2;
+                let row_len = (tile_x_end - tile_x_start) * 2;
+                if row_start + row_len > y_data.len() {
+                    continue;
+                }
+                for px in y_data[row_start..row_start + row_len].chunks_exact(2) {
+                    sum += (u16::from_le_bytes([px[0], px[1]]) & 0x03FF) as f64;
+                    count += 1.0;
+                }
+            }
+
+            if count > 0.0 {
+                let mean = sum / count;
+                let mut sum_sq_dev = 0.0f64;
+                for y in tile_y_start..tile_y_end {
+                    let row_start = y * stride + tile_x_start * 2;
+                    let row_len = (tile_x_end - tile_x_start) * 2;
+                    if row_start + row_len > y_data.len() {
+                        continue;
+                    }
+                    for px in y_data[row_start..row_start + row_len].chunks_exact(2) {
+                        let code = (u16::from_le_bytes([px[0], px[1]]) & 0x03FF) as f64;
+                        sum_sq_dev += (code - mean) * (code - mean);
+                    }
+                }
+                values[ty * tiles_x + tx] = sum_sq_dev / count;
+            }
+        }
+    }
+
+    VarianceMap {
+        values,
+        tiles_x,
+        tiles_y,
+        crop_x: x_start,
+        crop_y: y_start,
+    }
+}
+
+/// Single-pass whole-frame mean and variance of Y-plane intensity (10-bit code values) over the
+/// active crop, for [`crate::analysis::scene::variance_gate_confirms`]. Unlike
+/// [`compute_variance_map`]'s per-16x16-tile variance (used for denoise strength), this is one
+/// aggregate statistic for the whole frame, derived via `variance = E[Y^2] - E[Y]^2` from
+/// running integer sum/sum-of-squares accumulators (a single read-through, vs. the two-pass
+/// mean-then-deviation loop `compute_variance_map` uses per tile -- fine there since tiles are
+/// small, but worth avoiding here since this runs over the full crop every frame).
+pub fn compute_intensity_stats(y_data: &[u8], stride: usize, crop_rect: &CropRect) -> (f64, f64) {
+    let x_start = crop_rect.x as usize;
+    let y_start = crop_rect.y as usize;
+    let x_end = x_start + crop_rect.width as usize;
+    let y_end = y_start + crop_rect.height as usize;
+
+    let mut sum: u64 = 0;
+    let mut sum_sq: u64 = 0;
+    let mut count: u64 = 0;
+
+    for y in y_start..y_end {
+        let row_start = y * stride + x_start * 2;
+        let row_len = (x_end - x_start) * 2;
+        if row_start + row_len > y_data.len() {
+            continue;
+        }
+        for px in y_data[row_start..row_start + row_len].chunks_exact(2) {
+            let code = (u16::from_le_bytes([px[0], px[1]]) & 0x03FF) as u64;
+            sum += code;
+            sum_sq += code * code;
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+
+    let mean = sum as f64 / count as f64;
+    let mean_sq = sum_sq as f64 / count as f64;
+    (mean, (mean_sq - mean * mean).max(0.0))
+}
+
+/// Downsample the active-crop Y-plane to a small fixed-height luma thumbnail (nearest-neighbor,
+/// keeping aspect), normalized to `[0, 1]`, for the low-cost frame-to-frame motion signal used
+/// by `--scene-metric flow`/`hybrid`. A thumbnail this small keeps the per-frame scratch buffers
+/// (and the MAD comparison in [`luma_mad`]) O(1) in source resolution.
+pub fn downsample_luma_thumbnail(
+    y_data: &[u8],
+    stride: usize,
+    crop_rect: &CropRect,
+    target_height: u32,
+) -> Vec<f64> {
+    let target_height = target_height.max(1);
+    let target_width = ((crop_rect.width as u64 * target_height as u64)
+        / crop_rect.height.max(1) as u64)
+        .max(1) as u32;
+
+    let mut thumbnail = Vec::with_capacity((target_width * target_height) as usize);
+    for ty in 0..target_height {
+        let src_y = crop_rect.y + (ty * crop_rect.height) / target_height;
+        for tx in 0..target_width {
+            let src_x = crop_rect.x + (tx * crop_rect.width) / target_width;
+            let offset = src_y as usize * stride + src_x as usize * 2;
+            let code = if offset + 1 < y_data.len() {
+                (u16::from_le_bytes([y_data[offset], y_data[offset + 1]]) & 0x03FF) as f64
+            } else {
+                0.0
+            };
+            thumbnail.push(code / 1023.0);
+        }
+    }
+    thumbnail
+}
+
+/// Mean absolute difference between two luma thumbnails from [`downsample_luma_thumbnail`],
+/// already normalized to `[0, 1]` by construction.
+pub fn luma_mad(curr: &[f64], prev: &[f64]) -> f64 {
+    if curr.is_empty() || curr.len() != prev.len() {
+        return 0.0;
+    }
+    let sum: f64 = curr.iter().zip(prev).map(|(a, b)| (a - b).abs()).sum();
+    sum / curr.len() as f64
+}
+
+/// Apply a variance-adaptive 3x3 median filter to Y-plane data (in-place on a cloned buffer).
 ///
-/// This reduces noise in the luminance data before histogram computation,
-/// improving stability of APL and peak measurements in grainy content.
+/// Filtering strength is modulated per pixel by `variance_map`: tiles at or above
+/// `variance_threshold` get the full median filter, tiles below it are blended proportionally
+/// toward the original (unfiltered) value, so flat regions -- where a flat filter would smear
+/// genuine specular highlights and fine detail -- are left alone while grain-heavy regions are
+/// fully denoised.
 ///
 /// # Arguments
 /// * `y_data` - Y-plane data (10-bit, 2 bytes per pixel)
 /// * `stride` - Row stride in bytes
 /// * `crop_rect` - Active area to denoise
+/// * `variance_map` - Per-tile variance from [`compute_variance_map`]
+/// * `variance_threshold` - Tile variance (in squared 10-bit code units) at or above which
+///   filtering is applied at full strength
 ///
 /// # Returns
 /// Denoised Y-plane data (cloned and filtered)
-fn apply_median3_denoise(y_data: &[u8], stride: usize, crop_rect: &CropRect) -> Vec<u8> {
+fn apply_median3_denoise(
+    y_data: &[u8],
+    stride: usize,
+    crop_rect: &CropRect,
+    variance_map: &VarianceMap,
+    variance_threshold: f64,
+) -> Vec<u8> {
     let mut output = y_data.to_vec();
     let x_start = crop_rect.x as usize;
     let y_start = crop_rect.y as usize;
@@ -30,7 +215,17 @@ fn apply_median3_denoise(y_data: &[u8], stride: usize, crop_rect: &CropRect) ->
     // Process interior pixels (skip borders to avoid edge handling complexity)
     for y in (y_start + 1)..(y_end.saturating_sub(1)) {
         for x in (x_start + 1)..(x_end.saturating_sub(1)) {
+            let blend = if variance_threshold > 0.0 {
+                (variance_map.variance_at(x, y) / variance_threshold).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            if blend <= 0.0 {
+                continue; // Flat region: leave the original value untouched.
+            }
+
             let mut neighbors = Vec::with_capacity(9);
+            let mut center = 0u16;
 
             // Collect 3x3 neighborhood
             for dy in -1..=1 {
@@ -42,6 +237,9 @@ fn apply_median3_denoise(y_data: &[u8], stride: usize, crop_rect: &CropRect) ->
                         let code =
                             u16::from_le_bytes([y_data[offset], y_data[offset + 1]]) & 0x03FF;
                         neighbors.push(code);
+                        if dx == 0 && dy == 0 {
+                            center = code;
+                        }
                     }
                 }
             }
@@ -50,9 +248,12 @@ fn apply_median3_denoise(y_data: &[u8], stride: usize, crop_rect: &CropRect) ->
             if !neighbors.is_empty() {
                 neighbors.sort_unstable();
                 let median = neighbors[neighbors.len() / 2];
+                let blended = (center as f64 * (1.0 - blend) + median as f64 * blend)
+                    .round()
+                    .clamp(0.0, 1023.0) as u16;
                 let out_offset = y * stride + x * 2;
                 if out_offset + 1 < output.len() {
-                    let bytes = median.to_le_bytes();
+                    let bytes = blended.to_le_bytes();
                     output[out_offset] = bytes[0];
                     output[out_offset + 1] = bytes[1];
                 }
@@ -63,6 +264,232 @@ fn apply_median3_denoise(y_data: &[u8], stride: usize, crop_rect: &CropRect) ->
     output
 }
 
+/// Compute an edge-strength histogram from per-pixel Sobel gradient magnitudes over the
+/// active crop area, binned into `bins` buckets and normalized to percentages (sums to
+/// ~100.0). Used as optional corroborating evidence for scene-cut detection: a genuine cut
+/// usually also shifts the edge/texture distribution, which helps distinguish it from a
+/// flash or exposure change that only shifts luminance.
+pub fn compute_edge_histogram(
+    y_data: &[u8],
+    stride: usize,
+    crop_rect: &CropRect,
+    bins: usize,
+) -> Vec<f64> {
+    let bins = bins.max(1);
+    let mut histogram = vec![0.0f64; bins];
+
+    let x_start = crop_rect.x as usize;
+    let y_start = crop_rect.y as usize;
+    let x_end = x_start + crop_rect.width as usize;
+    let y_end = y_start + crop_rect.height as usize;
+
+    let read_code = |x: usize, y: usize| -> f64 {
+        let offset = y * stride + x * 2;
+        if offset + 1 >= y_data.len() {
+            return 0.0;
+        }
+        (u16::from_le_bytes([y_data[offset], y_data[offset + 1]]) & 0x03FF) as f64
+    };
+
+    let mut max_magnitude = 0.0f64;
+    let mut magnitudes: Vec<f64> = Vec::new();
+
+    for y in (y_start + 1)..(y_end.saturating_sub(1)) {
+        for x in (x_start + 1)..(x_end.saturating_sub(1)) {
+            // 3x3 Sobel kernels.
+            let gx = (read_code(x + 1, y - 1)
+                + 2.0 * read_code(x + 1, y)
+                + read_code(x + 1, y + 1))
+                - (read_code(x - 1, y - 1) + 2.0 * read_code(x - 1, y) + read_code(x - 1, y + 1));
+            let gy = (read_code(x - 1, y + 1)
+                + 2.0 * read_code(x, y + 1)
+                + read_code(x + 1, y + 1))
+                - (read_code(x - 1, y - 1) + 2.0 * read_code(x, y - 1) + read_code(x + 1, y - 1));
+            let magnitude = (gx * gx + gy * gy).sqrt();
+            magnitudes.push(magnitude);
+            if magnitude > max_magnitude {
+                max_magnitude = magnitude;
+            }
+        }
+    }
+
+    if magnitudes.is_empty() || max_magnitude <= 0.0 {
+        return histogram;
+    }
+
+    for magnitude in &magnitudes {
+        let bin = ((*magnitude / max_magnitude) * (bins - 1) as f64).floor() as usize;
+        histogram[bin.min(bins - 1)] += 1.0;
+    }
+
+    let total = magnitudes.len() as f64;
+    for v in &mut histogram {
+        *v = (*v / total) * 100.0;
+    }
+
+    histogram
+}
+
+const TEMPORAL_BLOCK_SIZE: usize = 16;
+const TEMPORAL_SEARCH_RADIUS: i32 = 7;
+const TEMPORAL_FILTER_STRENGTH: f64 = 5.0;
+const TEMPORAL_DIFF_SCALE: f64 = 0.0012;
+
+/// Ring buffer of recently decoded (pre-denoise) Y planes, fed to [`apply_temporal_denoise`]
+/// so it can motion-compensate the current frame against its recent neighbors. The pipeline
+/// decodes forward in a single pass with no look-ahead, so the window is backward-only rather
+/// than centered. Call [`TemporalDenoiseRing::reset`] on every confirmed scene cut so a new
+/// shot's frames are never motion-compensated against the previous shot's content.
+pub struct TemporalDenoiseRing {
+    frames: VecDeque<(Vec<u8>, usize)>,
+    capacity: usize,
+}
+
+impl TemporalDenoiseRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity.max(1)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Drop all buffered frames. Call this when a scene cut is confirmed.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+
+    fn push(&mut self, y_data: &[u8], stride: usize) {
+        self.frames.push_back((y_data.to_vec(), stride));
+        if self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+}
+
+/// Motion-compensated temporal denoise of the Y plane over the active crop area.
+///
+/// The crop is partitioned into `TEMPORAL_BLOCK_SIZE`x`TEMPORAL_BLOCK_SIZE` blocks. For each
+/// block and each buffered neighbor frame, a small full-search motion estimation (SAD over a
+/// `+/-TEMPORAL_SEARCH_RADIUS` px window) finds the best-matching block, and that match's mean
+/// absolute difference feeds a vp9-style weight `w = max(0, filter_strength - diff^2 * scale)`.
+/// Per-pixel values are accumulated weighted by match quality (the current frame always
+/// contributes at full weight) and divided by the accumulated weight. This is weaker on
+/// jittery per-frame measurements than a single-frame spatial median, but much better at
+/// suppressing heavy film grain without smearing genuine spatial detail.
+fn apply_temporal_denoise(
+    y_data: &[u8],
+    stride: usize,
+    crop_rect: &CropRect,
+    ring: &TemporalDenoiseRing,
+) -> Vec<u8> {
+    let mut output = y_data.to_vec();
+    let x_start = crop_rect.x as usize;
+    let y_start = crop_rect.y as usize;
+    let x_end = x_start + crop_rect.width as usize;
+    let y_end = y_start + crop_rect.height as usize;
+
+    let read_code = |data: &[u8], stride: usize, x: usize, y: usize| -> i32 {
+        let offset = y * stride + x * 2;
+        if offset + 1 >= data.len() {
+            return 0;
+        }
+        (u16::from_le_bytes([data[offset], data[offset + 1]]) & 0x03FF) as i32
+    };
+
+    let mut by = y_start;
+    while by < y_end {
+        let block_h = TEMPORAL_BLOCK_SIZE.min(y_end - by);
+        let mut bx = x_start;
+        while bx < x_end {
+            let block_w = TEMPORAL_BLOCK_SIZE.min(x_end - bx);
+            let block_pixels = block_w * block_h;
+
+            // The current frame's own block always contributes at full weight.
+            let mut weight_sum = vec![1.0f64; block_pixels];
+            let mut value_sum: Vec<f64> = (0..block_h)
+                .flat_map(|dy| (0..block_w).map(move |dx| (dx, dy)))
+                .map(|(dx, dy)| read_code(y_data, stride, bx + dx, by + dy) as f64)
+                .collect();
+
+            for (neighbor_data, neighbor_stride) in &ring.frames {
+                let mut best_sad = i64::MAX;
+                let mut best_dx = 0i32;
+                let mut best_dy = 0i32;
+                for dy in -TEMPORAL_SEARCH_RADIUS..=TEMPORAL_SEARCH_RADIUS {
+                    for dx in -TEMPORAL_SEARCH_RADIUS..=TEMPORAL_SEARCH_RADIUS {
+                        let nx = bx as i32 + dx;
+                        let ny = by as i32 + dy;
+                        if nx < x_start as i32 || ny < y_start as i32 {
+                            continue;
+                        }
+                        let mut sad: i64 = 0;
+                        for py in 0..block_h {
+                            for px in 0..block_w {
+                                let cur = read_code(y_data, stride, bx + px, by + py);
+                                let reference = read_code(
+                                    neighbor_data,
+                                    *neighbor_stride,
+                                    nx as usize + px,
+                                    ny as usize + py,
+                                );
+                                sad += (cur - reference).abs() as i64;
+                            }
+                        }
+                        if sad < best_sad {
+                            best_sad = sad;
+                            best_dx = dx;
+                            best_dy = dy;
+                        }
+                    }
+                }
+
+                if best_sad == i64::MAX {
+                    continue;
+                }
+                let mean_abs_diff = best_sad as f64 / block_pixels as f64;
+                let weight = (TEMPORAL_FILTER_STRENGTH
+                    - mean_abs_diff * mean_abs_diff * TEMPORAL_DIFF_SCALE)
+                    .max(0.0);
+                if weight <= 0.0 {
+                    continue;
+                }
+
+                let nx = (bx as i32 + best_dx) as usize;
+                let ny = (by as i32 + best_dy) as usize;
+                for dy in 0..block_h {
+                    for dx in 0..block_w {
+                        let idx = dy * block_w + dx;
+                        let reference =
+                            read_code(neighbor_data, *neighbor_stride, nx + dx, ny + dy);
+                        value_sum[idx] += reference as f64 * weight;
+                        weight_sum[idx] += weight;
+                    }
+                }
+            }
+
+            for dy in 0..block_h {
+                for dx in 0..block_w {
+                    let idx = dy * block_w + dx;
+                    let denoised = (value_sum[idx] / weight_sum[idx])
+                        .round()
+                        .clamp(0.0, 1023.0) as u16;
+                    let offset = (by + dy) * stride + (bx + dx) * 2;
+                    if offset + 1 < output.len() {
+                        let bytes = denoised.to_le_bytes();
+                        output[offset] = bytes[0];
+                        output[offset + 1] = bytes[1];
+                    }
+                }
+            }
+
+            bx += TEMPORAL_BLOCK_SIZE;
+        }
+        by += TEMPORAL_BLOCK_SIZE;
+    }
+
+    output
+}
+
 pub fn analyze_native_frame_cropped(
     frame: &frame::Video,
     _width: u32,
@@ -71,16 +498,42 @@ pub fn analyze_native_frame_cropped(
     denoise_mode: &str,
     transfer_function: TransferFunction,
     hlg_peak_nits: f64,
-) -> Result<MadVRFrame> {
+    mut temporal_ring: Option<&mut TemporalDenoiseRing>,
+    median_variance_threshold: f64,
+    activity_weighted_apl: bool,
+    log_histogram_precision: u32,
+    skip_hue_histogram: bool,
+) -> Result<(MadVRFrame, Option<LogHistogram>)> {
     // Y plane data
     let y_plane_data_raw = frame.data(0);
     let y_stride = frame.stride(0);
 
     // Apply denoising if requested
     let y_plane_data_denoised;
+    let mut variance_map: Option<VarianceMap> = None;
     let y_plane_data = if denoise_mode == "median3" {
-        y_plane_data_denoised = apply_median3_denoise(y_plane_data_raw, y_stride, crop_rect);
+        let map = compute_variance_map(y_plane_data_raw, y_stride, crop_rect);
+        y_plane_data_denoised = apply_median3_denoise(
+            y_plane_data_raw,
+            y_stride,
+            crop_rect,
+            &map,
+            median_variance_threshold,
+        );
+        if activity_weighted_apl {
+            variance_map = Some(map);
+        }
         &y_plane_data_denoised[..]
+    } else if denoise_mode == "temporal" || denoise_mode == "mctf" {
+        match temporal_ring.as_deref_mut() {
+            Some(ring) => {
+                y_plane_data_denoised =
+                    apply_temporal_denoise(y_plane_data_raw, y_stride, crop_rect, ring);
+                ring.push(y_plane_data_raw, y_stride);
+                &y_plane_data_denoised[..]
+            }
+            None => y_plane_data_raw,
+        }
     } else {
         y_plane_data_raw
     };
@@ -110,7 +563,7 @@ pub fn analyze_native_frame_cropped(
                 let len = want_len.min(max_len) & !1; // even number of bytes
                 if len >= 2 {
                     let row = &y_plane_data[base..base + len];
-                    for px in row.chunks_exact(2) {
+                    for (i, px) in row.chunks_exact(2).enumerate() {
                         // Read 10-bit limited-range code (0..1023 in 16-bit container)
                         let code10 = u16::from_le_bytes([px[0], px[1]]) & 0x03FF;
 
@@ -136,7 +589,14 @@ pub fn analyze_native_frame_cropped(
                         } else {
                             64 + ((pq - sdr_peak_pq) / hdr_step).floor() as usize
                         };
-                        local_hist[bin.min(255)] += 1.0;
+                        // Activity-weighted APL: down-weight busy/grainy tiles (per
+                        // `variance_map`) so avg_pq isn't skewed by noisy detail. Peak (above)
+                        // stays unweighted -- a genuine specular highlight should still count.
+                        let weight = variance_map
+                            .as_ref()
+                            .map(|map| 1.0 / (1.0 + map.variance_at(x_start + i, y) / 64.0))
+                            .unwrap_or(1.0);
+                        local_hist[bin.min(255)] += weight;
                     }
                 }
             }
@@ -190,17 +650,62 @@ pub fn analyze_native_frame_cropped(
     }
     avg_pq = avg_pq.min(1.0);
 
-    // Compute hue histogram from chroma planes
-    let hue_histogram = compute_hue_histogram(frame, crop_rect);
+    // Compute hue histogram from chroma planes, unless the caller's fast path (see
+    // `--luma-only`) skipped chroma conversion entirely and has nothing for us to read.
+    let hue_histogram = if skip_hue_histogram {
+        vec![0.0; HUE_BINS]
+    } else {
+        compute_hue_histogram(frame, crop_rect)
+    };
 
-    Ok(MadVRFrame {
-        peak_pq_2020: max_pq,
-        avg_pq,
-        lum_histogram: histogram,
-        hue_histogram: Some(hue_histogram),
-        target_nits: None,
-        ..Default::default()
-    })
+    // Optional high-resolution companion histogram for bounded-error P99/P99.9 peak queries
+    // (see `analysis::log_histogram`). A plain sequential second pass, same as
+    // `compute_edge_histogram`'s, rather than folding into the parallel reduction above --
+    // it's opt-in and keeps the nits derivation isolated from the per-thread weighting logic.
+    let log_histogram = if log_histogram_precision > 0 {
+        let mut hist = LogHistogram::new(log_histogram_precision);
+        for y in y_start..y_end {
+            let row_start = y.saturating_mul(y_stride);
+            let base = row_start + x_start.saturating_mul(2);
+            if base >= y_plane_data.len() {
+                continue;
+            }
+            let want_len = (x_end - x_start).saturating_mul(2);
+            let max_len = y_plane_data.len() - base;
+            let len = want_len.min(max_len) & !1;
+            if len < 2 {
+                continue;
+            }
+            for px in y_plane_data[base..base + len].chunks_exact(2) {
+                let code10 = u16::from_le_bytes([px[0], px[1]]) & 0x03FF;
+                let norm = ((code10 as i32 - 64) as f64 / 876.0).clamp(0.0, 1.0);
+                let pq = match transfer_function {
+                    TransferFunction::Hlg => {
+                        let nits = hlg_signal_to_nits(norm, hlg_peak_nits);
+                        nits_to_pq(nits)
+                    }
+                    _ => norm,
+                }
+                .clamp(0.0, 1.0);
+                hist.record(pq_to_nits(pq));
+            }
+        }
+        Some(hist)
+    } else {
+        None
+    };
+
+    Ok((
+        MadVRFrame {
+            peak_pq_2020: max_pq,
+            avg_pq,
+            lum_histogram: histogram,
+            hue_histogram: Some(hue_histogram),
+            target_nits: None,
+            ..Default::default()
+        },
+        log_histogram,
+    ))
 }
 
 /// Analyze a native FFmpeg frame to extract HDR metadata with correct 10-bit PQ mapping.
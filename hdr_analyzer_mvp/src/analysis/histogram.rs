@@ -1,3 +1,4 @@
+use crate::analysis::log_histogram::LogHistogram;
 use crate::crop::CropRect;
 use ffmpeg_next::frame;
 
@@ -143,13 +144,27 @@ pub fn compute_histogram_percentile_pq(histogram: &[f64], percentile: f64) -> f6
 /// * `histogram` - Luminance histogram
 /// * `direct_max_pq` - Peak PQ from direct frame analysis
 /// * `peak_source` - Method to use: "max", "histogram99", or "histogram999"
+/// * `log_histogram` - Optional higher-resolution [`LogHistogram`] (typically aggregated over
+///   a whole scene). When present and non-empty, takes priority over the coarse 256-bin
+///   `histogram` for the "histogram99"/"histogram999" sources, since its log-bucketed
+///   resolution makes P99.9 peak detection far less noisy in the HDR highlight region.
 ///
 /// # Returns
 /// Peak PQ value selected by the specified method
-pub fn select_peak_pq(histogram: &[f64], direct_max_pq: f64, peak_source: &str) -> f64 {
+pub fn select_peak_pq(
+    histogram: &[f64],
+    direct_max_pq: f64,
+    peak_source: &str,
+    log_histogram: Option<&LogHistogram>,
+) -> f64 {
+    let log_histogram = log_histogram.filter(|h| !h.is_empty());
     match peak_source {
-        "histogram99" => compute_histogram_percentile_pq(histogram, 99.0),
-        "histogram999" => compute_histogram_percentile_pq(histogram, 99.9),
+        "histogram99" => log_histogram
+            .map(|h| nits_to_pq(h.percentile_nits(99.0)))
+            .unwrap_or_else(|| compute_histogram_percentile_pq(histogram, 99.0)),
+        "histogram999" => log_histogram
+            .map(|h| nits_to_pq(h.percentile_nits(99.9)))
+            .unwrap_or_else(|| compute_histogram_percentile_pq(histogram, 99.9)),
         _ => direct_max_pq, // "max" or unknown defaults to direct max
     }
 }
@@ -242,8 +257,12 @@ pub fn apply_histogram_temporal_median(histogram: &mut [f64], history: &[Vec<f64
 ///
 /// # Returns
 /// Vector of 31 f64 values representing percentage distribution across hue bins
+/// Number of bins `compute_hue_histogram` produces; `pub(crate)` so callers that skip the hue
+/// histogram entirely (e.g. the `--luma-only` fast path) can still emit a same-length
+/// all-zero placeholder.
+pub(crate) const HUE_BINS: usize = 31;
+
 pub fn compute_hue_histogram(frame: &frame::Video, crop_rect: &CropRect) -> Vec<f64> {
-    const HUE_BINS: usize = 31;
     let mut hue_histogram = vec![0.0; HUE_BINS];
 
     // U and V planes (4:2:0 subsampled, so dimensions are halved)
@@ -314,6 +333,148 @@ pub fn compute_hue_histogram(frame: &frame::Video, crop_rect: &CropRect) -> Vec<
     hue_histogram
 }
 
+/// Number of bins `compute_hue_histogram` produces; `pub(crate)` so callers that skip the hue
+/// histogram entirely (e.g. the `--luma-only` fast path) can still emit a same-length
+/// all-zero placeholder.
+pub(crate) const HUE_BINS: usize = 31;
+
+/// Compute a 2D-quantized Cb/Cr chroma distribution, flattened row-major into a single
+/// `bins_per_axis * bins_per_axis` histogram and normalized to percentages (sums to ~100.0).
+/// Unlike [`compute_hue_histogram`], this keeps near-neutral (low-saturation) pixels rather
+/// than discarding them, since `--hist-scenecut` wants the full chroma distribution -- a cut
+/// to or from a desaturated shot is itself a meaningful shift.
+pub fn compute_chroma_histogram(
+    frame: &frame::Video,
+    crop_rect: &CropRect,
+    bins_per_axis: usize,
+) -> Vec<f64> {
+    let bins_per_axis = bins_per_axis.max(1);
+    let mut histogram = vec![0.0; bins_per_axis * bins_per_axis];
+
+    let u_plane = frame.data(1);
+    let v_plane = frame.data(2);
+    let u_stride = frame.stride(1);
+    let v_stride = frame.stride(2);
+
+    let cx_start = (crop_rect.x / 2) as usize;
+    let cy_start = (crop_rect.y / 2) as usize;
+    let cx_end = cx_start + (crop_rect.width / 2) as usize;
+    let cy_end = cy_start + (crop_rect.height / 2) as usize;
+
+    let mut total_pixels = 0u64;
+
+    for cy in cy_start..cy_end {
+        let u_row_base = cy * u_stride + cx_start * 2;
+        let v_row_base = cy * v_stride + cx_start * 2;
+
+        if u_row_base >= u_plane.len() || v_row_base >= v_plane.len() {
+            continue;
+        }
+
+        let u_row_end = (u_row_base + (cx_end - cx_start) * 2).min(u_plane.len());
+        let v_row_end = (v_row_base + (cx_end - cx_start) * 2).min(v_plane.len());
+
+        let u_row = &u_plane[u_row_base..u_row_end];
+        let v_row = &v_plane[v_row_base..v_row_end];
+
+        for (u_px, v_px) in u_row.chunks_exact(2).zip(v_row.chunks_exact(2)) {
+            let u_code = (u16::from_le_bytes([u_px[0], u_px[1]]) & 0x03FF) as f64;
+            let v_code = (u16::from_le_bytes([v_px[0], v_px[1]]) & 0x03FF) as f64;
+
+            // Normalize 10-bit chroma (nominal 64..960) to [0, 1] before quantizing.
+            let u_norm = ((u_code - 64.0) / 896.0).clamp(0.0, 1.0);
+            let v_norm = ((v_code - 64.0) / 896.0).clamp(0.0, 1.0);
+
+            let u_bin = ((u_norm * bins_per_axis as f64) as usize).min(bins_per_axis - 1);
+            let v_bin = ((v_norm * bins_per_axis as f64) as usize).min(bins_per_axis - 1);
+
+            histogram[v_bin * bins_per_axis + u_bin] += 1.0;
+            total_pixels += 1;
+        }
+    }
+
+    if total_pixels > 0 {
+        let total = total_pixels as f64;
+        for bin in &mut histogram {
+            *bin = (*bin / total) * 100.0;
+        }
+    }
+
+    histogram
+}
+
+/// Collect normalized `(Y, Cb, Cr)` triples from the active crop for palette extraction
+/// (see [`crate::analysis::palette`]). Reuses the chroma-plane reading loop from
+/// [`compute_chroma_histogram`], additionally reading the co-located luma sample at each
+/// chroma position (luma is full-resolution, chroma is 2x2 subsampled, so each chroma sample
+/// pairs with the luma sample at `(cx*2, cy*2)`). `stride` skips `stride - 1` chroma samples
+/// between reads in both axes to bound the sample count for whole-scene aggregation; 1 samples
+/// every chroma pixel.
+pub fn sample_ycbcr(
+    frame: &frame::Video,
+    crop_rect: &CropRect,
+    stride: usize,
+) -> Vec<(f64, f64, f64)> {
+    let stride = stride.max(1);
+    let mut samples = Vec::new();
+
+    let y_plane = frame.data(0);
+    let y_stride = frame.stride(0);
+    let u_plane = frame.data(1);
+    let v_plane = frame.data(2);
+    let u_stride = frame.stride(1);
+    let v_stride = frame.stride(2);
+
+    let cx_start = (crop_rect.x / 2) as usize;
+    let cy_start = (crop_rect.y / 2) as usize;
+    let cx_end = cx_start + (crop_rect.width / 2) as usize;
+    let cy_end = cy_start + (crop_rect.height / 2) as usize;
+
+    let mut cy = cy_start;
+    while cy < cy_end {
+        let u_row_base = cy * u_stride + cx_start * 2;
+        let v_row_base = cy * v_stride + cx_start * 2;
+        let y_row = cy * 2;
+
+        if u_row_base >= u_plane.len() || v_row_base >= v_plane.len() {
+            cy += stride;
+            continue;
+        }
+
+        let mut cx = cx_start;
+        while cx < cx_end {
+            let u_offset = u_row_base + (cx - cx_start) * 2;
+            let v_offset = v_row_base + (cx - cx_start) * 2;
+            let y_offset = y_row * y_stride + cx * 2 * 2;
+
+            if u_offset + 1 >= u_plane.len()
+                || v_offset + 1 >= v_plane.len()
+                || y_offset + 1 >= y_plane.len()
+            {
+                cx += stride;
+                continue;
+            }
+
+            let u_code =
+                (u16::from_le_bytes([u_plane[u_offset], u_plane[u_offset + 1]]) & 0x03FF) as f64;
+            let v_code =
+                (u16::from_le_bytes([v_plane[v_offset], v_plane[v_offset + 1]]) & 0x03FF) as f64;
+            let y_code =
+                (u16::from_le_bytes([y_plane[y_offset], y_plane[y_offset + 1]]) & 0x03FF) as f64;
+
+            let y_norm = ((y_code - 64.0) / 876.0).clamp(0.0, 1.0);
+            let cb_norm = ((u_code - 64.0) / 896.0).clamp(0.0, 1.0);
+            let cr_norm = ((v_code - 64.0) / 896.0).clamp(0.0, 1.0);
+
+            samples.push((y_norm, cb_norm, cr_norm));
+            cx += stride;
+        }
+        cy += stride;
+    }
+
+    samples
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,193 @@
+//! HdrHistogram-style logarithmic-bucket histogram giving constant *relative* error across a
+//! wide dynamic range. The 256-bin madVR-compatible `lum_histogram` used elsewhere in this
+//! crate only spends ~192 of its bins on the 100-10000 nits HDR highlight region, so bin width
+//! near the top is hundreds of nits -- too coarse for a stable P99.9 peak estimate. This module
+//! is an optional, higher-resolution companion for that one query; the 256-bin histogram is
+//! untouched and still drives everything else (madVR compatibility, EMA/temporal smoothing,
+//! avg_pq).
+
+use crate::analysis::histogram::pq_to_nits;
+
+/// Lowest nits value tracked individually; anything at or below this falls into the first
+/// sub-bucket. HDR highlight percentiles don't need sub-nit precision.
+const LOWEST_TRACKABLE_NITS: f64 = 1.0;
+/// Highest nits value tracked; ST.2084 tops out at 10000.
+const HIGHEST_TRACKABLE_NITS: f64 = 10000.0;
+
+/// Sub-bucket bit width giving `1 / 2^precision` relative error per sample, comfortably under
+/// the 0.5% target (`1 / 2^8 = 0.39%`).
+pub const DEFAULT_PRECISION: u32 = 8;
+
+/// A logarithmic-bucket histogram over the nits range `[LOWEST_TRACKABLE_NITS,
+/// HIGHEST_TRACKABLE_NITS]`. Each power-of-two "magnitude" band is subdivided into
+/// `2^precision` equal-width linear sub-buckets, so every recorded value maps to a bucket no
+/// wider than `1 / 2^precision` of its own magnitude -- the same bounded-relative-error
+/// property as a real HdrHistogram. Recording a value is O(1) integer math (a `log2().floor()`
+/// plus a linear offset); percentile queries walk buckets from the bottom accumulating counts.
+pub struct LogHistogram {
+    precision: u32,
+    sub_buckets: usize,
+    num_magnitudes: usize,
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl LogHistogram {
+    pub fn new(precision: u32) -> Self {
+        let sub_buckets = 1usize << precision;
+        let num_magnitudes = (HIGHEST_TRACKABLE_NITS / LOWEST_TRACKABLE_NITS)
+            .log2()
+            .ceil() as usize
+            + 1;
+        Self {
+            precision,
+            sub_buckets,
+            num_magnitudes,
+            counts: vec![0u64; sub_buckets * num_magnitudes],
+            total: 0,
+        }
+    }
+
+    fn magnitude_band(&self, magnitude: usize) -> (f64, f64) {
+        let band_start = LOWEST_TRACKABLE_NITS * 2f64.powi(magnitude as i32);
+        (band_start, band_start * 2.0)
+    }
+
+    fn bucket_index(&self, nits: f64) -> usize {
+        let v = nits.clamp(LOWEST_TRACKABLE_NITS, HIGHEST_TRACKABLE_NITS);
+        let magnitude = ((v / LOWEST_TRACKABLE_NITS).log2().floor().max(0.0) as usize)
+            .min(self.num_magnitudes - 1);
+        let (band_start, band_end) = self.magnitude_band(magnitude);
+        let frac = ((v - band_start) / (band_end - band_start)).clamp(0.0, 1.0);
+        let sub_bucket = ((frac * self.sub_buckets as f64) as usize).min(self.sub_buckets - 1);
+        magnitude * self.sub_buckets + sub_bucket
+    }
+
+    /// Representative (sub-bucket-midpoint) nits value for a flat bucket index.
+    fn bucket_value(&self, index: usize) -> f64 {
+        let magnitude = index / self.sub_buckets;
+        let sub_bucket = index % self.sub_buckets;
+        let (band_start, band_end) = self.magnitude_band(magnitude);
+        let sub_bucket_width = (band_end - band_start) / self.sub_buckets as f64;
+        band_start + (sub_bucket as f64 + 0.5) * sub_bucket_width
+    }
+
+    /// O(1) record of a single nits sample.
+    pub fn record(&mut self, nits: f64) {
+        self.record_weighted(nits, 1);
+    }
+
+    /// O(1) record of a nits sample with an integer weight, for feeding already-aggregated
+    /// (e.g. percentage-bucketed) data without a per-sample loop.
+    pub fn record_weighted(&mut self, nits: f64, weight: u64) {
+        if nits <= 0.0 || weight == 0 {
+            return;
+        }
+        let index = self.bucket_index(nits);
+        self.counts[index] += weight;
+        self.total += weight;
+    }
+
+    /// Merge another histogram's counts into this one. Both histograms must share the same
+    /// precision; mismatched histograms are silently ignored rather than panicking, since
+    /// merging is always driven by this crate's own scene-aggregation code with a single
+    /// configured precision.
+    pub fn merge(&mut self, other: &LogHistogram) {
+        if self.precision != other.precision || self.counts.len() != other.counts.len() {
+            return;
+        }
+        for (a, b) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *a += *b;
+        }
+        self.total += other.total;
+    }
+
+    /// Nits value below which `percentile` percent of recorded samples fall (e.g. 99.9 for
+    /// P99.9 peak detection).
+    pub fn percentile_nits(&self, percentile: f64) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let target = (self.total as f64) * (percentile.clamp(0.0, 100.0) / 100.0);
+        let mut cumulative = 0u64;
+        for (index, &count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative as f64 >= target {
+                return self.bucket_value(index);
+            }
+        }
+        HIGHEST_TRACKABLE_NITS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+}
+
+/// Record a frame's 256-bin PQ `lum_histogram` into a [`LogHistogram`] by converting each
+/// bin's representative PQ value to nits and weighting by the bin's percentage. Used to seed a
+/// log histogram from frames where only the coarse madVR histogram was captured (e.g. cached
+/// sample-rate-skipped frames); prefer per-pixel [`LogHistogram::record`] when raw samples are
+/// available, since reconstructing from the 256-bin histogram re-introduces its coarse binning.
+pub fn record_pq_histogram(log_hist: &mut LogHistogram, pq_histogram: &[f64]) {
+    const SAMPLE_SCALE: f64 = 100.0; // percentage points -> integer sample weight
+    let last_bin = pq_histogram.len().saturating_sub(1).max(1) as f64;
+    for (bin_index, &percent) in pq_histogram.iter().enumerate() {
+        if percent <= 0.0 {
+            continue;
+        }
+        let pq = (bin_index as f64) / last_bin;
+        let nits = pq_to_nits(pq);
+        let weight = (percent * SAMPLE_SCALE).round() as u64;
+        log_hist.record_weighted(nits, weight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_histogram_percentile_round_trip() {
+        let mut hist = LogHistogram::new(DEFAULT_PRECISION);
+        for _ in 0..990 {
+            hist.record(100.0);
+        }
+        for _ in 0..10 {
+            hist.record(4000.0);
+        }
+        let p99 = hist.percentile_nits(99.0);
+        assert!(
+            (p99 - 100.0).abs() / 100.0 < 0.01,
+            "expected ~100 nits, got {}",
+            p99
+        );
+        let p999 = hist.percentile_nits(99.9);
+        assert!(
+            (p999 - 4000.0).abs() / 4000.0 < 0.01,
+            "expected ~4000 nits, got {}",
+            p999
+        );
+    }
+
+    #[test]
+    fn test_log_histogram_merge_combines_counts() {
+        let mut a = LogHistogram::new(DEFAULT_PRECISION);
+        let mut b = LogHistogram::new(DEFAULT_PRECISION);
+        for _ in 0..50 {
+            a.record(200.0);
+        }
+        for _ in 0..50 {
+            b.record(200.0);
+        }
+        a.merge(&b);
+        assert!((a.percentile_nits(50.0) - 200.0).abs() / 200.0 < 0.01);
+    }
+
+    #[test]
+    fn test_log_histogram_empty_is_zero() {
+        let hist = LogHistogram::new(DEFAULT_PRECISION);
+        assert!(hist.is_empty());
+        assert_eq!(hist.percentile_nits(99.0), 0.0);
+    }
+}
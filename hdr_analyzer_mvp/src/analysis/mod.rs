@@ -0,0 +1,6 @@
+pub mod frame;
+pub mod histogram;
+pub mod hlg;
+pub mod log_histogram;
+pub mod palette;
+pub mod scene;
@@ -0,0 +1,182 @@
+//! Per-scene dominant-color palette via median-cut quantization. `compute_hue_histogram`
+//! reduces color to a 31-bin hue angle, discarding the saturation/lightness structure that
+//! downstream Dolby Vision L4 color trims care about; this module works from the raw
+//! `(Y, Cb, Cr)` samples collected by [`crate::analysis::histogram::sample_ycbcr`] instead, and
+//! produces a small set of representative colors per scene rather than a single-axis
+//! distribution.
+
+/// One dominant-color palette entry: the pixel-count-weighted average color of a median-cut
+/// box, in the same normalized `[0, 1]` Y/Cb/Cr units as the input samples.
+#[derive(Debug, Clone, Copy)]
+pub struct PaletteColor {
+    pub y: f64,
+    pub cb: f64,
+    pub cr: f64,
+    /// Fraction of the scene's sampled pixels this color represents (0.0-1.0).
+    pub weight: f64,
+}
+
+impl PaletteColor {
+    /// Approximate BT.2020 non-constant-luminance RGB, scaled to `peak_nits` so callers can
+    /// present nits-scaled colors (e.g. "this palette entry peaks around 400 nits").
+    pub fn to_rgb_nits(&self, peak_nits: f64) -> (f64, f64, f64) {
+        let cb = self.cb - 0.5;
+        let cr = self.cr - 0.5;
+        let r = self.y + 1.4746 * cr;
+        let g = self.y - 0.1646 * cb - 0.5714 * cr;
+        let b = self.y + 1.8814 * cb;
+        (
+            r.clamp(0.0, 1.0) * peak_nits,
+            g.clamp(0.0, 1.0) * peak_nits,
+            b.clamp(0.0, 1.0) * peak_nits,
+        )
+    }
+}
+
+/// One axis-aligned box of samples in median-cut's working set.
+struct ColorBox {
+    samples: Vec<(f64, f64, f64)>,
+}
+
+impl ColorBox {
+    /// Axis (0=Y, 1=Cb, 2=Cr) and extent of this box's widest channel.
+    fn widest_axis(&self) -> (usize, f64) {
+        let mut best_axis = 0;
+        let mut best_extent = -1.0;
+        for axis in 0..3 {
+            let (min, max) = self
+                .samples
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(mn, mx), s| {
+                    let v = match axis {
+                        0 => s.0,
+                        1 => s.1,
+                        _ => s.2,
+                    };
+                    (mn.min(v), mx.max(v))
+                });
+            let extent = max - min;
+            if extent > best_extent {
+                best_extent = extent;
+                best_axis = axis;
+            }
+        }
+        (best_axis, best_extent)
+    }
+
+    fn average_color(&self) -> (f64, f64, f64) {
+        let n = self.samples.len() as f64;
+        let (sy, scb, scr) = self
+            .samples
+            .iter()
+            .fold((0.0, 0.0, 0.0), |(ay, acb, acr), s| {
+                (ay + s.0, acb + s.1, acr + s.2)
+            });
+        (sy / n, scb / n, scr / n)
+    }
+}
+
+/// Median-cut quantization: repeatedly splits the box with the largest extent along any
+/// channel at its median along that channel, until `max_colors` boxes exist (or no box can be
+/// split further). Each resulting box's pixel-count-weighted average color becomes one
+/// [`PaletteColor`]. Returns fewer than `max_colors` entries if there are too few distinct
+/// samples to split that far.
+pub fn extract_palette(samples: &[(f64, f64, f64)], max_colors: usize) -> Vec<PaletteColor> {
+    if samples.is_empty() || max_colors == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![ColorBox {
+        samples: samples.to_vec(),
+    }];
+
+    while boxes.len() < max_colors {
+        let splittable = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.samples.len() >= 2)
+            .map(|(i, b)| {
+                let (axis, extent) = b.widest_axis();
+                (i, axis, extent)
+            })
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+        let Some((index, axis, extent)) = splittable else {
+            break;
+        };
+        if extent <= 0.0 {
+            break;
+        }
+
+        let mut box_to_split = boxes.remove(index);
+        box_to_split.samples.sort_by(|a, b| {
+            let av = match axis {
+                0 => a.0,
+                1 => a.1,
+                _ => a.2,
+            };
+            let bv = match axis {
+                0 => b.0,
+                1 => b.1,
+                _ => b.2,
+            };
+            av.partial_cmp(&bv).unwrap()
+        });
+        let mid = box_to_split.samples.len() / 2;
+        let upper_half = box_to_split.samples.split_off(mid);
+        boxes.push(box_to_split);
+        boxes.push(ColorBox {
+            samples: upper_half,
+        });
+    }
+
+    let total_samples = samples.len() as f64;
+    boxes
+        .into_iter()
+        .map(|b| {
+            let weight = b.samples.len() as f64 / total_samples;
+            let (y, cb, cr) = b.average_color();
+            PaletteColor { y, cb, cr, weight }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_palette_separates_two_clusters() {
+        let mut samples = Vec::new();
+        for _ in 0..50 {
+            samples.push((0.1, 0.2, 0.2));
+        }
+        for _ in 0..50 {
+            samples.push((0.9, 0.8, 0.8));
+        }
+        let palette = extract_palette(&samples, 2);
+        assert_eq!(palette.len(), 2);
+        let (dark, bright) = if palette[0].y < palette[1].y {
+            (palette[0], palette[1])
+        } else {
+            (palette[1], palette[0])
+        };
+        assert!((dark.y - 0.1).abs() < 1e-9);
+        assert!((bright.y - 0.9).abs() < 1e-9);
+        assert!((dark.weight - 0.5).abs() < 1e-9);
+        assert!((bright.weight - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_extract_palette_caps_at_distinct_sample_count() {
+        let samples = vec![(0.5, 0.5, 0.5); 10];
+        let palette = extract_palette(&samples, 8);
+        assert_eq!(palette.len(), 1);
+        assert!((palette[0].weight - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_extract_palette_empty_input() {
+        assert!(extract_palette(&[], 8).is_empty());
+    }
+}
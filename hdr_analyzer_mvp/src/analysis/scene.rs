@@ -1,4 +1,203 @@
-use madvr_parse::MadVRScene;
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use madvr_parse::{MadVRFrame, MadVRScene};
+
+use crate::analysis::histogram::pq_to_nits;
+
+/// Scene-detection strategy: how a candidate cut's acceptance threshold is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneDetectMode {
+    /// Compare the smoothed histogram difference against a single fixed threshold (`--scene-threshold`).
+    Fixed,
+    /// Track a rolling mean/stddev of the smoothed difference and accept a cut only when it
+    /// exceeds `mean + k*stddev`, adapting to content whose baseline inter-frame difference varies.
+    Adaptive,
+}
+
+impl SceneDetectMode {
+    pub fn get_possible_modes() -> &'static [&'static str] {
+        &["fixed", "adaptive"]
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "fixed" => Ok(Self::Fixed),
+            "adaptive" => Ok(Self::Adaptive),
+            _ => Err(anyhow::anyhow!(
+                "Invalid scene detect mode: '{}'. Valid options: {}",
+                name,
+                Self::get_possible_modes().join(", ")
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SceneDetectMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneDetectMode::Fixed => write!(f, "fixed"),
+            SceneDetectMode::Adaptive => write!(f, "adaptive"),
+        }
+    }
+}
+
+/// Rolling-statistics cut detector used by `SceneDetectMode::Adaptive`.
+///
+/// Maintains a window of recent smoothed histogram differences and flags a candidate cut
+/// when the current value exceeds `mean + k*stddev` of that window, so the acceptance
+/// threshold adapts to the clip's own baseline noise instead of one fixed global value.
+pub struct AdaptiveSceneDetector {
+    window: VecDeque<f64>,
+    window_size: usize,
+}
+
+impl AdaptiveSceneDetector {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(window_size.max(1)),
+            window_size: window_size.max(1),
+        }
+    }
+
+    /// Evaluate `diff` against the current rolling mean/stddev, then record it into the window.
+    pub fn evaluate(&mut self, diff: f64, k: f64) -> bool {
+        let is_candidate = if self.window.len() >= 2 {
+            let mean = self.window.iter().sum::<f64>() / self.window.len() as f64;
+            let variance = self.window.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+                / self.window.len() as f64;
+            diff > mean + k * variance.sqrt()
+        } else {
+            false
+        };
+
+        self.window.push_back(diff);
+        if self.window.len() > self.window_size {
+            self.window.pop_front();
+        }
+
+        is_candidate
+    }
+
+    /// Clear the rolling window, e.g. right after a cut is emitted, so the adaptive baseline
+    /// isn't inflated by the cut's own large diff value and doesn't suppress a closely
+    /// following real cut.
+    pub fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// A scene-cut candidate awaiting lookahead confirmation.
+struct PendingCut {
+    frame: u32,
+    hist_before: Vec<f64>,
+    frames_since: usize,
+    revert_floor: f64,
+}
+
+/// Confirms scene-cut candidates with a lookahead window to suppress single-frame flashes
+/// (camera flash, explosion, subtitle flash) that would otherwise fragment a scene.
+///
+/// When a candidate cut fires at frame `f`, the confirmation is held through frame
+/// `f + confirm_frames - 1` (the candidate frame itself counts as the window's first frame).
+/// If the content reverts to look like frame `f-1` again within that window (histogram
+/// difference from `f-1` drops back below `flash_revert_tolerance` of its initial jump),
+/// the candidate is a flash and is dropped; otherwise it is confirmed.
+pub struct FlashFadeFilter {
+    confirm_frames: usize,
+    flash_revert_tolerance: f64,
+    hist_before: Option<Vec<f64>>,
+    pending: Option<PendingCut>,
+}
+
+impl FlashFadeFilter {
+    pub fn new(confirm_frames: usize, flash_revert_tolerance: f64) -> Self {
+        Self {
+            confirm_frames: confirm_frames.max(1),
+            flash_revert_tolerance,
+            hist_before: None,
+            pending: None,
+        }
+    }
+
+    /// Feed the next analyzed frame in order. `candidate` is true when the raw scene-cut
+    /// check (fixed threshold or adaptive) fired for this frame. Returns the frame index of
+    /// a cut that should now be committed, once its confirmation window has elapsed.
+    pub fn observe(&mut self, frame: u32, candidate: bool, histogram: &[f64]) -> Option<u32> {
+        let mut confirmed = None;
+
+        if let Some(pending) = &mut self.pending {
+            pending.frames_since += 1;
+            let diff_from_before = calculate_histogram_difference(&pending.hist_before, histogram);
+            if diff_from_before < pending.revert_floor {
+                // Content looks like it did before the candidate cut again: a flash, not a cut.
+                self.pending = None;
+            } else if pending.frames_since >= self.confirm_frames {
+                confirmed = Some(pending.frame);
+                self.pending = None;
+            }
+        }
+
+        if candidate && self.pending.is_none() {
+            if let Some(hist_before) = &self.hist_before {
+                let initial_jump = calculate_histogram_difference(hist_before, histogram);
+                self.pending = Some(PendingCut {
+                    frame,
+                    hist_before: hist_before.clone(),
+                    // The candidate frame itself counts as the first frame of the confirmation
+                    // window, so this starts at 1 rather than 0.
+                    frames_since: 1,
+                    revert_floor: initial_jump * self.flash_revert_tolerance,
+                });
+            }
+        }
+
+        self.hist_before = Some(histogram.to_vec());
+        confirmed
+    }
+}
+
+/// Detects a gradual fade: a run of frames with positive, monotonically non-decreasing,
+/// below-cut-threshold difference drift. Once the run exceeds `window` frames, the span is
+/// treated as a single soft scene boundary instead of a string of spurious hard cuts.
+pub struct FadeDetector {
+    window: u32,
+    threshold: f64,
+    run_len: u32,
+    last_diff: f64,
+}
+
+impl FadeDetector {
+    pub fn new(window: u32, threshold: f64) -> Self {
+        Self {
+            window: window.max(1),
+            threshold,
+            run_len: 0,
+            last_diff: 0.0,
+        }
+    }
+
+    /// Feed the next frame's (smoothed) difference; returns the frame index marking the
+    /// fade's soft boundary once a sustained drifting run exceeds `window` frames.
+    pub fn observe(&mut self, frame: u32, diff: f64) -> Option<u32> {
+        let drifting = diff > 0.0 && diff < self.threshold && diff >= self.last_diff;
+        self.last_diff = diff;
+
+        if drifting {
+            self.run_len += 1;
+            // The boundary is the frame *after* a full `window`-frame drifting run, i.e. the
+            // first frame of the now-settled post-fade content, so this fires one frame past
+            // `run_len == window` rather than on it.
+            if self.run_len > self.window {
+                self.run_len = 0;
+                return Some(frame);
+            }
+        } else {
+            self.run_len = 0;
+        }
+        None
+    }
+}
 
 /// Calculate histogram difference using Sum of Absolute Differences for scene detection.
 ///
@@ -82,10 +281,258 @@ pub fn convert_scene_cuts_to_scenes(
     scenes
 }
 
+/// Downscale size, cut threshold, and minimum scene length for
+/// [`crate::pipeline::prescan_scenes_grid`]'s standalone grid-luma pre-pass.
+#[derive(Clone, Copy, Debug)]
+pub struct GridPrescanConfig {
+    /// Luma-grid thumbnail height (width follows the source aspect ratio). See
+    /// [`crate::analysis::frame::downsample_luma_thumbnail`].
+    pub grid_size: u32,
+    /// MAD threshold (on the `[0, 1]` scale [`crate::analysis::frame::luma_mad`] returns) a
+    /// grid diff must exceed to flag a cut.
+    pub threshold: f64,
+    /// Minimum scene length in frames; gates flicker the same way [`cut_allowed`] does for
+    /// every other detector in this module.
+    pub min_scene_len: u32,
+}
+
+impl Default for GridPrescanConfig {
+    fn default() -> Self {
+        Self {
+            grid_size: 32,
+            threshold: 0.06,
+            min_scene_len: 24,
+        }
+    }
+}
+
+/// Scan a sequence of consecutive-frame luma-grid MAD values (each `diffs[i]` the MAD between
+/// frame `i` and frame `i - 1`'s downscaled grid; `diffs[0]` is ignored, since frame 0 always
+/// starts the first scene) for cut candidates, gated by `min_scene_len` via [`cut_allowed`] --
+/// the same acceptance rule [`detect_scene_boundaries_sad`] applies to histogram-based cuts.
+pub fn detect_scene_boundaries_from_diffs(
+    diffs: &[f64],
+    threshold: f64,
+    min_scene_len: u32,
+) -> Vec<u32> {
+    let mut cuts = Vec::new();
+    let mut last_cut: Option<u32> = None;
+    for (i, &diff) in diffs.iter().enumerate().skip(1) {
+        let frame = i as u32;
+        if diff > threshold && cut_allowed(last_cut, frame, min_scene_len) {
+            cuts.push(frame);
+            last_cut = Some(frame);
+        }
+    }
+    cuts
+}
+
+/// A scene boundary found by [`detect_scene_boundaries_sad`]: the first frame of a new scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SceneBoundary {
+    pub frame: u32,
+}
+
+/// Per-scene rollup of stable (non-flickering) HDR metadata, aggregated across the frames of
+/// a single scene rather than read per-frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneRollup {
+    pub start: u32,
+    pub end: u32,
+    /// Scene peak luminance in nits (MaxCLL-equivalent: brightest single frame in the scene).
+    pub max_cll_nits: u32,
+    /// Scene average luminance in nits (MaxFALL-equivalent: mean of per-frame averages).
+    pub max_fall_nits: u32,
+    /// The in-scene frame's histogram whose peak_pq is closest to the scene's own average
+    /// peak_pq -- a representative histogram rather than any single frame's flickering one.
+    pub representative_histogram: Vec<f64>,
+}
+
+/// Sum of absolute per-bin differences between two percent-normalized histograms (true SAD,
+/// as distinct from the chi-squared distance `calculate_histogram_difference` computes).
+pub fn histogram_sad(hist1: &[f64], hist2: &[f64]) -> f64 {
+    let len = hist1.len().min(hist2.len());
+    (0..len).map(|i| (hist1[i] - hist2[i]).abs()).sum()
+}
+
+/// Combined scene-cut signal for `--hist-scenecut` mode: the larger of the edge-histogram SAD
+/// and the chroma-histogram SAD, each normalized to `[0, 1]` by dividing by `2 * total` (two
+/// histograms sharing no mass at all have a SAD of `2 * total`, since `total` is the
+/// percentage-normalized sum of either one). Taking the max rather than requiring both means a
+/// cut signalled by only one of texture or color shifting still gets through.
+pub fn hist_scenecut_diff(
+    edge_hist: &[f64],
+    prev_edge_hist: &[f64],
+    chroma_hist: &[f64],
+    prev_chroma_hist: &[f64],
+) -> f64 {
+    let normalized_sad = |hist: &[f64], prev: &[f64]| -> f64 {
+        let total: f64 = hist.iter().sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        histogram_sad(hist, prev) / (2.0 * total)
+    };
+    normalized_sad(edge_hist, prev_edge_hist).max(normalized_sad(chroma_hist, prev_chroma_hist))
+}
+
+/// Reference variance (squared 10-bit code units) above which a frame is considered normally
+/// textured, used to scale [`variance_gate_confirms`]'s thresholds.
+const VARIANCE_GATE_REFERENCE_VARIANCE: f64 = 400.0;
+/// Floor for `variance_prev` in the scaling below, so a perfectly flat previous frame (variance
+/// 0) doesn't produce an infinite/undefined flatness multiplier.
+const VARIANCE_GATE_MIN_VARIANCE: f64 = 1.0;
+/// Full 10-bit code range, used to convert `sensitivity` into an absolute mean-shift threshold.
+const VARIANCE_GATE_CODE_RANGE: f64 = 1023.0;
+
+/// Corroborates a histogram-distance scene-cut candidate against the frame-to-frame change in
+/// Y-plane mean/variance (from [`crate::analysis::frame::compute_intensity_stats`]). A candidate
+/// is only confirmed if the mean or variance shift exceeds a threshold scaled by `sensitivity`
+/// and by how flat the previous frame was: low-variance (flat) content needs a proportionally
+/// larger change to confirm a cut, since grain/fade jitter on flat frames produces large
+/// *relative* swings that histogram SAD alone can mistake for a real cut.
+pub fn variance_gate_confirms(
+    mean_cur: f64,
+    variance_cur: f64,
+    mean_prev: f64,
+    variance_prev: f64,
+    sensitivity: f64,
+) -> bool {
+    let flatness = (VARIANCE_GATE_REFERENCE_VARIANCE
+        / variance_prev.max(VARIANCE_GATE_MIN_VARIANCE))
+    .sqrt()
+    .max(1.0);
+    let mean_threshold = sensitivity * flatness * VARIANCE_GATE_CODE_RANGE;
+    let variance_threshold = sensitivity * flatness * variance_prev.max(VARIANCE_GATE_MIN_VARIANCE);
+
+    (mean_cur - mean_prev).abs() > mean_threshold
+        || (variance_cur - variance_prev).abs() > variance_threshold
+}
+
+/// Detects scene boundaries over an already-analyzed stream of [`MadVRFrame`]s using
+/// histogram SAD against an adaptive threshold (a multiple of the running average of the
+/// last 16 SAD values), corroborated by a significant mean-luminance shift (`avg_pq`) and/or
+/// a significant edge-histogram SAD when edge histograms are supplied. Requiring corroboration
+/// suppresses false positives from flashes/fades that shift one signal but not the others.
+///
+/// `sad_multiplier` is the running-average multiple a candidate's SAD must exceed (1.5-2.0
+/// per the design). `mean_shift_threshold` and `edge_sad_threshold` gate the corroborating
+/// signals; `edge_histograms`, if provided, must have one entry per frame.
+pub fn detect_scene_boundaries_sad(
+    frames: &[MadVRFrame],
+    min_scene_len: u32,
+    sad_multiplier: f64,
+    mean_shift_threshold: f64,
+    edge_histograms: Option<&[Vec<f64>]>,
+    edge_sad_threshold: f64,
+) -> Vec<SceneBoundary> {
+    const RUNNING_WINDOW: usize = 16;
+    let mut running: VecDeque<f64> = VecDeque::with_capacity(RUNNING_WINDOW);
+    let mut boundaries = Vec::new();
+    let mut last_cut: Option<u32> = None;
+
+    for i in 1..frames.len() {
+        let sad = histogram_sad(&frames[i - 1].lum_histogram, &frames[i].lum_histogram);
+        let running_avg = if running.is_empty() {
+            sad
+        } else {
+            running.iter().sum::<f64>() / running.len() as f64
+        };
+
+        let sad_exceeds = sad > running_avg * sad_multiplier;
+        let mean_shift = (frames[i].avg_pq - frames[i - 1].avg_pq).abs();
+        let mean_shift_significant = mean_shift > mean_shift_threshold;
+        let edge_significant = edge_histograms
+            .map(|hists| {
+                i < hists.len() && histogram_sad(&hists[i - 1], &hists[i]) > edge_sad_threshold
+            })
+            .unwrap_or(false);
+
+        if sad_exceeds
+            && (mean_shift_significant || edge_significant)
+            && cut_allowed(last_cut, i as u32, min_scene_len)
+        {
+            boundaries.push(SceneBoundary { frame: i as u32 });
+            last_cut = Some(i as u32);
+        }
+
+        running.push_back(sad);
+        if running.len() > RUNNING_WINDOW {
+            running.pop_front();
+        }
+    }
+
+    boundaries
+}
+
+/// Aggregates stable per-scene MaxCLL/MaxFALL and a representative histogram from scene
+/// boundaries produced by [`detect_scene_boundaries_sad`], so downstream consumers (tone
+/// mapping, denoising) get scene-stable values instead of flickering per-frame ones.
+pub fn aggregate_scene_rollups(
+    boundaries: &[SceneBoundary],
+    frames: &[MadVRFrame],
+) -> Vec<SceneRollup> {
+    if frames.is_empty() {
+        return Vec::new();
+    }
+
+    let mut bounds: Vec<u32> = boundaries.iter().map(|b| b.frame).collect();
+    bounds.sort_unstable();
+
+    let mut starts = vec![0u32];
+    starts.extend(bounds);
+
+    let total_frames = frames.len() as u32;
+    let mut rollups = Vec::with_capacity(starts.len());
+
+    for (idx, &start) in starts.iter().enumerate() {
+        let end = starts
+            .get(idx + 1)
+            .map(|&next| next.saturating_sub(1))
+            .unwrap_or(total_frames.saturating_sub(1));
+
+        let start_idx = start as usize;
+        let end_idx = ((end + 1) as usize).min(frames.len());
+        if start_idx >= frames.len() || start_idx >= end_idx {
+            continue;
+        }
+        let scene_frames = &frames[start_idx..end_idx];
+
+        let max_peak_pq = scene_frames
+            .iter()
+            .map(|f| f.peak_pq_2020)
+            .fold(0.0f64, f64::max);
+        let avg_pq_mean =
+            scene_frames.iter().map(|f| f.avg_pq).sum::<f64>() / scene_frames.len() as f64;
+        let avg_peak_pq = scene_frames.iter().map(|f| f.peak_pq_2020).sum::<f64>()
+            / scene_frames.len() as f64;
+
+        let representative = scene_frames
+            .iter()
+            .min_by(|a, b| {
+                (a.peak_pq_2020 - avg_peak_pq)
+                    .abs()
+                    .partial_cmp(&(b.peak_pq_2020 - avg_peak_pq).abs())
+                    .unwrap()
+            })
+            .map(|f| f.lum_histogram.clone())
+            .unwrap_or_default();
+
+        rollups.push(SceneRollup {
+            start,
+            end,
+            max_cll_nits: pq_to_nits(max_peak_pq) as u32,
+            max_fall_nits: pq_to_nits(avg_pq_mean) as u32,
+            representative_histogram: representative,
+        });
+    }
+
+    rollups
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::VecDeque;
 
     #[test]
     fn test_histogram_diff_smoothing_behaves() {
@@ -154,4 +601,225 @@ mod tests {
         assert!(!cut_allowed(Some(100), 110, 24)); // Too close
         assert!(cut_allowed(Some(100), 124, 24)); // Exactly min distance
     }
+
+    #[test]
+    fn test_scene_detect_mode_from_name() {
+        assert_eq!(
+            SceneDetectMode::from_name("fixed").unwrap(),
+            SceneDetectMode::Fixed
+        );
+        assert_eq!(
+            SceneDetectMode::from_name("ADAPTIVE").unwrap(),
+            SceneDetectMode::Adaptive
+        );
+        assert!(SceneDetectMode::from_name("bogus").is_err());
+    }
+
+    #[test]
+    fn test_adaptive_scene_detector_flags_spike() {
+        let mut detector = AdaptiveSceneDetector::new(60);
+        // Feed a quiet baseline so mean/stddev settle near zero
+        for _ in 0..30 {
+            assert!(!detector.evaluate(0.05, 2.5));
+        }
+        // A sudden spike well above the baseline should be flagged
+        assert!(detector.evaluate(5.0, 2.5));
+    }
+
+    #[test]
+    fn test_adaptive_scene_detector_window_bounded() {
+        let mut detector = AdaptiveSceneDetector::new(3);
+        for i in 0..10 {
+            detector.evaluate(i as f64, 2.5);
+        }
+        assert_eq!(detector.window.len(), 3);
+    }
+
+    #[test]
+    fn test_flash_flag_rejects_single_frame_flash() {
+        let mut filter = FlashFadeFilter::new(6, 0.4);
+        let dark = vec![1.0; 256];
+        let mut bright = vec![0.0; 256];
+        bright[255] = 1.0;
+
+        assert_eq!(filter.observe(0, false, &dark), None);
+        // Candidate cut on a single bright frame...
+        assert_eq!(filter.observe(1, true, &bright), None);
+        // ...but content reverts to dark immediately after: a flash, must be rejected.
+        for f in 2..8 {
+            assert_eq!(filter.observe(f, false, &dark), None);
+        }
+    }
+
+    #[test]
+    fn test_flash_filter_confirms_genuine_cut() {
+        let mut filter = FlashFadeFilter::new(4, 0.4);
+        let dark = vec![1.0; 256];
+        let mut bright = vec![0.0; 256];
+        bright[255] = 1.0;
+
+        assert_eq!(filter.observe(0, false, &dark), None);
+        assert_eq!(filter.observe(1, true, &bright), None);
+        // Content stays bright (genuine scene change): confirm after the window elapses.
+        assert_eq!(filter.observe(2, false, &bright), None);
+        assert_eq!(filter.observe(3, false, &bright), None);
+        assert_eq!(filter.observe(4, false, &bright), Some(1));
+    }
+
+    #[test]
+    fn test_fade_detector_flags_sustained_drift() {
+        let mut detector = FadeDetector::new(5, 0.3);
+        let diffs = [0.05, 0.08, 0.12, 0.15, 0.2, 0.25];
+        let mut boundary = None;
+        for (i, d) in diffs.iter().enumerate() {
+            if let Some(b) = detector.observe(i as u32, *d) {
+                boundary = Some(b);
+            }
+        }
+        assert_eq!(boundary, Some(5));
+    }
+
+    #[test]
+    fn test_fade_detector_ignores_non_monotonic_noise() {
+        let mut detector = FadeDetector::new(5, 0.3);
+        let diffs = [0.05, 0.2, 0.05, 0.2, 0.05];
+        let mut flagged = false;
+        for (i, d) in diffs.iter().enumerate() {
+            if detector.observe(i as u32, *d).is_some() {
+                flagged = true;
+            }
+        }
+        assert!(
+            !flagged,
+            "oscillating noise should not be mistaken for a fade"
+        );
+    }
+
+    fn frame_with(peak_pq: f64, avg_pq: f64, histogram: Vec<f64>) -> MadVRFrame {
+        MadVRFrame {
+            peak_pq_2020: peak_pq,
+            avg_pq,
+            lum_histogram: histogram,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_histogram_sad_identical_is_zero() {
+        let hist = vec![1.0; 256];
+        assert!(histogram_sad(&hist, &hist).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_histogram_sad_opposite_is_high() {
+        let mut hist1 = vec![0.0; 256];
+        hist1[0] = 100.0;
+        let mut hist2 = vec![0.0; 256];
+        hist2[255] = 100.0;
+        assert!((histogram_sad(&hist1, &hist2) - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hist_scenecut_diff_identical_is_zero() {
+        let edge = vec![1.0; 32];
+        let chroma = vec![1.0; 256];
+        assert!(hist_scenecut_diff(&edge, &edge, &chroma, &chroma).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hist_scenecut_diff_tracks_larger_signal() {
+        let mut edge1 = vec![0.0; 32];
+        edge1[0] = 100.0;
+        let mut edge2 = vec![0.0; 32];
+        edge2[31] = 100.0;
+        let chroma = vec![1.0; 256];
+
+        // Only the edge histogram shifts; chroma is unchanged. The combined diff should still
+        // reflect the edge shift (normalized SAD of fully disjoint histograms is 1.0).
+        let diff = hist_scenecut_diff(&edge1, &edge2, &chroma, &chroma);
+        assert!((diff - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_variance_gate_confirms_rejects_unchanged_frame() {
+        assert!(!variance_gate_confirms(128.0, 500.0, 128.0, 500.0, 0.05));
+    }
+
+    #[test]
+    fn test_variance_gate_confirms_accepts_large_mean_shift() {
+        assert!(variance_gate_confirms(600.0, 500.0, 128.0, 500.0, 0.05));
+    }
+
+    #[test]
+    fn test_variance_gate_confirms_demands_more_on_flat_content() {
+        // A mean shift that confirms against normally-textured (reference-variance) content...
+        let shift_confirms = variance_gate_confirms(170.0, 400.0, 100.0, 400.0, 0.05);
+        assert!(shift_confirms);
+        // ...should be rejected against a much flatter previous frame, since the same absolute
+        // shift is relatively larger grain/fade jitter there, not a real cut.
+        let same_shift_on_flat_content = variance_gate_confirms(170.0, 1.0, 100.0, 1.0, 0.05);
+        assert!(!same_shift_on_flat_content);
+    }
+
+    #[test]
+    fn test_detect_scene_boundaries_sad_flags_genuine_cut() {
+        let mut dark_hist = vec![0.0; 256];
+        dark_hist[0] = 100.0;
+        let mut bright_hist = vec![0.0; 256];
+        bright_hist[255] = 100.0;
+
+        let mut frames: Vec<MadVRFrame> = (0..30)
+            .map(|_| frame_with(0.1, 0.1, dark_hist.clone()))
+            .collect();
+        frames.extend((0..30).map(|_| frame_with(0.9, 0.9, bright_hist.clone())));
+
+        let boundaries = detect_scene_boundaries_sad(&frames, 10, 1.5, 0.1, None, 10.0);
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].frame, 30);
+    }
+
+    #[test]
+    fn test_detect_scene_boundaries_sad_requires_mean_shift_corroboration() {
+        // Histogram flips dramatically every frame but the mean (avg_pq) never moves --
+        // shouldn't model a real-world cut, and the high mean_shift_threshold rejects it.
+        let mut a = vec![0.0; 256];
+        a[0] = 100.0;
+        let mut b = vec![0.0; 256];
+        b[255] = 100.0;
+
+        let frames: Vec<MadVRFrame> = (0..10)
+            .map(|i| {
+                if i % 2 == 0 {
+                    frame_with(0.5, 0.5, a.clone())
+                } else {
+                    frame_with(0.5, 0.5, b.clone())
+                }
+            })
+            .collect();
+
+        let boundaries = detect_scene_boundaries_sad(&frames, 1, 1.5, 100.0, None, 10.0);
+        assert!(boundaries.is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_scene_rollups_splits_on_boundaries() {
+        let frames: Vec<MadVRFrame> = (0..10)
+            .map(|i| {
+                if i < 5 {
+                    frame_with(0.2, 0.2, vec![1.0; 256])
+                } else {
+                    frame_with(0.8, 0.8, vec![2.0; 256])
+                }
+            })
+            .collect();
+        let boundaries = vec![SceneBoundary { frame: 5 }];
+
+        let rollups = aggregate_scene_rollups(&boundaries, &frames);
+        assert_eq!(rollups.len(), 2);
+        assert_eq!(rollups[0].start, 0);
+        assert_eq!(rollups[0].end, 4);
+        assert_eq!(rollups[1].start, 5);
+        assert_eq!(rollups[1].end, 9);
+        assert!(rollups[1].max_cll_nits > rollups[0].max_cll_nits);
+    }
 }
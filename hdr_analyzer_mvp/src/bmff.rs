@@ -0,0 +1,378 @@
+//! Minimal ISO base media file format (ISO/IEC 14496-12) box writer, used to package the
+//! pipeline's per-frame/per-scene tone-mapping metadata as a `.mp4` timed-metadata track -- an
+//! alternative to the raw madVR `.bin` (see `writer.rs`) for muxers/players that expect a
+//! standards-shaped container they can remux directly, instead of a madVR-specific format.
+//!
+//! This is not a general-purpose MP4 muxer: only the boxes needed for a single timed-metadata
+//! track (`ftyp`, `moov`/`mvhd`/`trak`/`mdia`/`minf`/`stbl`, `mdat`) are implemented. The
+//! per-sample payload reuses [`crate::hdr10plus::SceneStats`] so the same scene-stable numbers
+//! back both the HDR10+ JSON and this sidecar; it's a simple fixed-size binary record today,
+//! but the box layer is generic enough to carry a different payload (e.g. raw HDR10+ metadata)
+//! later without touching the box-writing code.
+
+use anyhow::{Context, Result};
+use madvr_parse::{MadVRFrame, MadVRScene};
+
+use crate::hdr10plus::compute_scene_stats;
+
+/// Bytes per timed-metadata sample: 10 distribution values + average_rgb + max_scl +
+/// knee_point_y, each a big-endian u32.
+const SAMPLE_SIZE: usize = 13 * 4;
+
+/// Write a box: a 4-byte size (back-patched once `body` has appended its content), a 4-byte
+/// type, then whatever `body` appends to `buf`.
+fn write_box(buf: &mut Vec<u8>, fourcc: &[u8; 4], body: impl FnOnce(&mut Vec<u8>)) {
+    let size_pos = buf.len();
+    buf.extend_from_slice(&[0, 0, 0, 0]); // patched below
+    buf.extend_from_slice(fourcc);
+    body(buf);
+    let size = (buf.len() - size_pos) as u32;
+    buf[size_pos..size_pos + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+/// Like [`write_box`], but for a "full box" that starts with a version byte and a 3-byte flags
+/// field before `body`'s content.
+fn write_full_box(
+    buf: &mut Vec<u8>,
+    fourcc: &[u8; 4],
+    version: u8,
+    flags: u32,
+    body: impl FnOnce(&mut Vec<u8>),
+) {
+    write_box(buf, fourcc, |buf| {
+        buf.push(version);
+        buf.extend_from_slice(&flags.to_be_bytes()[1..]); // low 24 bits only
+        body(buf);
+    });
+}
+
+fn write_ftyp(buf: &mut Vec<u8>) {
+    write_box(buf, b"ftyp", |buf| {
+        buf.extend_from_slice(b"isom");
+        buf.extend_from_slice(&0u32.to_be_bytes());
+        buf.extend_from_slice(b"isom");
+        buf.extend_from_slice(b"mp42");
+    });
+}
+
+fn write_mvhd(buf: &mut Vec<u8>, timescale: u32, duration: u32) {
+    write_full_box(buf, b"mvhd", 0, 0, |buf| {
+        buf.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        buf.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        buf.extend_from_slice(&timescale.to_be_bytes());
+        buf.extend_from_slice(&duration.to_be_bytes());
+        buf.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate, 1.0
+        buf.extend_from_slice(&0x0100u16.to_be_bytes()); // volume, 1.0
+        buf.extend_from_slice(&[0u8; 2]); // reserved
+        buf.extend_from_slice(&[0u8; 8]); // reserved
+                                          // unity 3x3 transformation matrix
+        for value in [0x0001_0000i32, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000] {
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+        buf.extend_from_slice(&[0u8; 24]); // pre_defined
+        buf.extend_from_slice(&2u32.to_be_bytes()); // next_track_ID
+    });
+}
+
+fn write_tkhd(buf: &mut Vec<u8>, duration: u32) {
+    write_full_box(buf, b"tkhd", 0, 0x000007, |buf| {
+        buf.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        buf.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        buf.extend_from_slice(&1u32.to_be_bytes()); // track_ID
+        buf.extend_from_slice(&0u32.to_be_bytes()); // reserved
+        buf.extend_from_slice(&duration.to_be_bytes());
+        buf.extend_from_slice(&[0u8; 8]); // reserved
+        buf.extend_from_slice(&0u16.to_be_bytes()); // layer
+        buf.extend_from_slice(&0u16.to_be_bytes()); // alternate_group
+        buf.extend_from_slice(&0u16.to_be_bytes()); // volume (non-audio track)
+        buf.extend_from_slice(&[0u8; 2]); // reserved
+        for value in [0x0001_0000i32, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000] {
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+        buf.extend_from_slice(&0u32.to_be_bytes()); // width (no visual presentation)
+        buf.extend_from_slice(&0u32.to_be_bytes()); // height
+    });
+}
+
+fn write_mdhd(buf: &mut Vec<u8>, timescale: u32, duration: u32) {
+    write_full_box(buf, b"mdhd", 0, 0, |buf| {
+        buf.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        buf.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        buf.extend_from_slice(&timescale.to_be_bytes());
+        buf.extend_from_slice(&duration.to_be_bytes());
+        buf.extend_from_slice(&0x55C4u16.to_be_bytes()); // language "und"
+        buf.extend_from_slice(&0u16.to_be_bytes()); // pre_defined
+    });
+}
+
+fn write_hdlr(buf: &mut Vec<u8>) {
+    write_full_box(buf, b"hdlr", 0, 0, |buf| {
+        buf.extend_from_slice(&0u32.to_be_bytes()); // pre_defined
+        buf.extend_from_slice(b"meta"); // handler_type: timed metadata
+        buf.extend_from_slice(&[0u8; 12]); // reserved
+        buf.extend_from_slice(b"hdr_analyzer_mvp tone-mapping metadata\0");
+    });
+}
+
+fn write_dref(buf: &mut Vec<u8>) {
+    write_full_box(buf, b"dref", 0, 0, |buf| {
+        buf.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        write_full_box(buf, b"url ", 0, 0x000001, |_buf| {}); // self-contained (flags & 1)
+    });
+}
+
+fn write_dinf(buf: &mut Vec<u8>) {
+    write_box(buf, b"dinf", |buf| {
+        write_dref(buf);
+    });
+}
+
+/// Sample entry for our custom `hdrm` metadata format -- just enough structure for a reader to
+/// recognize the track as private per-frame tone-mapping data, not a real registered format.
+fn write_stsd(buf: &mut Vec<u8>) {
+    write_full_box(buf, b"stsd", 0, 0, |buf| {
+        buf.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        write_box(buf, b"hdrm", |buf| {
+            buf.extend_from_slice(&[0u8; 6]); // reserved
+            buf.extend_from_slice(&1u16.to_be_bytes()); // data_reference_index
+        });
+    });
+}
+
+fn write_stts(buf: &mut Vec<u8>, sample_count: u32, sample_duration: u32) {
+    write_full_box(buf, b"stts", 0, 0, |buf| {
+        buf.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        buf.extend_from_slice(&sample_count.to_be_bytes());
+        buf.extend_from_slice(&sample_duration.to_be_bytes());
+    });
+}
+
+fn write_stsc(buf: &mut Vec<u8>, sample_count: u32) {
+    write_full_box(buf, b"stsc", 0, 0, |buf| {
+        buf.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        buf.extend_from_slice(&1u32.to_be_bytes()); // first_chunk
+        buf.extend_from_slice(&sample_count.to_be_bytes()); // samples_per_chunk: one chunk total
+        buf.extend_from_slice(&1u32.to_be_bytes()); // sample_description_index
+    });
+}
+
+fn write_stsz(buf: &mut Vec<u8>, sample_count: u32) {
+    write_full_box(buf, b"stsz", 0, 0, |buf| {
+        buf.extend_from_slice(&(SAMPLE_SIZE as u32).to_be_bytes()); // uniform sample_size
+        buf.extend_from_slice(&sample_count.to_be_bytes());
+    });
+}
+
+fn write_stco(buf: &mut Vec<u8>, chunk_offset: u32) {
+    write_full_box(buf, b"stco", 0, 0, |buf| {
+        buf.extend_from_slice(&1u32.to_be_bytes()); // entry_count
+        buf.extend_from_slice(&chunk_offset.to_be_bytes());
+    });
+}
+
+fn write_stbl(buf: &mut Vec<u8>, sample_count: u32, sample_duration: u32, chunk_offset: u32) {
+    write_box(buf, b"stbl", |buf| {
+        write_stsd(buf);
+        write_stts(buf, sample_count, sample_duration);
+        write_stsc(buf, sample_count);
+        write_stsz(buf, sample_count);
+        write_stco(buf, chunk_offset);
+    });
+}
+
+fn write_minf(buf: &mut Vec<u8>, sample_count: u32, sample_duration: u32, chunk_offset: u32) {
+    write_box(buf, b"minf", |buf| {
+        // "null" media header, the generic handler for tracks that are neither video nor
+        // audio (timed metadata included).
+        write_full_box(buf, b"nmhd", 0, 0, |_buf| {});
+        write_dinf(buf);
+        write_stbl(buf, sample_count, sample_duration, chunk_offset);
+    });
+}
+
+fn write_mdia(
+    buf: &mut Vec<u8>,
+    timescale: u32,
+    duration: u32,
+    sample_count: u32,
+    sample_duration: u32,
+    chunk_offset: u32,
+) {
+    write_box(buf, b"mdia", |buf| {
+        write_mdhd(buf, timescale, duration);
+        write_hdlr(buf);
+        write_minf(buf, sample_count, sample_duration, chunk_offset);
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_trak(
+    buf: &mut Vec<u8>,
+    timescale: u32,
+    duration: u32,
+    sample_count: u32,
+    sample_duration: u32,
+    chunk_offset: u32,
+) {
+    write_box(buf, b"trak", |buf| {
+        write_tkhd(buf, duration);
+        write_mdia(
+            buf,
+            timescale,
+            duration,
+            sample_count,
+            sample_duration,
+            chunk_offset,
+        );
+    });
+}
+
+/// Size, in bytes, of the `ftyp` + `moov` boxes written before `mdat`, used to compute `mdat`'s
+/// absolute byte offset for `stco` ahead of actually serializing the boxes.
+fn moov_size(timescale: u32, duration: u32, sample_count: u32, sample_duration: u32) -> usize {
+    let mut probe = Vec::new();
+    write_moov(
+        &mut probe,
+        timescale,
+        duration,
+        sample_count,
+        sample_duration,
+        0,
+    );
+    probe.len()
+}
+
+fn write_moov(
+    buf: &mut Vec<u8>,
+    timescale: u32,
+    duration: u32,
+    sample_count: u32,
+    sample_duration: u32,
+    chunk_offset: u32,
+) {
+    write_box(buf, b"moov", |buf| {
+        write_mvhd(buf, timescale, duration);
+        write_trak(
+            buf,
+            timescale,
+            duration,
+            sample_count,
+            sample_duration,
+            chunk_offset,
+        );
+    });
+}
+
+/// Pack one scene's [`crate::hdr10plus::SceneStats`] into a fixed-size big-endian record:
+/// 10 distribution values, then average_rgb, max_scl, knee_point_y.
+fn encode_sample(stats: &crate::hdr10plus::SceneStats) -> Vec<u8> {
+    let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+    for value in &stats.distribution_values {
+        sample.extend_from_slice(&value.to_be_bytes());
+    }
+    sample.extend_from_slice(&stats.average_rgb.to_be_bytes());
+    sample.extend_from_slice(&stats.max_scl.to_be_bytes());
+    sample.extend_from_slice(&stats.knee_point_y.to_be_bytes());
+    sample
+}
+
+/// Write an ISO BMFF (`.mp4`) sidecar carrying one sample of tone-mapping metadata per frame,
+/// as a timed-metadata track, for muxers/players that want a standards-shaped container
+/// instead of the raw madVR `.bin`.
+pub fn write_bmff_sidecar(
+    output_path: &str,
+    scenes: &[MadVRScene],
+    frames: &[MadVRFrame],
+    frame_rate: f64,
+) -> Result<()> {
+    let timescale = 1000u32;
+    let sample_duration = if frame_rate > 0.0 {
+        (timescale as f64 / frame_rate).round().max(1.0) as u32
+    } else {
+        timescale / 24
+    };
+    let sample_count = frames.len() as u32;
+    let duration = sample_count.saturating_mul(sample_duration);
+
+    let moov_len = moov_size(timescale, duration, sample_count, sample_duration);
+    let ftyp_len = {
+        let mut probe = Vec::new();
+        write_ftyp(&mut probe);
+        probe.len()
+    };
+    // mdat's payload starts right after its own 8-byte header.
+    let chunk_offset = (ftyp_len + moov_len + 8) as u32;
+
+    let mut buf = Vec::new();
+    write_ftyp(&mut buf);
+    write_moov(
+        &mut buf,
+        timescale,
+        duration,
+        sample_count,
+        sample_duration,
+        chunk_offset,
+    );
+    write_box(&mut buf, b"mdat", |buf| {
+        for (scene_id, scene) in scenes.iter().enumerate() {
+            let start = scene.start as usize;
+            let end = ((scene.end + 1) as usize).min(frames.len());
+            if start >= frames.len() || start >= end {
+                continue;
+            }
+            let _ = scene_id;
+            let stats = compute_scene_stats(scene, &frames[start..end]);
+            let sample = encode_sample(&stats);
+            for _frame in &frames[start..end] {
+                buf.extend_from_slice(&sample);
+            }
+        }
+    });
+
+    std::fs::write(output_path, buf)
+        .with_context(|| format!("Failed to write ISO BMFF sidecar: {}", output_path))?;
+
+    println!(
+        "Successfully wrote ISO BMFF timed-metadata sidecar: {}",
+        output_path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_box_patches_size() {
+        let mut buf = Vec::new();
+        write_box(&mut buf, b"test", |buf| {
+            buf.extend_from_slice(&[1, 2, 3, 4]);
+        });
+        assert_eq!(buf.len(), 12);
+        let size = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+        assert_eq!(size, 12);
+        assert_eq!(&buf[4..8], b"test");
+    }
+
+    #[test]
+    fn test_write_full_box_version_and_flags() {
+        let mut buf = Vec::new();
+        write_full_box(&mut buf, b"full", 1, 0x000203, |_buf| {});
+        assert_eq!(buf[8], 1); // version
+        assert_eq!(&buf[9..12], &[0x00, 0x02, 0x03]); // flags, low 24 bits
+    }
+
+    #[test]
+    fn test_encode_sample_size() {
+        let stats = crate::hdr10plus::SceneStats {
+            distribution_values: vec![0; 10],
+            average_rgb: 0,
+            max_scl: 0,
+            knee_point_x: 0,
+            knee_point_y: 0,
+            anchors: vec![],
+        };
+        assert_eq!(encode_sample(&stats).len(), SAMPLE_SIZE);
+    }
+}
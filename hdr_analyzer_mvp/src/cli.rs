@@ -26,6 +26,17 @@ pub struct Cli {
     #[arg(long)]
     pub hwaccel: Option<String>,
 
+    /// Number of threads FFmpeg's decoder should use (frame- and slice-threaded). 0 auto-selects
+    /// the number of logical CPUs. Default: 0
+    #[arg(long, default_value_t = 0)]
+    pub decode_threads: usize,
+
+    /// Upper bound, in frames, on the estimated decode latency a frame-threaded decoder reports
+    /// (informational; it buffers up to one frame per decode thread internally). Defaults to the
+    /// resolved thread count when unset.
+    #[arg(long)]
+    pub max_frame_delay: Option<usize>,
+
     /// madVR measurement file version to write (5 or 6). Default: 5
     #[arg(long, default_value_t = 5)]
     pub madvr_version: u8,
@@ -42,6 +53,85 @@ pub struct Cli {
     #[arg(long, default_value_t = 5)]
     pub scene_smoothing: u32,
 
+    /// Scene-cut signal: histogram (luminance-histogram chi-squared distance, the default),
+    /// flow (mean absolute difference of a downscaled luma thumbnail against the previous
+    /// frame, catching pans/dissolves that barely move the histogram), or hybrid (weighted
+    /// blend of both, see --scene-metric-weight). Mutually exclusive with `--hist-scenecut`,
+    /// which picks the cut signal by its own flag instead. Default: histogram
+    #[arg(long, default_value = "histogram", conflicts_with = "hist_scenecut")]
+    pub scene_metric: String,
+
+    /// Weight given to the histogram component when --scene-metric=hybrid; the luma-flow
+    /// component gets `1.0 - scene_metric_weight`. Default: 0.5
+    #[arg(long, default_value_t = 0.5)]
+    pub scene_metric_weight: f64,
+
+    /// Path to a "zones" file giving per-frame-range overrides: one zone per line, formatted
+    /// `start end key=value ...` with keys scene_threshold, min_scene_length,
+    /// optimizer_profile, and/or target_peak_nits. Every zone's start frame also forces a
+    /// scene cut there, regardless of the scene-cut metric. Lets users hand-tune difficult
+    /// sections (credits, fades, a too-bright title card) without re-tuning global flags.
+    #[arg(long)]
+    pub zones_file: Option<String>,
+
+    /// Path to write an hdr10plus_tool-compatible HDR10+ (ST 2094-40) dynamic metadata JSON
+    /// file, synthesized from the per-frame luminance histograms and per-scene peak/target
+    /// stats. Off by default; when set, this is written alongside the madVR measurement file.
+    #[arg(long)]
+    pub hdr10plus_json: Option<String>,
+
+    /// Path to write an ISO base media file (`.mp4`) carrying the per-frame/per-scene
+    /// tone-mapping metadata as a timed-metadata track, for muxers/players that expect a
+    /// standards-shaped container instead of the raw madVR `.bin`. Off by default; when set,
+    /// this is written alongside the madVR measurement file.
+    #[arg(long)]
+    pub bmff_output: Option<String>,
+
+    /// Frame rate (fps) used to compute the timed-metadata track's per-sample decode times in
+    /// `--bmff-output`. The pipeline doesn't probe the source frame rate itself, so this must
+    /// match the source; getting it wrong only affects playback timing of the sidecar, not the
+    /// measurement data. Default: 24.0
+    #[arg(long, default_value_t = 24.0)]
+    pub bmff_frame_rate: f64,
+
+    /// When the decoder hands back P010LE/P016LE surfaces (the common VAAPI/NVDEC 10-bit
+    /// output) and --downscale is 1, skip `swscale` and convert luma directly with a
+    /// right-shift-by-6 fast path (see `convert_p01x_to_yuv420p10le`). With this flag, also
+    /// skip the chroma deinterleave and hue-histogram computation entirely -- MadVRFrame's
+    /// hue_histogram will be all-zero. Use when only luma statistics (histogram, peak, scene
+    /// cuts) matter, to avoid the chroma work in the fast path too.
+    #[arg(long)]
+    pub luma_only: bool,
+
+    /// Scene-detection strategy: fixed (single global threshold) or adaptive (rolling
+    /// mean+k*stddev). Default: fixed
+    #[arg(long, default_value = "fixed")]
+    pub scene_detect_mode: String,
+
+    /// Rolling window size (in frames) used by adaptive scene detection. Default: 60
+    #[arg(long, default_value_t = 60)]
+    pub adaptive_scene_window: usize,
+
+    /// Sensitivity multiplier k for adaptive scene detection (cut when diff > mean + k*stddev).
+    /// Default: 2.75
+    #[arg(long, default_value_t = 2.75)]
+    pub adaptive_scene_k: f64,
+
+    /// Lookahead confirmation window (in frames) used to suppress flash/flicker false cuts.
+    /// Default: 8
+    #[arg(long, default_value_t = 8)]
+    pub flash_confirm_frames: usize,
+
+    /// Fraction of a candidate cut's initial histogram jump below which content is considered
+    /// to have reverted (a flash rather than a genuine cut). Default: 0.4
+    #[arg(long, default_value_t = 0.4)]
+    pub flash_revert_tolerance: f64,
+
+    /// Number of consecutive frames of sustained, below-threshold drift required to treat a
+    /// slow fade as a single soft scene boundary. Default: 30
+    #[arg(long, default_value_t = 30)]
+    pub fade_detect_window: u32,
+
     /// Optional override for header.target_peak_nits (used for v6). If omitted, defaults to computed maxCLL.
     #[arg(long)]
     pub target_peak_nits: Option<u32>,
@@ -55,6 +145,16 @@ pub struct Cli {
     #[arg(long)]
     pub no_crop: bool,
 
+    /// Crop detection strategy: off (full frame), single (one frame, fast but fragile), or
+    /// temporal (consensus across sampled frames, robust to fades/title cards). Default: temporal
+    #[arg(long, default_value = "temporal")]
+    pub crop_detect: String,
+
+    /// Number of frames to sample across the file for temporal crop detection. Samples are
+    /// spread evenly, skipping the first/last 5% of the runtime. Default: 60
+    #[arg(long, default_value_t = 60)]
+    pub crop_sample_count: usize,
+
     /// Disable dynamic optimizer (enabled by default).
     #[arg(long)]
     pub disable_optimizer: bool,
@@ -67,7 +167,8 @@ pub struct Cli {
     #[arg(long)]
     pub profile_performance: bool,
 
-    /// Optimizer profile: conservative, balanced, or aggressive (default: balanced)
+    /// Optimizer profile: conservative, balanced, aggressive, or a path to a user-defined
+    /// TOML profile file (default: balanced)
     #[arg(long, default_value = "balanced")]
     pub optimizer_profile: String,
 
@@ -84,7 +185,147 @@ pub struct Cli {
     #[arg(long, default_value_t = 0)]
     pub hist_temporal_median: usize,
 
-    /// Pre-analysis Y-plane denoising: nlmeans, median3, or off (default: off)
+    /// Pre-analysis Y-plane denoising: nlmeans, median3, temporal (alias: mctf), or off
+    /// (default: off). "temporal"/"mctf" motion-compensates against the last few decoded
+    /// frames instead of a single-frame spatial median, and resets across scene cuts.
     #[arg(long, default_value = "off")]
     pub pre_denoise: String,
+
+    /// Tile variance (in squared 10-bit code units, computed over 16x16 tiles) at or above
+    /// which median3 denoising applies at full strength. Below it, filtering is blended off
+    /// proportionally so flat regions keep genuine specular highlight/fine detail. Only used
+    /// when --pre-denoise=median3. Default: 150.0
+    #[arg(long, default_value_t = 150.0)]
+    pub median_variance_threshold: f64,
+
+    /// When using --pre-denoise=median3, down-weight busy/grainy tiles (per their measured
+    /// variance) when integrating avg_pq, so noisy detail contributes less than flat content
+    /// to the APL measurement. Peak-nit measurement is unaffected.
+    #[arg(long)]
+    pub activity_weighted_apl: bool,
+
+    /// Post-optimization target_nits smoothing mode: off, ema, or gaussian. Default: off
+    #[arg(long, default_value = "off")]
+    pub target_smoother: String,
+
+    /// EMA smoothing factor alpha (0.0-1.0) used when target_smoother is "ema". Default: 0.2
+    #[arg(long, default_value_t = 0.2)]
+    pub smoother_alpha: f64,
+
+    /// Average a forward and backward EMA pass when target_smoother is "ema" (zero-phase).
+    #[arg(long)]
+    pub smoother_bidirectional: bool,
+
+    /// Standard deviation (in frames) of the Gaussian kernel used when target_smoother is
+    /// "gaussian". Default: 3.0
+    #[arg(long, default_value_t = 3.0)]
+    pub smoother_sigma: f64,
+
+    /// Lookahead horizon (in frames) for pre-ramping target_nits ahead of sustained rises or
+    /// drops, so the delta limiter's slope reaches the upcoming level exactly when the new
+    /// content starts instead of popping at the boundary. 0 disables. Default: 0
+    #[arg(long, default_value_t = 0)]
+    pub lookahead_horizon: u32,
+
+    /// Render a tone-mapped SDR still to this path (PNG) from a representative mid-file
+    /// frame, so the conversion can be sanity-checked without a DV-capable display. Disabled
+    /// unless set.
+    #[arg(long)]
+    pub sdr_preview: Option<String>,
+
+    /// Tone-mapping operator used for --sdr-preview: reinhard, hable, or linear. Default: hable
+    #[arg(long, default_value = "hable")]
+    pub tone_map: String,
+
+    /// HDR reference peak used to normalize linear light before tone mapping. Either an
+    /// absolute nits value (e.g. "1000") or a luminance percentile measured from the preview
+    /// frame itself (e.g. "99.5%"). Default: 99.5%
+    #[arg(long, default_value = "99.5%")]
+    pub hdr_max: String,
+
+    /// Chroma scale applied in Oklab a/b after tone mapping, to compensate for the
+    /// saturation loss of luminance compression. 1.0 = unchanged. Default: 1.0
+    #[arg(long, default_value_t = 1.0)]
+    pub saturation: f64,
+
+    /// Run an additional diagnostic scene-cut pass using true histogram SAD against an
+    /// adaptive running-average threshold (corroborated by mean-luminance shift), and print a
+    /// per-scene MaxCLL/MaxFALL rollup. Informational only -- does not affect the measurement
+    /// file, which still uses the primary fixed/adaptive cut detector above.
+    #[arg(long)]
+    pub scene_cut_sad_diagnostic: bool,
+
+    /// Run an additional scene-cut pass that decodes the file independently of the main
+    /// pipeline, downscales each frame's luma plane to a small fixed grid, and flags a cut on a
+    /// MAD spike against the previous frame's grid -- split across
+    /// `std::thread::available_parallelism` concurrently-decoded chunks of the timeline instead
+    /// of one serialized decode. Prints a per-scene MaxCLL/MaxFALL rollup from the detected
+    /// boundaries. Informational only -- does not affect the measurement file.
+    #[arg(long)]
+    pub scene_prescan: bool,
+
+    /// Luma-grid thumbnail height used by `--scene-prescan` (width follows the source aspect
+    /// ratio, same as `--scene-metric flow`'s thumbnail). Default: 32
+    #[arg(long, default_value_t = 32)]
+    pub scene_prescan_grid_size: u32,
+
+    /// MAD threshold (on the same `[0, 1]` scale as the flow scene metric) a grid diff must
+    /// exceed for `--scene-prescan` to flag a cut. Default: 0.06
+    #[arg(long, default_value_t = 0.06)]
+    pub scene_prescan_threshold: f64,
+
+    /// Minimum scene length in frames for `--scene-prescan`, gating flicker the same way
+    /// `--min-scene-length` does for the primary detector. Default: 24
+    #[arg(long, default_value_t = 24)]
+    pub scene_prescan_min_len: u32,
+
+    /// Use normalized chroma-distribution + luma-edge SAD as the scene-cut signal instead of
+    /// the primary luminance-histogram chi-squared distance. Unlike
+    /// `--scene-cut-sad-diagnostic`, this replaces the cut decision that feeds the measurement
+    /// file rather than just reporting alongside it; `--min-scene-length` and
+    /// `--scene-smoothing` still apply on top. Useful for content where color/texture shifts
+    /// mark a cut more reliably than a luminance shift (e.g. graded day-for-night scenes).
+    /// Mutually exclusive with `--scene-metric`, since both pick the cut signal.
+    #[arg(long, conflicts_with = "scene_metric")]
+    pub hist_scenecut: bool,
+
+    /// Cut threshold for `--hist-scenecut`, on the same normalized [0, 1] scale as the
+    /// underlying chroma/edge SAD (1.0 = histograms share no mass at all). Default: 0.2
+    #[arg(long, default_value_t = 0.2)]
+    pub hist_threshold: f64,
+
+    /// Enable a logarithmic-bucket companion histogram (HdrHistogram-style) per frame, giving
+    /// bounded *relative* error P99/P99.9 peak queries instead of the 256-bin madVR
+    /// histogram's coarse highlight-region binning. Value is the sub-bucket precision in bits
+    /// per power-of-two band; e.g. 8 bounds relative error to 1/256 (~0.39%). 0 disables.
+    /// Only affects `--peak-source histogram99`/`histogram999`, and only when histogram
+    /// smoothing is enabled (`--hist-bin-ema-beta` or `--hist-temporal-median`), since that is
+    /// currently the only pass that recomputes `peak_pq_2020` after initial analysis.
+    #[arg(long, default_value_t = 0)]
+    pub log_histogram_precision: u32,
+
+    /// Run a per-scene dominant-color palette extraction pass (median-cut quantization over
+    /// sampled Y/Cb/Cr), and print the N palette entries for each scene. The measurement file
+    /// format has no room for arbitrary per-scene data (see `--scene-cut-sad-diagnostic`), so
+    /// like that diagnostic this is console output only -- it does not alter the `.bin` file.
+    #[arg(long)]
+    pub scene_palette: bool,
+
+    /// Number of dominant colors to extract per scene with `--scene-palette`. Default: 8
+    #[arg(long, default_value_t = 8)]
+    pub scene_palette_colors: usize,
+
+    /// Chroma-sample stride used by `--scene-palette` to bound the number of samples collected
+    /// per frame (1 samples every chroma pixel, 2 samples every other, etc.). Default: 4
+    #[arg(long, default_value_t = 4)]
+    pub scene_palette_sample_stride: usize,
+
+    /// Sensitivity for the whole-frame Y-plane mean/variance gate that corroborates every
+    /// scene-cut candidate (histogram distance alone can misfire on grain/fades). A candidate
+    /// is confirmed only if the frame-to-frame mean or variance shift exceeds a threshold of
+    /// roughly `sensitivity * 1023` code units, scaled up further on flat (low-variance)
+    /// previous frames so grain/fade jitter there doesn't read as a relatively large shift.
+    /// Default: 0.05
+    #[arg(long, default_value_t = 0.05)]
+    pub variance_gate_sensitivity: f64,
 }
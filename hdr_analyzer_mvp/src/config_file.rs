@@ -0,0 +1,292 @@
+//! Argument-file support: `@file.toml` (or `--config file.toml`) supplies defaults for CLI
+//! flags, following mwa_hyperdrive's "argument files" feature. Resolution happens before clap
+//! parses the real argv, by injecting `--flag value` pairs for whatever the file sets and the
+//! command line didn't already specify -- so explicit flags always win over the file.
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::cli::Cli;
+
+/// Whether `token` (a `--long`, `--long=value`, or `-s` argv entry) names a [`Cli`] flag that
+/// consumes a following argv token as its value, per clap's own parsed definition of the flag --
+/// used so [`ConfigFile::as_default_args`]'s positional-input scan can skip flag *values*
+/// (`vaapi` in `--hwaccel vaapi`) instead of mistaking them for a positional `input`.
+fn flag_takes_value(token: &str) -> bool {
+    let name = token.split('=').next().unwrap_or(token);
+    let long = name.strip_prefix("--");
+    let short = (!name.starts_with("--"))
+        .then(|| name.strip_prefix('-'))
+        .flatten()
+        .filter(|s| s.len() == 1)
+        .and_then(|s| s.chars().next());
+
+    Cli::command().get_arguments().any(|arg| {
+        let name_matches =
+            long.is_some_and(|l| arg.get_long() == Some(l)) || short.is_some_and(|s| arg.get_short() == Some(s));
+        name_matches && matches!(arg.get_action(), clap::ArgAction::Set | clap::ArgAction::Append)
+    })
+}
+
+/// The subset of `Cli` flags an argument file may supply defaults for.
+/// `#[serde(deny_unknown_fields)]` turns a typo'd or unrecognized key into a parse error instead
+/// of silently ignoring it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    input: Option<String>,
+    output: Option<String>,
+    madvr_version: Option<u8>,
+    downscale: Option<u32>,
+    scene_threshold: Option<f64>,
+    min_scene_length: Option<u32>,
+    hwaccel: Option<String>,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read argument file: {}", path.display()))?;
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse argument file TOML: {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Catch out-of-range values the same way `--madvr-version`/`--downscale` are constrained
+    /// elsewhere, so a bad argument file fails fast instead of silently producing a bogus
+    /// measurement file.
+    fn validate(&self) -> Result<()> {
+        if let Some(version) = self.madvr_version {
+            if version != 5 && version != 6 {
+                return Err(anyhow::anyhow!(
+                    "madvr_version in argument file must be 5 or 6, got {}",
+                    version
+                ));
+            }
+        }
+        if let Some(downscale) = self.downscale {
+            if downscale != 1 && downscale != 2 && downscale != 4 {
+                return Err(anyhow::anyhow!(
+                    "downscale in argument file must be 1, 2, or 4, got {}",
+                    downscale
+                ));
+            }
+        }
+        if let Some(threshold) = self.scene_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow::anyhow!(
+                    "scene_threshold in argument file must be between 0.0 and 1.0, got {}",
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the `--flag value` pairs this config supplies, skipping any flag already present
+    /// in `existing_args` so explicit command-line flags always win.
+    fn as_default_args(&self, existing_args: &[String]) -> Vec<String> {
+        let has_flag = |flag: &str| existing_args.iter().any(|a| a == flag);
+        let mut defaults = Vec::new();
+
+        if let Some(output) = &self.output {
+            if !has_flag("--output") && !has_flag("-o") {
+                defaults.push("--output".to_string());
+                defaults.push(output.clone());
+            }
+        }
+        if let Some(version) = self.madvr_version {
+            if !has_flag("--madvr-version") {
+                defaults.push("--madvr-version".to_string());
+                defaults.push(version.to_string());
+            }
+        }
+        if let Some(downscale) = self.downscale {
+            if !has_flag("--downscale") {
+                defaults.push("--downscale".to_string());
+                defaults.push(downscale.to_string());
+            }
+        }
+        if let Some(threshold) = self.scene_threshold {
+            if !has_flag("--scene-threshold") {
+                defaults.push("--scene-threshold".to_string());
+                defaults.push(threshold.to_string());
+            }
+        }
+        if let Some(min_scene_length) = self.min_scene_length {
+            if !has_flag("--min-scene-length") {
+                defaults.push("--min-scene-length".to_string());
+                defaults.push(min_scene_length.to_string());
+            }
+        }
+        if let Some(hwaccel) = &self.hwaccel {
+            if !has_flag("--hwaccel") {
+                defaults.push("--hwaccel".to_string());
+                defaults.push(hwaccel.clone());
+            }
+        }
+        // `input` has no flag form guaranteed to apply (it may be given positionally or via
+        // -i/--input), so only fall back to it when the command line supplies no input at all.
+        if let Some(input) = &self.input {
+            let has_input_flag = has_flag("--input") || has_flag("-i");
+            let has_positional_input = {
+                let mut skip_next = false;
+                existing_args.iter().skip(1).any(|a| {
+                    if skip_next {
+                        skip_next = false;
+                        return false;
+                    }
+                    if a.starts_with('-') {
+                        skip_next = flag_takes_value(a) && !a.contains('=');
+                        return false;
+                    }
+                    true
+                })
+            };
+            if !has_input_flag && !has_positional_input {
+                defaults.push(input.clone());
+            }
+        }
+
+        defaults
+    }
+}
+
+/// Strips a `--config <path>` pair or a leading `@path` token out of `args` (argv, including
+/// `args[0]`), and returns the argument-file path it named, if any.
+fn extract_config_path(args: &mut Vec<String>) -> Result<Option<String>> {
+    if let Some(idx) = args.iter().position(|a| a == "--config") {
+        let path = args
+            .get(idx + 1)
+            .cloned()
+            .context("--config requires a path to a TOML argument file")?;
+        args.drain(idx..=idx + 1);
+        return Ok(Some(path));
+    }
+
+    if let Some(idx) = args.iter().skip(1).position(|a| a.starts_with('@')) {
+        let idx = idx + 1; // undo the skip(1) offset
+        let path = args.remove(idx)[1..].to_string();
+        return Ok(Some(path));
+    }
+
+    Ok(None)
+}
+
+/// Resolves an `@file.toml`/`--config file.toml` argument file (if present in `raw_args`) into
+/// the effective argv clap should parse: explicit flags are left untouched, and whatever the
+/// file supplies for flags not already present is spliced in right after `argv[0]`.
+pub fn resolve_args(mut raw_args: Vec<String>) -> Result<Vec<String>> {
+    let Some(config_path) = extract_config_path(&mut raw_args)? else {
+        return Ok(raw_args);
+    };
+
+    let config = ConfigFile::load(Path::new(&config_path))?;
+    let defaults = config.as_default_args(&raw_args);
+    raw_args.splice(1..1, defaults);
+    Ok(raw_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_args_injects_file_defaults_without_overriding_explicit_flags() {
+        let raw_args = vec![
+            "hdr_analyzer_mvp".to_string(),
+            "--config".to_string(),
+            "unused".to_string(),
+            "--madvr-version".to_string(),
+            "5".to_string(),
+            "input.mkv".to_string(),
+        ];
+        let config = ConfigFile {
+            madvr_version: Some(6),
+            downscale: Some(2),
+            ..Default::default()
+        };
+        let defaults = config.as_default_args(&raw_args);
+        assert!(!defaults.contains(&"--madvr-version".to_string()));
+        assert!(defaults.contains(&"--downscale".to_string()));
+    }
+
+    #[test]
+    fn test_as_default_args_does_not_mistake_flag_value_for_positional_input() {
+        // `--config`/its path are already stripped by `extract_config_path` before
+        // `resolve_args` ever calls `as_default_args`, so this mirrors the post-strip argv
+        // rather than raw `env::args()`.
+        let raw_args = vec![
+            "hdr_analyzer_mvp".to_string(),
+            "--output".to_string(),
+            "out.bin".to_string(),
+            "--hwaccel".to_string(),
+            "vaapi".to_string(),
+        ];
+        let config = ConfigFile {
+            input: Some("input.mkv".to_string()),
+            ..Default::default()
+        };
+        let defaults = config.as_default_args(&raw_args);
+        assert!(
+            defaults.contains(&"input.mkv".to_string()),
+            "flag values like 'out.bin'/'vaapi' should not be mistaken for a positional input"
+        );
+    }
+
+    #[test]
+    fn test_extract_config_path_handles_at_syntax() {
+        let mut args = vec![
+            "hdr_analyzer_mvp".to_string(),
+            "@settings.toml".to_string(),
+            "input.mkv".to_string(),
+        ];
+        let path = extract_config_path(&mut args).unwrap();
+        assert_eq!(path, Some("settings.toml".to_string()));
+        assert_eq!(
+            args,
+            vec!["hdr_analyzer_mvp".to_string(), "input.mkv".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_config_path_handles_flag_syntax() {
+        let mut args = vec![
+            "hdr_analyzer_mvp".to_string(),
+            "--config".to_string(),
+            "settings.toml".to_string(),
+            "input.mkv".to_string(),
+        ];
+        let path = extract_config_path(&mut args).unwrap();
+        assert_eq!(path, Some("settings.toml".to_string()));
+        assert_eq!(
+            args,
+            vec!["hdr_analyzer_mvp".to_string(), "input.mkv".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_key() {
+        let toml_body = "not_a_real_flag = 42\n";
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_config_unknown_key.toml");
+        std::fs::write(&path, toml_body).unwrap();
+
+        assert!(ConfigFile::load(&path).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_config_file_rejects_out_of_range_madvr_version() {
+        let toml_body = "madvr_version = 99\n";
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_config_bad_version.toml");
+        std::fs::write(&path, toml_body).unwrap();
+
+        assert!(ConfigFile::load(&path).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
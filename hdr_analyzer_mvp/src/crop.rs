@@ -11,7 +11,12 @@ pub struct CropRect {
 
 impl CropRect {
     pub fn full(width: u32, height: u32) -> Self {
-        CropRect { x: 0, y: 0, width, height }
+        CropRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
     }
 }
 
@@ -60,6 +65,23 @@ pub fn detect_crop(frame: &frame::Video) -> CropRect {
         return CropRect::full(width, height);
     }
 
+    match detect_crop_edges(frame) {
+        Some((top, bottom, left, right)) => finalize_rect(top, bottom, left, right, width, height),
+        None => CropRect::full(width, height),
+    }
+}
+
+/// Scan a single frame for its raw (unrounded) active-area edges: `(top, bottom, left, right)`.
+/// Returns `None` if the whole frame is black (no row/column ever reaches the non-black
+/// threshold), so callers building a multi-frame consensus can discard it rather than letting
+/// it collapse the detected rectangle to full-frame.
+pub fn detect_crop_edges(frame: &frame::Video) -> Option<(u32, u32, u32, u32)> {
+    let width = frame.width() as u32;
+    let height = frame.height() as u32;
+    if width == 0 || height == 0 {
+        return None;
+    }
+
     let y_data = frame.data(0);
     let stride = frame.stride(0) as usize;
 
@@ -155,10 +177,32 @@ pub fn detect_crop(frame: &frame::Video) -> CropRect {
 
     // Validate
     if right <= left || bottom <= top {
-        return CropRect::full(width, height);
+        return None;
     }
 
-    // Round to even coordinates/dimensions and clamp
+    // No qualifying row leaves `top` at its initial 0 without row 0 qualifying: the frame is
+    // entirely black.
+    let top_row_non_black = (0..width as usize)
+        .step_by(sample_step)
+        .filter(|&x| is_non_black_10bit_limited(read_luma10(y_data, stride, x, top as usize)))
+        .count();
+    if top_row_non_black < required_non_black_row {
+        return None;
+    }
+
+    Some((top, bottom, left, right))
+}
+
+/// Round a set of raw `(top, bottom, left, right)` edges to even coordinates/dimensions,
+/// clamped within `width`x`height`. Shared by `detect_crop` and `detect_crop_temporal`.
+fn finalize_rect(
+    top: u32,
+    bottom: u32,
+    left: u32,
+    right: u32,
+    width: u32,
+    height: u32,
+) -> CropRect {
     let mut x0 = round_down_even(left);
     let mut y0 = round_down_even(top);
     let mut w = (right - x0 + 1).max(2);
@@ -168,11 +212,105 @@ pub fn detect_crop(frame: &frame::Video) -> CropRect {
 
     // Ensure within bounds
     if x0 + w > width {
-        if width >= w { x0 = width - w; } else { x0 = 0; w = width & !1; }
+        if width >= w {
+            x0 = width - w;
+        } else {
+            x0 = 0;
+            w = width & !1;
+        }
     }
     if y0 + h > height {
-        if height >= h { y0 = height - h; } else { y0 = 0; h = height & !1; }
+        if height >= h {
+            y0 = height - h;
+        } else {
+            y0 = 0;
+            h = height & !1;
+        }
     }
 
-    CropRect { x: x0, y: y0, width: w, height: h }
+    CropRect {
+        x: x0,
+        y: y0,
+        width: w,
+        height: h,
+    }
+}
+
+/// Fraction of sampled frames that must be matched-or-tighter by the consensus rectangle on
+/// each edge (e.g. 0.90 means the chosen offset is safe for at least 90% of sampled frames).
+const CONSENSUS_FRACTION: f64 = 0.90;
+
+/// Pick the value at a given percentile (0.0-1.0) from a sorted copy of `values`.
+fn percentile(values: &[u32], q: f64) -> u32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let idx = (((sorted.len() - 1) as f64) * q).round() as usize;
+    sorted[idx]
+}
+
+/// Aggregate per-frame `(top, bottom, left, right)` edge samples (as produced by
+/// `detect_crop_edges`, with black frames already discarded by the caller) into a single
+/// robust consensus rectangle.
+///
+/// For each edge, pick the offset that is matched-or-tighter (i.e. does not crop into content)
+/// for at least `CONSENSUS_FRACTION` of the sampled frames: the low percentile for `top`/`left`
+/// (smaller means less cropped) and the high percentile for `bottom`/`right` (larger means less
+/// cropped). This avoids letting a single frame that briefly shows bright content near the
+/// frame edge — or is otherwise an outlier — pull the crop in.
+pub fn detect_crop_temporal(samples: &[(u32, u32, u32, u32)], width: u32, height: u32) -> CropRect {
+    if samples.is_empty() {
+        return CropRect::full(width, height);
+    }
+
+    let tops: Vec<u32> = samples.iter().map(|s| s.0).collect();
+    let bottoms: Vec<u32> = samples.iter().map(|s| s.1).collect();
+    let lefts: Vec<u32> = samples.iter().map(|s| s.2).collect();
+    let rights: Vec<u32> = samples.iter().map(|s| s.3).collect();
+
+    let top = percentile(&tops, 1.0 - CONSENSUS_FRACTION);
+    let bottom = percentile(&bottoms, CONSENSUS_FRACTION);
+    let left = percentile(&lefts, 1.0 - CONSENSUS_FRACTION);
+    let right = percentile(&rights, CONSENSUS_FRACTION);
+
+    if right <= left || bottom <= top {
+        return CropRect::full(width, height);
+    }
+
+    finalize_rect(top, bottom, left, right, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_crop_temporal_empty_samples_returns_full() {
+        let rect = detect_crop_temporal(&[], 1920, 1080);
+        assert_eq!(rect.width, 1920);
+        assert_eq!(rect.height, 1080);
+    }
+
+    #[test]
+    fn test_detect_crop_temporal_ignores_single_outlier_frame() {
+        // 9 frames agree on a 1920x800 letterboxed area (top=140, bottom=939), plus one
+        // outlier frame where a bright element briefly reaches all the way to the top.
+        let mut samples: Vec<(u32, u32, u32, u32)> = (0..9).map(|_| (140, 939, 0, 1919)).collect();
+        samples.push((0, 939, 0, 1919));
+
+        let rect = detect_crop_temporal(&samples, 1920, 1080);
+
+        // Consensus should keep the stable letterbox crop rather than following the outlier.
+        assert_eq!(rect.y, 140);
+        assert_eq!(rect.height, 800);
+    }
+
+    #[test]
+    fn test_detect_crop_temporal_rounds_to_even() {
+        let samples = vec![(141, 938, 1, 1918); 10];
+        let rect = detect_crop_temporal(&samples, 1920, 1080);
+        assert_eq!(rect.x % 2, 0);
+        assert_eq!(rect.y % 2, 0);
+        assert_eq!(rect.width % 2, 0);
+        assert_eq!(rect.height % 2, 0);
+    }
 }
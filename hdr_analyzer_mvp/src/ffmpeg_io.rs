@@ -1,7 +1,12 @@
 use anyhow::{Context, Result};
 use ffmpeg_next as ffmpeg;
-use ffmpeg_next::{codec, format, media, util::color};
+use ffmpeg_next::{codec, ffi, format, frame, media, util::color};
 use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::slice;
 
 /// Video transfer function reported by FFmpeg metadata.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -25,13 +30,35 @@ impl From<color::TransferCharacteristic> for TransferFunction {
     fn from(value: color::TransferCharacteristic) -> Self {
         use color::TransferCharacteristic::*;
         match value {
-            SMPTE2084 | BT2020_10 | BT2020_12 => TransferFunction::Pq,
+            // BT2020_10/BT2020_12 are the SDR-range BT.2020 transfer curves (effectively
+            // BT.709's gamma, just with the wider BT.2020 primaries) -- not PQ -- so they must
+            // not be folded in here, or true SDR BT.2020 content gets misreported as HDR.
+            SMPTE2084 => TransferFunction::Pq,
             ARIB_STD_B67 => TransferFunction::Hlg,
             _ => TransferFunction::Unknown,
         }
     }
 }
 
+/// SMPTE ST 2086 mastering display color volume: the primaries/white point chromaticities (CIE
+/// 1931 x,y) and min/max display luminance (cd/m²) the source declares it was graded on, read
+/// from `AV_PKT_DATA_MASTERING_DISPLAY_METADATA` side data.
+#[derive(Clone, Copy, Debug)]
+pub struct MasteringDisplay {
+    /// Display primaries in R, G, B order.
+    pub display_primaries: [(f64, f64); 3],
+    pub white_point: (f64, f64),
+    pub min_luminance: f64,
+    pub max_luminance: f64,
+}
+
+/// MaxCLL/MaxFALL (cd/m²), read from `AV_PKT_DATA_CONTENT_LIGHT_LEVEL` side data.
+#[derive(Clone, Copy, Debug)]
+pub struct ContentLightLevel {
+    pub max_content_light_level: u32,
+    pub max_frame_average_light_level: u32,
+}
+
 /// Basic metadata about the input video stream needed by the analyzer pipeline.
 #[derive(Clone, Copy, Debug)]
 pub struct VideoInfo {
@@ -39,33 +66,180 @@ pub struct VideoInfo {
     pub height: u32,
     pub total_frames: Option<u32>,
     pub transfer_function: TransferFunction,
+    /// `Some` only when the source declares SMPTE ST 2086 mastering display metadata.
+    pub mastering_display: Option<MasteringDisplay>,
+    /// `Some` only when the source declares MaxCLL/MaxFALL content light level metadata.
+    pub content_light_level: Option<ContentLightLevel>,
 }
 
-/// Native video information extraction using ffmpeg-next.
-///
-/// This function replaces the external ffprobe process with native FFmpeg library calls
-/// to extract essential video metadata needed for analysis.
-///
-/// # Arguments
-/// * `input_path` - Path to the input video file
-///
-/// # Returns
-/// `Result<(VideoInfo, format::context::Input)>` - (video metadata, input_context)
-pub fn get_native_video_info(input_path: &str) -> Result<(VideoInfo, format::context::Input)> {
-    // Initialize FFmpeg
-    ffmpeg::init().context("Failed to initialize FFmpeg")?;
+// Bit flags for `AVCodecContext.thread_type` (`libavcodec/avcodec.h`'s `FF_THREAD_FRAME`/
+// `FF_THREAD_SLICE` `#define`s) -- these are preprocessor macros, not part of FFmpeg's ABI, so
+// the FFI bindings don't expose them as named constants; defined directly here instead.
+const FF_THREAD_FRAME: i32 = 1;
+const FF_THREAD_SLICE: i32 = 2;
+
+/// Frame/slice-threaded decoding parameters, threaded into [`get_native_video_info`] and
+/// [`setup_hardware_decoder`] so callers can tune decode parallelism instead of the single
+/// hardcoded auto-select thread count used previously.
+#[derive(Clone, Copy, Debug)]
+pub struct DecoderConfig {
+    /// Number of decode threads. 0 auto-selects `std::thread::available_parallelism()`.
+    pub thread_count: usize,
+    /// `AVCodecContext.thread_type` bitmask (`FF_THREAD_FRAME` and/or `FF_THREAD_SLICE`).
+    pub thread_type: i32,
+    /// Upper bound, in frames, on the estimate [`DecoderConfig::decode_latency_frames`] reports.
+    /// `None` defaults to the resolved thread count (frame-threaded decoders can buffer up to
+    /// one frame per thread before the first output frame appears).
+    pub max_frame_delay: Option<usize>,
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self {
+            thread_count: 0,
+            thread_type: FF_THREAD_FRAME | FF_THREAD_SLICE,
+            max_frame_delay: None,
+        }
+    }
+}
 
-    // Open input file
-    let input_context = format::input(input_path).context("Failed to open input video file")?;
+impl DecoderConfig {
+    /// Resolves `thread_count`, substituting `available_parallelism()` (falling back to 1 if it
+    /// can't be queried) for the auto-select value of 0.
+    pub fn resolved_thread_count(&self) -> usize {
+        if self.thread_count == 0 {
+            std::thread::available_parallelism()
+                .map(std::num::NonZeroUsize::get)
+                .unwrap_or(1)
+        } else {
+            self.thread_count
+        }
+    }
+
+    /// Estimated decode latency in frames: a frame-threaded decoder buffers up to one frame per
+    /// thread internally before its first output frame appears, so callers must push at least
+    /// this many frames before expecting output, and must keep draining this many frames past
+    /// EOF (`send_eof` + repeated `receive_frame`) to flush what's buffered. Capped at
+    /// `max_frame_delay` (default: the resolved thread count itself).
+    pub fn decode_latency_frames(&self) -> usize {
+        let resolved = self.resolved_thread_count();
+        resolved.min(self.max_frame_delay.unwrap_or(resolved))
+    }
+
+    /// Applies `thread_count`/`thread_type` to a not-yet-opened decoder context.
+    pub(crate) fn apply(&self, decoder_context: &mut codec::context::Context) {
+        // SAFETY: `decoder_context` hasn't been opened yet, so writing its thread_count/
+        // thread_type fields before `.decoder()` only configures the decoder's threading
+        // behavior before it starts.
+        unsafe {
+            let ctx = decoder_context.as_mut_ptr();
+            (*ctx).thread_count = self.resolved_thread_count() as i32;
+            (*ctx).thread_type = self.thread_type;
+        }
+    }
+}
+
+/// Finds a specific coded side-data entry's payload on `video_stream`'s codec parameters, if
+/// present. HDR10 static metadata (mastering display, content light level) is declared once for
+/// the whole stream rather than per-frame, so it lives here rather than on decoded packets.
+fn find_coded_side_data(
+    video_stream: &ffmpeg::format::stream::Stream,
+    side_data_type: ffi::AVPacketSideDataType,
+) -> Option<*const u8> {
+    // SAFETY: `video_stream.as_ptr()` is a valid `AVStream*` for the call's duration; `codecpar`
+    // and its `coded_side_data` array, when non-null, are owned by the stream and only read here.
+    unsafe {
+        let codecpar = (*video_stream.as_ptr()).codecpar;
+        if codecpar.is_null() {
+            return None;
+        }
+        let entries = (*codecpar).coded_side_data;
+        if entries.is_null() {
+            return None;
+        }
+        for i in 0..(*codecpar).nb_coded_side_data {
+            let entry = *entries.offset(i as isize);
+            if entry.type_ == side_data_type && !entry.data.is_null() {
+                return Some(entry.data);
+            }
+        }
+    }
+    None
+}
+
+/// Reads SMPTE ST 2086 mastering display metadata from `video_stream`'s coded side data, if the
+/// source declares it (and declares both primaries and luminance -- FFmpeg allows either half to
+/// be absent, in which case the metadata isn't usable).
+fn read_mastering_display(video_stream: &ffmpeg::format::stream::Stream) -> Option<MasteringDisplay> {
+    let ptr = find_coded_side_data(
+        video_stream,
+        ffi::AVPacketSideDataType::AV_PKT_DATA_MASTERING_DISPLAY_METADATA,
+    )? as *const ffi::AVMasteringDisplayMetadata;
+    // SAFETY: `ptr` was returned by `find_coded_side_data` for exactly this side-data type, so
+    // FFmpeg guarantees it points to a valid, fully-initialized `AVMasteringDisplayMetadata`.
+    let data = unsafe { *ptr };
+    if data.has_primaries == 0 || data.has_luminance == 0 {
+        return None;
+    }
+    let rational = |r: ffi::AVRational| r.num as f64 / r.den as f64;
+    Some(MasteringDisplay {
+        display_primaries: [
+            (
+                rational(data.display_primaries[0][0]),
+                rational(data.display_primaries[0][1]),
+            ),
+            (
+                rational(data.display_primaries[1][0]),
+                rational(data.display_primaries[1][1]),
+            ),
+            (
+                rational(data.display_primaries[2][0]),
+                rational(data.display_primaries[2][1]),
+            ),
+        ],
+        white_point: (
+            rational(data.white_point[0]),
+            rational(data.white_point[1]),
+        ),
+        min_luminance: rational(data.min_luminance),
+        max_luminance: rational(data.max_luminance),
+    })
+}
+
+/// Reads MaxCLL/MaxFALL content light level metadata from `video_stream`'s coded side data, if
+/// the source declares it.
+fn read_content_light_level(
+    video_stream: &ffmpeg::format::stream::Stream,
+) -> Option<ContentLightLevel> {
+    let ptr = find_coded_side_data(
+        video_stream,
+        ffi::AVPacketSideDataType::AV_PKT_DATA_CONTENT_LIGHT_LEVEL,
+    )? as *const ffi::AVContentLightMetadata;
+    // SAFETY: same guarantee as `read_mastering_display` above.
+    let data = unsafe { *ptr };
+    Some(ContentLightLevel {
+        max_content_light_level: data.MaxCLL,
+        max_frame_average_light_level: data.MaxFALL,
+    })
+}
 
+/// Probes an already-opened `input_context` for the [`VideoInfo`] the analyzer pipeline needs:
+/// resolution, frame count, transfer function, and HDR10 static metadata. Shared by the
+/// path-based and stream-based entry points below, since everything past "the container is
+/// open" is identical for both.
+fn probe_video_info(
+    input_context: &format::context::Input,
+    decoder_config: DecoderConfig,
+) -> Result<VideoInfo> {
     // Find the best video stream
     let video_stream = input_context
         .streams()
         .best(media::Type::Video)
         .context("No video stream found in input file")?;
 
-    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
+    let mut decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
         .context("Failed to create decoder context")?;
+    decoder_config.apply(&mut decoder_context);
     let transfer_characteristic =
         unsafe { color::TransferCharacteristic::from((*decoder_context.as_ptr()).color_trc) };
     let decoder = decoder_context
@@ -122,6 +296,8 @@ pub fn get_native_video_info(input_path: &str) -> Result<(VideoInfo, format::con
         .name()
         .unwrap_or("unspecified")
         .to_string();
+    let mastering_display = read_mastering_display(&video_stream);
+    let content_light_level = read_content_light_level(&video_stream);
 
     println!("Native video info: {}x{}", width, height);
     if let Some(frames) = frame_count {
@@ -131,72 +307,540 @@ pub fn get_native_video_info(input_path: &str) -> Result<(VideoInfo, format::con
         "Transfer function: {} ({})",
         transfer_label, transfer_function
     );
+    if let Some(mastering) = &mastering_display {
+        println!(
+            "Mastering display: max {:.1} cd/m2, min {:.4} cd/m2",
+            mastering.max_luminance, mastering.min_luminance
+        );
+    }
+    if let Some(cll) = &content_light_level {
+        println!(
+            "Content light level: MaxCLL {} cd/m2, MaxFALL {} cd/m2",
+            cll.max_content_light_level, cll.max_frame_average_light_level
+        );
+    }
+    println!(
+        "Decode threads: {} (estimated decode latency: {} frame(s))",
+        decoder_config.resolved_thread_count(),
+        decoder_config.decode_latency_frames()
+    );
     let info = VideoInfo {
         width,
         height,
         total_frames: frame_count,
         transfer_function,
+        mastering_display,
+        content_light_level,
+    };
+
+    Ok(info)
+}
+
+/// A caller-supplied byte source wired into a custom `AVIOContext` by
+/// [`get_native_video_info_from_reader`], in place of FFmpeg's own file/URL protocols. Mirrors
+/// FFmpeg's `read_packet`/`seek` callback contract (rather than `std::io::{Read, Seek}`
+/// directly) so a genuinely non-seekable source -- a pipe, a plain HTTP GET without range
+/// support -- can report that honestly instead of faking a `Seek` impl that always errors.
+pub trait AvioReader: Send {
+    /// Fills as much of `buf` as is currently available and returns the number of bytes written,
+    /// or `Ok(0)` at EOF. Mirrors `AVIOContext`'s `read_packet` callback.
+    fn read_packet(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Seeks to `pos` and returns the new absolute byte offset. The default implementation
+    /// reports the source as non-seekable; demuxers that need to probe backward (most container
+    /// formats do, to find an index or re-read a header) will fail to open over such a source.
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let _ = pos;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "stream does not support seeking",
+        ))
+    }
+}
+
+impl AvioReader for std::fs::File {
+    fn read_packet(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(self, buf)
+    }
+
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        io::Seek::seek(self, pos)
+    }
+}
+
+/// Size, in bytes, of the read buffer handed to `avio_alloc_context`. FFmpeg copies out of this
+/// buffer into its own demuxer-level buffering, so it only needs to be large enough to keep
+/// `read_packet` call overhead low, not to hold a whole GOP.
+const AVIO_BUFFER_SIZE: usize = 32 * 1024;
+
+/// `AVERROR(EIO)`: FFmpeg's generic I/O-error return code for `read_packet`/`seek` callbacks.
+/// `AVERROR` is a C preprocessor macro (`-e` for a positive POSIX errno), not something the FFI
+/// bindings expose as a function, so it's replicated here the same way `FF_THREAD_FRAME`/
+/// `FF_THREAD_SLICE` are above.
+const AVERROR_EIO: c_int = -5;
+
+/// Opaque state handed to FFmpeg as the `AVIOContext`'s `opaque` pointer: just the boxed reader,
+/// reachable from the `extern "C"` callbacks below via a raw pointer cast.
+struct AvioState {
+    reader: Box<dyn AvioReader>,
+}
+
+// SAFETY: called by FFmpeg as the `AVIOContext`'s `read_packet` callback; `opaque` is the
+// `*mut AvioState` this context was allocated with, and `buf` is a valid, writable buffer of
+// `buf_size` bytes for the duration of this call.
+unsafe extern "C" fn read_packet(opaque: *mut c_void, buf: *mut u8, buf_size: c_int) -> c_int {
+    let state = &mut *(opaque as *mut AvioState);
+    let out = slice::from_raw_parts_mut(buf, buf_size.max(0) as usize);
+    match state.reader.read_packet(out) {
+        Ok(0) => ffi::AVERROR_EOF,
+        Ok(n) => n as c_int,
+        Err(_) => AVERROR_EIO,
+    }
+}
+
+/// FFmpeg's `AVSEEK_SIZE` pseudo-whence (`avio.h`): report the stream's total size without
+/// moving the read position, rather than actually seeking. Not exposed by the FFI bindings
+/// (it's a `#define`, like the other constants replicated in this file).
+const AVSEEK_SIZE: c_int = 0x10000;
+
+// SAFETY: called by FFmpeg as the `AVIOContext`'s `seek` callback; `opaque` is the `*mut
+// AvioState` this context was allocated with.
+unsafe extern "C" fn seek_packet(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let state = &mut *(opaque as *mut AvioState);
+    let pos = match whence {
+        0 => io::SeekFrom::Start(offset as u64),
+        1 => io::SeekFrom::Current(offset),
+        2 => io::SeekFrom::End(offset),
+        _ => return -1, // AVSEEK_SIZE and anything else: not supported by this bridge.
     };
+    match state.reader.seek(pos) {
+        Ok(new_pos) => new_pos as i64,
+        Err(_) => -1,
+    }
+}
 
-    Ok((info, input_context))
+/// Owns the custom `AVIOContext`, its read buffer, and the boxed [`AvioReader`] that back a
+/// [`StreamVideoInput`]. `avformat_close_input` (run by `format::context::Input`'s own `Drop`)
+/// leaves a custom `pb` alone -- that's what setting `AVFMT_FLAG_CUSTOM_IO` tells it to do -- so
+/// this guard's own `Drop` is what frees the `AVIOContext`/buffer and the boxed reader. It must
+/// therefore outlive the `Input` it backs: see field order in [`StreamVideoInput`].
+struct AvioGuard {
+    ctx: *mut ffi::AVIOContext,
+    state: *mut AvioState,
+}
+
+// SAFETY: `ctx` and `state` are heap allocations exclusively owned by this guard; nothing else
+// holds a live reference to them across threads, so moving the guard across threads is sound.
+unsafe impl Send for AvioGuard {}
+
+impl Drop for AvioGuard {
+    fn drop(&mut self) {
+        // SAFETY: `ctx`/`state` were allocated together in `get_native_video_info_from_reader`
+        // and haven't been freed yet; freeing `ctx->buffer` before `avio_context_free` matches
+        // FFmpeg's own documented custom-AVIO teardown order (the buffer FFmpeg ends up using
+        // may not be the one it was allocated with, so it must be read off the context, not
+        // reused from where we allocated it).
+        unsafe {
+            if !self.ctx.is_null() {
+                ffi::av_freep(&mut (*self.ctx).buffer as *mut *mut u8 as *mut c_void);
+                ffi::avio_context_free(&mut self.ctx);
+            }
+            if !self.state.is_null() {
+                drop(Box::from_raw(self.state));
+            }
+        }
+    }
+}
+
+/// A [`format::context::Input`] opened over a caller-supplied [`AvioReader`] instead of a
+/// filesystem path, returned by [`get_native_video_info_from_reader`]. Derefs to the underlying
+/// `Input` so it drops straight into the existing decode/analysis code paths; the only
+/// difference from a plain `Input` is that dropping this also tears down the custom
+/// `AVIOContext` backing it, in the order that's actually safe (`Input` first, then the
+/// `AVIOContext`/reader -- field declaration order below is what makes that so).
+pub struct StreamVideoInput {
+    input: format::context::Input,
+    _avio: AvioGuard,
+}
+
+impl Deref for StreamVideoInput {
+    type Target = format::context::Input;
+
+    fn deref(&self) -> &Self::Target {
+        &self.input
+    }
+}
+
+impl DerefMut for StreamVideoInput {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.input
+    }
+}
+
+/// Native video information extraction using ffmpeg-next, reading from an arbitrary
+/// [`AvioReader`] rather than a filesystem path. This is what makes it possible to analyze a
+/// piped stream, a buffer that's already in memory, or a remote URL the caller opened itself --
+/// anything that never needs to hit local disk -- through the same native decode path as
+/// [`get_native_video_info`].
+///
+/// # Arguments
+/// * `reader` - boxed byte source backing the custom `AVIOContext`
+/// * `decoder_config` - Frame/slice-threading parameters applied to the probe decoder
+///
+/// # Returns
+/// `Result<(VideoInfo, StreamVideoInput)>` - (video metadata, input context + AVIO teardown guard)
+pub fn get_native_video_info_from_reader(
+    reader: Box<dyn AvioReader>,
+    decoder_config: DecoderConfig,
+) -> Result<(VideoInfo, StreamVideoInput)> {
+    ffmpeg::init().context("Failed to initialize FFmpeg")?;
+
+    let state = Box::into_raw(Box::new(AvioState { reader }));
+
+    // SAFETY: `AVIO_BUFFER_SIZE` is a fixed, non-zero size; `av_malloc` returns either a valid
+    // allocation of that size or null on failure, which is checked below.
+    let buffer = unsafe { ffi::av_malloc(AVIO_BUFFER_SIZE) as *mut u8 };
+    if buffer.is_null() {
+        // SAFETY: `state` was just allocated by the `Box::into_raw` above and hasn't been
+        // handed to FFmpeg yet, so reclaiming it here is sound.
+        unsafe {
+            drop(Box::from_raw(state));
+        }
+        anyhow::bail!("Failed to allocate AVIO read buffer");
+    }
+
+    // SAFETY: `buffer` is a valid `av_malloc`-allocated block of `AVIO_BUFFER_SIZE` bytes that
+    // FFmpeg takes ownership of; `state` outlives this call via the raw pointer stashed in
+    // `AvioGuard`/`StreamVideoInput` below. `read_packet`/`seek_packet` match the callback
+    // signatures `avio_alloc_context` expects.
+    let avio_ctx = unsafe {
+        ffi::avio_alloc_context(
+            buffer,
+            AVIO_BUFFER_SIZE as c_int,
+            0,
+            state as *mut c_void,
+            Some(read_packet),
+            None,
+            Some(seek_packet),
+        )
+    };
+    if avio_ctx.is_null() {
+        // SAFETY: `avio_alloc_context` failed without taking ownership of `buffer`/`state`.
+        unsafe {
+            ffi::av_free(buffer as *mut c_void);
+            drop(Box::from_raw(state));
+        }
+        anyhow::bail!("Failed to allocate AVIOContext");
+    }
+
+    let mut fmt_ctx = unsafe { ffi::avformat_alloc_context() };
+    if fmt_ctx.is_null() {
+        // SAFETY: nothing has taken ownership of `avio_ctx`/`buffer`/`state` yet, so this guard
+        // frees all three the same way `AvioGuard::drop` does.
+        unsafe {
+            let mut ctx = avio_ctx;
+            ffi::av_freep(&mut (*ctx).buffer as *mut *mut u8 as *mut c_void);
+            ffi::avio_context_free(&mut ctx);
+            drop(Box::from_raw(state));
+        }
+        anyhow::bail!("Failed to allocate AVFormatContext");
+    }
+
+    // SAFETY: `fmt_ctx` was just allocated and hasn't been opened yet, so wiring its `pb` and
+    // flags before `avformat_open_input` is sound. `AVFMT_FLAG_CUSTOM_IO` tells
+    // `avformat_close_input` not to free `pb` itself -- `AvioGuard` owns that instead.
+    unsafe {
+        (*fmt_ctx).pb = avio_ctx;
+        (*fmt_ctx).flags |= ffi::AVFMT_FLAG_CUSTOM_IO as i32;
+    }
+
+    // SAFETY: `fmt_ctx` is a valid, not-yet-opened `AVFormatContext` with a custom `pb` already
+    // attached; passing a null path tells the demuxer to probe via `pb` instead of opening a URL.
+    let ret = unsafe {
+        ffi::avformat_open_input(&mut fmt_ctx, ptr::null(), ptr::null_mut(), ptr::null_mut())
+    };
+    if ret < 0 {
+        // On failure `avformat_open_input` frees `fmt_ctx` itself, but -- because
+        // `AVFMT_FLAG_CUSTOM_IO` was set -- leaves `pb`/`buffer`/`state` for us to free.
+        unsafe {
+            let mut ctx = avio_ctx;
+            ffi::av_freep(&mut (*ctx).buffer as *mut *mut u8 as *mut c_void);
+            ffi::avio_context_free(&mut ctx);
+            drop(Box::from_raw(state));
+        }
+        anyhow::bail!("Failed to open input stream (error {})", ret);
+    }
+
+    let avio_guard = AvioGuard {
+        ctx: avio_ctx,
+        state,
+    };
+
+    // SAFETY: `fmt_ctx` is a fully opened `AVFormatContext` returned by `avformat_open_input`.
+    let input_context = unsafe { format::context::Input::wrap(fmt_ctx) };
+
+    let video_info = probe_video_info(&input_context, decoder_config)?;
+
+    Ok((
+        video_info,
+        StreamVideoInput {
+            input: input_context,
+            _avio: avio_guard,
+        },
+    ))
+}
+
+/// Native video information extraction using ffmpeg-next.
+///
+/// This function replaces the external ffprobe process with native FFmpeg library calls
+/// to extract essential video metadata needed for analysis. It's a thin wrapper over
+/// [`get_native_video_info_from_reader`]: the file is opened as a plain `std::fs::File` and
+/// handed in as the `AvioReader`, so the path- and stream-based entry points share one decode
+/// path end to end instead of diverging at the FFmpeg layer.
+///
+/// # Arguments
+/// * `input_path` - Path to the input video file
+/// * `decoder_config` - Frame/slice-threading parameters applied to the probe decoder
+///
+/// # Returns
+/// `Result<(VideoInfo, StreamVideoInput)>` - (video metadata, input context)
+pub fn get_native_video_info(
+    input_path: &str,
+    decoder_config: DecoderConfig,
+) -> Result<(VideoInfo, StreamVideoInput)> {
+    let file = std::fs::File::open(input_path)
+        .with_context(|| format!("Failed to open input video file: {}", input_path))?;
+    get_native_video_info_from_reader(Box::new(file), decoder_config)
+}
+
+// `get_format` (set below) has no user-data parameter beyond the `AVCodecContext` it's invoked
+// with, so the hw pixel format it should pick out of the decoder's offered list is threaded
+// through that context's `opaque` field instead of a thread-local. A thread-local doesn't work
+// here: `send_packet`/`receive_frame` run on a separate thread the pipeline spawns via
+// `std::thread::scope` for overlapped decode, which has its own independent thread-local
+// storage and never observes a value written on the thread that called `setup_hardware_decoder`.
+//
+// The `AVPixelFormat` written into `opaque` is intentionally leaked (`Box::into_raw`, never
+// freed) -- it's 4 bytes that need to outlive the decoder context, which has no teardown hook
+// for us to reclaim it in.
+unsafe fn set_wanted_hw_pixel_format(ctx: &mut codec::context::Context, wanted: ffi::AVPixelFormat) {
+    let slot = Box::into_raw(Box::new(wanted));
+    unsafe {
+        (*ctx.as_mut_ptr()).opaque = slot as *mut c_void;
+    }
+}
+
+// SAFETY: invoked by FFmpeg as the decoder's `get_format` callback; `pix_fmts` is a valid,
+// `AV_PIX_FMT_NONE`-terminated array owned by the decoder for the duration of this call, and
+// `ctx.opaque` was set by `set_wanted_hw_pixel_format` to a pointer from a live `Box<AVPixelFormat>`.
+unsafe extern "C" fn negotiate_hw_pixel_format(
+    ctx: *mut ffi::AVCodecContext,
+    mut pix_fmts: *const ffi::AVPixelFormat,
+) -> ffi::AVPixelFormat {
+    let wanted = unsafe { *((*ctx).opaque as *const ffi::AVPixelFormat) };
+    let first = pix_fmts;
+    while *pix_fmts != ffi::AVPixelFormat::AV_PIX_FMT_NONE {
+        if *pix_fmts == wanted {
+            return *pix_fmts;
+        }
+        pix_fmts = pix_fmts.add(1);
+    }
+    eprintln!(
+        "Warning: decoder did not offer the requested hardware pixel format, falling back to its first offered format"
+    );
+    *first
+}
+
+/// Maps a `--hwaccel` value to the `AVHWDeviceType` used to create its device context and the
+/// `AVPixelFormat` `negotiate_hw_pixel_format` should pick for it. Returns `None` for values with
+/// no generic `hw_device_ctx` path (currently just `"cuda"`, which uses the dedicated
+/// `hevc_cuvid` decoder instead) or values that aren't a recognized hwaccel at all.
+fn hw_device_type_for(hwaccel: &str) -> Option<(ffi::AVHWDeviceType, ffi::AVPixelFormat)> {
+    match hwaccel {
+        "vaapi" => Some((
+            ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+            ffi::AVPixelFormat::AV_PIX_FMT_VAAPI,
+        )),
+        "videotoolbox" => Some((
+            ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+            ffi::AVPixelFormat::AV_PIX_FMT_VIDEOTOOLBOX,
+        )),
+        _ => None,
+    }
+}
+
+/// Downloads a decoded hardware surface (VAAPI/VideoToolbox) into a normal CPU-side frame via
+/// `av_hwframe_transfer_data`, so the rest of the pipeline can read it exactly like a
+/// software-decoded frame. The returned frame's pixel format is whatever the hardware frames
+/// context's underlying software format is (e.g. NV12 or P010LE for 10-bit surfaces), not
+/// necessarily the hw surface format itself.
+pub fn download_hw_frame(hw_frame: &frame::Video) -> Result<frame::Video> {
+    let mut sw_frame = frame::Video::empty();
+    // SAFETY: `hw_frame` is a valid, fully-initialized `AVFrame` holding a hardware surface;
+    // `sw_frame` is empty and owned by us, and `av_hwframe_transfer_data` only allocates into it.
+    let ret =
+        unsafe { ffi::av_hwframe_transfer_data(sw_frame.as_mut_ptr(), hw_frame.as_ptr(), 0) };
+    if ret < 0 {
+        anyhow::bail!(
+            "Failed to transfer hardware-decoded frame to CPU memory (error {})",
+            ret
+        );
+    }
+    Ok(sw_frame)
 }
 
 /// Set up hardware-accelerated decoder based on the specified acceleration type.
 ///
+/// For `"vaapi"`/`"videotoolbox"` this creates a real `AVHWDeviceContext`, attaches it to the
+/// decoder via `hw_device_ctx`, and installs a `get_format` callback so FFmpeg negotiates the
+/// matching hardware pixel format; decoded frames come back as opaque hardware surfaces that
+/// must be downloaded with [`download_hw_frame`] before the analysis pipeline can read them.
+/// `"cuda"` keeps using the dedicated `hevc_cuvid` decoder, which hands back normal planar
+/// frames directly.
+///
+/// Because this is only called when the user explicitly asked for hardware acceleration via
+/// `--hwaccel`, a recognized hwaccel that fails to initialize is a hard error rather than a
+/// silent fallback; only an unrecognized `hwaccel` value falls back to software (with a
+/// warning), since that's a bad argument rather than unavailable hardware.
+///
 /// # Arguments
 /// * `decoder_context` - The decoder context to configure
 /// * `hwaccel` - Hardware acceleration type ("cuda", "vaapi", "videotoolbox")
+/// * `decoder_config` - Frame/slice-threading parameters applied to the decoder that's opened
 ///
 /// # Returns
-/// `Result<codec::decoder::Video>` - Configured hardware decoder
+/// `Result<(codec::decoder::Video, Option<format::Pixel>)>` - the decoder, and `Some(fmt)` with
+/// the hardware surface pixel format frames will arrive in if they need [`download_hw_frame`],
+/// or `None` if decoded frames are already plain CPU frames.
 pub fn setup_hardware_decoder(
-    decoder_context: codec::context::Context,
+    mut decoder_context: codec::context::Context,
     hwaccel: &str,
-) -> Result<codec::decoder::Video> {
-    match hwaccel {
-        "cuda" => {
-            // Try to find CUDA-specific decoder
-            if let Some(cuda_decoder) = codec::decoder::find_by_name("hevc_cuvid") {
-                let mut context = codec::context::Context::new_with_codec(cuda_decoder);
-                // Copy parameters from the original context
-                unsafe {
-                    (*context.as_mut_ptr()).width = (*decoder_context.as_ptr()).width;
-                    (*context.as_mut_ptr()).height = (*decoder_context.as_ptr()).height;
-                    (*context.as_mut_ptr()).pix_fmt = (*decoder_context.as_ptr()).pix_fmt;
-                }
-                context
-                    .decoder()
-                    .video()
-                    .context("Failed to create CUDA hardware decoder")
-            } else {
-                println!("CUDA decoder not available, falling back to software decoder");
-                decoder_context
-                    .decoder()
-                    .video()
-                    .context("Failed to create fallback software decoder")
+    decoder_config: DecoderConfig,
+) -> Result<(codec::decoder::Video, Option<format::Pixel>)> {
+    decoder_config.apply(&mut decoder_context);
+
+    if hwaccel == "cuda" {
+        let cuda_decoder = codec::decoder::find_by_name("hevc_cuvid")
+            .context("CUDA hardware acceleration requested but hevc_cuvid decoder is unavailable")?;
+        let mut context = codec::context::Context::new_with_codec(cuda_decoder);
+        // SAFETY: both contexts are valid and `context` hasn't been opened yet, so writing its
+        // dimensions/pixel format before `.decoder()` is sound.
+        unsafe {
+            (*context.as_mut_ptr()).width = (*decoder_context.as_ptr()).width;
+            (*context.as_mut_ptr()).height = (*decoder_context.as_ptr()).height;
+            (*context.as_mut_ptr()).pix_fmt = (*decoder_context.as_ptr()).pix_fmt;
+        }
+        decoder_config.apply(&mut context);
+        let decoder = context
+            .decoder()
+            .video()
+            .context("Failed to create CUDA hardware decoder")?;
+        return Ok((decoder, None));
+    }
+
+    let Some((device_type, av_pix_fmt)) = hw_device_type_for(hwaccel) else {
+        eprintln!(
+            "Warning: unknown hardware acceleration type '{}', using software decoder",
+            hwaccel
+        );
+        let decoder = decoder_context
+            .decoder()
+            .video()
+            .context("Failed to create software decoder")?;
+        return Ok((decoder, None));
+    };
+
+    let mut hw_device_ctx: *mut ffi::AVBufferRef = ptr::null_mut();
+    // SAFETY: `hw_device_ctx` is a valid out-pointer; on failure FFmpeg leaves it null and we
+    // don't touch it further.
+    let ret = unsafe {
+        ffi::av_hwdevice_ctx_create(&mut hw_device_ctx, device_type, ptr::null(), ptr::null_mut(), 0)
+    };
+    if ret < 0 {
+        anyhow::bail!(
+            "Failed to create {} hardware device context (error {})",
+            hwaccel,
+            ret
+        );
+    }
+
+    // SAFETY: `decoder_context` hasn't been opened yet, so setting `opaque`/`hw_device_ctx`/
+    // `get_format` on its raw `AVCodecContext` before `.decoder()` is sound. `av_buffer_ref` takes
+    // the codec context's own reference, so `hw_device_ctx` (our local reference) is unreffed
+    // right after.
+    unsafe {
+        set_wanted_hw_pixel_format(&mut decoder_context, av_pix_fmt);
+        let ctx = decoder_context.as_mut_ptr();
+        (*ctx).hw_device_ctx = ffi::av_buffer_ref(hw_device_ctx);
+        (*ctx).get_format = Some(negotiate_hw_pixel_format);
+        ffi::av_buffer_unref(&mut hw_device_ctx);
+    }
+
+    let decoder = decoder_context
+        .decoder()
+        .video()
+        .with_context(|| format!("Failed to open {} hardware decoder", hwaccel))?;
+    Ok((decoder, Some(format::Pixel::from(av_pix_fmt))))
+}
+
+/// Convert a P010LE/P016LE frame (16-bit Y plane followed by an interleaved CbCr plane, the
+/// layout VAAPI/NVDEC hand back for 10-bit surfaces) into the pipeline's internal
+/// YUV420P10LE layout without going through `swscale`.
+///
+/// A P01x sample's 10 significant bits sit in the *top* of its 16-bit container
+/// (left-justified), whereas YUV420P10LE keeps them in the *bottom* 10 bits -- so this is a
+/// per-sample right-shift-by-6, not a general pixel-format/color-space conversion, which is
+/// what makes it worth bypassing `swscale` for.
+///
+/// `convert_chroma` controls whether the interleaved CbCr plane is deinterleaved into the
+/// separate U/V planes `analyze_native_frame_cropped`'s hue histogram needs; skipping it (for
+/// `--luma-only`) avoids that work entirely.
+pub fn convert_p01x_to_yuv420p10le(frame: &frame::Video, convert_chroma: bool) -> frame::Video {
+    let width = frame.width();
+    let height = frame.height();
+    let mut out = frame::Video::new(format::Pixel::YUV420P10LE, width, height);
+
+    let src_y_stride = frame.stride(0);
+    let dst_y_stride = out.stride(0);
+    {
+        let src_y = frame.data(0).to_vec();
+        let dst_y = out.data_mut(0);
+        for row in 0..height as usize {
+            let src_row = &src_y[row * src_y_stride..];
+            let dst_row = &mut dst_y[row * dst_y_stride..];
+            for x in 0..width as usize {
+                let sample = u16::from_le_bytes([src_row[x * 2], src_row[x * 2 + 1]]) >> 6;
+                dst_row[x * 2..x * 2 + 2].copy_from_slice(&sample.to_le_bytes());
             }
         }
-        "vaapi" | "videotoolbox" => {
-            // For VAAPI and VideoToolbox, we'll use software decoding for now
-            // as hardware acceleration setup is more complex and requires device contexts
-            println!(
-                "Hardware acceleration {} requested, using software decoder for now",
-                hwaccel
-            );
-            decoder_context
-                .decoder()
-                .video()
-                .context("Failed to create software decoder")
-        }
-        _ => {
-            println!(
-                "Unknown hardware acceleration type '{}', using software decoder",
-                hwaccel
-            );
-            decoder_context
-                .decoder()
-                .video()
-                .context("Failed to create software decoder")
+    }
+
+    if convert_chroma {
+        let chroma_width = width.div_ceil(2) as usize;
+        let chroma_height = height.div_ceil(2) as usize;
+        let src_c_stride = frame.stride(1);
+        let src_c = frame.data(1).to_vec();
+        let dst_u_stride = out.stride(1);
+        let dst_v_stride = out.stride(2);
+
+        // Deinterleave into owned scratch planes first: `out.data_mut(1)`/`data_mut(2)` can't
+        // be borrowed from `out` at the same time as each other.
+        let mut u_plane = vec![0u8; dst_u_stride * chroma_height];
+        let mut v_plane = vec![0u8; dst_v_stride * chroma_height];
+        for row in 0..chroma_height {
+            let src_row = &src_c[row * src_c_stride..];
+            for x in 0..chroma_width {
+                let cb = u16::from_le_bytes([src_row[x * 4], src_row[x * 4 + 1]]) >> 6;
+                let cr = u16::from_le_bytes([src_row[x * 4 + 2], src_row[x * 4 + 3]]) >> 6;
+                u_plane[row * dst_u_stride + x * 2..row * dst_u_stride + x * 2 + 2]
+                    .copy_from_slice(&cb.to_le_bytes());
+                v_plane[row * dst_v_stride + x * 2..row * dst_v_stride + x * 2 + 2]
+                    .copy_from_slice(&cr.to_le_bytes());
+            }
         }
+        out.data_mut(1).copy_from_slice(&u_plane);
+        out.data_mut(2).copy_from_slice(&v_plane);
     }
+
+    out
 }
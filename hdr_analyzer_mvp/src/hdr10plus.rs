@@ -0,0 +1,274 @@
+//! HDR10+ (ST 2094-40) dynamic metadata JSON writer, parallel to [`crate::writer`]'s madVR
+//! `.bin` output. Everything it needs -- per-frame `lum_histogram`, `peak_pq_2020`, `avg_pq`,
+//! and per-scene peak/target stats -- already comes out of the analysis pipeline; this module
+//! just reshapes it into the JSON schema `hdr10plus_tool` reads, so the measurement data is
+//! usable by standard HDR10+ injectors and not only by madVR-aware tools.
+//!
+//! Percentiles and the Bezier curve are computed once per scene (not per frame) so the
+//! metadata stays stable across a shot instead of flickering frame to frame, matching how the
+//! rest of the pipeline treats scenes as the unit of stable tone-mapping decisions.
+
+use anyhow::{Context, Result};
+use madvr_parse::{MadVRFrame, MadVRScene};
+use serde::Serialize;
+
+use crate::analysis::histogram::{compute_histogram_percentile_pq, pq_to_nits};
+
+/// Distribution percentiles hdr10plus_tool's JSON expects, in increasing order.
+const DISTRIBUTION_PERCENTILES: [f64; 10] =
+    [1.0, 5.0, 10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0, 99.98];
+
+/// HDR10+ encodes luminance on a 0-10000 fixed-point nits scale.
+const HDR10PLUS_MAX_NITS: f64 = 10000.0;
+
+#[derive(Debug, Serialize)]
+struct Hdr10PlusJson {
+    #[serde(rename = "JSONInfo")]
+    json_info: JsonInfo,
+    #[serde(rename = "SceneInfo")]
+    scene_info: Vec<SceneInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonInfo {
+    #[serde(rename = "HDR10plusProfile")]
+    hdr10plus_profile: String,
+    #[serde(rename = "Version")]
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SceneInfo {
+    #[serde(rename = "SceneFrameIndex")]
+    scene_frame_index: u32,
+    #[serde(rename = "SceneId")]
+    scene_id: u32,
+    #[serde(rename = "SceneFirstFrameIndex")]
+    scene_first_frame_index: u32,
+    #[serde(rename = "LuminanceParameters")]
+    luminance_parameters: LuminanceParameters,
+    #[serde(rename = "Bezier")]
+    bezier: Bezier,
+    #[serde(rename = "NumberOfWindows")]
+    number_of_windows: u8,
+}
+
+#[derive(Debug, Serialize)]
+struct LuminanceParameters {
+    #[serde(rename = "AverageRGB")]
+    average_rgb: u32,
+    #[serde(rename = "LuminanceDistributionIndex")]
+    luminance_distribution_index: Vec<f64>,
+    #[serde(rename = "LuminanceDistributionValues")]
+    luminance_distribution_values: Vec<u32>,
+    /// MaxSCL, one value per channel. We have no per-channel RGB data (the pipeline only ever
+    /// samples luma), so all three channels are set to the same scene peak as a gray-axis
+    /// approximation -- the same kind of luminance-preserving approximation `writer.rs` already
+    /// uses for the per-gamut peaks in the madVR header.
+    #[serde(rename = "MaxScl")]
+    max_scl: [u32; 3],
+}
+
+#[derive(Debug, Serialize)]
+struct Bezier {
+    #[serde(rename = "KneePointX")]
+    knee_point_x: u32,
+    #[serde(rename = "KneePointY")]
+    knee_point_y: u32,
+    #[serde(rename = "Anchors")]
+    anchors: Vec<u32>,
+}
+
+/// Per-scene stats computed once, then reused for every frame in the scene so values stay
+/// stable across the shot rather than wobbling frame to frame.
+///
+/// `pub(crate)` so [`crate::bmff`] can pack the same numbers into its timed-metadata samples
+/// instead of re-deriving them from the histograms a second time.
+pub(crate) struct SceneStats {
+    pub(crate) distribution_values: Vec<u32>,
+    pub(crate) average_rgb: u32,
+    pub(crate) max_scl: u32,
+    pub(crate) knee_point_x: u32,
+    pub(crate) knee_point_y: u32,
+    pub(crate) anchors: Vec<u32>,
+}
+
+fn clamp_nits_u32(nits: f64) -> u32 {
+    nits.clamp(0.0, HDR10PLUS_MAX_NITS).round() as u32
+}
+
+/// Average the per-frame 256-bin `lum_histogram`s across a scene into one stable histogram.
+fn average_scene_histogram(frames: &[MadVRFrame]) -> Vec<f64> {
+    if frames.is_empty() {
+        return Vec::new();
+    }
+    let bins = frames[0].lum_histogram.len();
+    let mut averaged = vec![0.0; bins];
+    for frame in frames {
+        for (bin, value) in averaged.iter_mut().zip(frame.lum_histogram.iter()) {
+            *bin += value;
+        }
+    }
+    for bin in averaged.iter_mut() {
+        *bin /= frames.len() as f64;
+    }
+    averaged
+}
+
+pub(crate) fn compute_scene_stats(scene: &MadVRScene, frames: &[MadVRFrame]) -> SceneStats {
+    let averaged_histogram = average_scene_histogram(frames);
+    let distribution_values: Vec<u32> = DISTRIBUTION_PERCENTILES
+        .iter()
+        .map(|&p| {
+            clamp_nits_u32(pq_to_nits(compute_histogram_percentile_pq(
+                &averaged_histogram,
+                p,
+            )))
+        })
+        .collect();
+
+    let average_rgb = if frames.is_empty() {
+        0
+    } else {
+        let sum_nits: f64 = frames.iter().map(|f| pq_to_nits(f.avg_pq)).sum();
+        clamp_nits_u32(sum_nits / frames.len() as f64)
+    };
+
+    let max_scl = clamp_nits_u32(scene.peak_nits as f64);
+
+    // Target the average target_nits of the scene (falling back to half the scene peak when
+    // no optimizer target is present) as the Bezier knee point, with the scene peak as the
+    // curve's top anchor -- a straight-line fit between knee and peak, since we don't have the
+    // richer per-window curve data a full HDR10+ encoder would.
+    let target_nits: Vec<u32> = frames
+        .iter()
+        .filter_map(|f| f.target_nits)
+        .map(u32::from)
+        .collect();
+    let knee_point_y = if target_nits.is_empty() {
+        max_scl / 2
+    } else {
+        (target_nits.iter().sum::<u32>() / target_nits.len() as u32).min(max_scl)
+    };
+    let knee_point_x = knee_point_y;
+
+    let anchor_count = 9;
+    let anchors: Vec<u32> = (1..=anchor_count)
+        .map(|i| {
+            let t = i as f64 / (anchor_count + 1) as f64;
+            knee_point_y + ((max_scl.saturating_sub(knee_point_y)) as f64 * t).round() as u32
+        })
+        .collect();
+
+    SceneStats {
+        distribution_values,
+        average_rgb,
+        max_scl,
+        knee_point_x,
+        knee_point_y,
+        anchors,
+    }
+}
+
+/// Write an hdr10plus_tool-compatible dynamic metadata JSON file derived from the analysis
+/// pipeline's per-frame histograms and per-scene peak/target stats.
+pub fn write_hdr10plus_json(
+    output_path: &str,
+    scenes: &[MadVRScene],
+    frames: &[MadVRFrame],
+) -> Result<()> {
+    let mut scene_info = Vec::with_capacity(frames.len());
+
+    for (scene_id, scene) in scenes.iter().enumerate() {
+        let start = scene.start as usize;
+        let end = ((scene.end + 1) as usize).min(frames.len());
+        if start >= frames.len() || start >= end {
+            continue;
+        }
+        let scene_frames = &frames[start..end];
+        let stats = compute_scene_stats(scene, scene_frames);
+
+        for (offset, _frame) in scene_frames.iter().enumerate() {
+            scene_info.push(SceneInfo {
+                scene_frame_index: offset as u32,
+                scene_id: scene_id as u32,
+                scene_first_frame_index: scene.start,
+                luminance_parameters: LuminanceParameters {
+                    average_rgb: stats.average_rgb,
+                    luminance_distribution_index: DISTRIBUTION_PERCENTILES.to_vec(),
+                    luminance_distribution_values: stats.distribution_values.clone(),
+                    max_scl: [stats.max_scl; 3],
+                },
+                bezier: Bezier {
+                    knee_point_x: stats.knee_point_x,
+                    knee_point_y: stats.knee_point_y,
+                    anchors: stats.anchors.clone(),
+                },
+                number_of_windows: 1,
+            });
+        }
+    }
+
+    let document = Hdr10PlusJson {
+        json_info: JsonInfo {
+            hdr10plus_profile: "B".to_string(),
+            version: "1.0".to_string(),
+        },
+        scene_info,
+    };
+
+    let json = serde_json::to_string_pretty(&document)
+        .context("Failed to serialize HDR10+ dynamic metadata to JSON")?;
+    std::fs::write(output_path, json)
+        .context("Failed to write HDR10+ dynamic metadata JSON file")?;
+
+    println!(
+        "Successfully wrote HDR10+ dynamic metadata: {}",
+        output_path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_with(avg_pq: f64, target_nits: Option<u16>) -> MadVRFrame {
+        MadVRFrame {
+            avg_pq,
+            target_nits,
+            lum_histogram: vec![100.0 / 256.0; 256],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_average_scene_histogram_uniform() {
+        let frames = vec![frame_with(0.5, None), frame_with(0.5, None)];
+        let averaged = average_scene_histogram(&frames);
+        assert_eq!(averaged.len(), 256);
+        assert!((averaged[0] - 100.0 / 256.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compute_scene_stats_knee_defaults_to_half_peak() {
+        let scene = MadVRScene {
+            start: 0,
+            end: 1,
+            peak_nits: 1000,
+            avg_pq: 0.5,
+            ..Default::default()
+        };
+        let frames = vec![frame_with(0.5, None), frame_with(0.5, None)];
+        let stats = compute_scene_stats(&scene, &frames);
+        assert_eq!(stats.max_scl, 1000);
+        assert_eq!(stats.knee_point_y, 500);
+        assert_eq!(stats.anchors.len(), 9);
+    }
+
+    #[test]
+    fn test_clamp_nits_u32_bounds() {
+        assert_eq!(clamp_nits_u32(-5.0), 0);
+        assert_eq!(clamp_nits_u32(20000.0), 10000);
+    }
+}
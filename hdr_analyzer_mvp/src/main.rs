@@ -4,20 +4,26 @@ use anyhow::Result;
 mod crop;
 
 mod analysis;
+mod bmff;
 mod cli;
+mod config_file;
 mod ffmpeg_io;
+mod hdr10plus;
 mod optimizer;
 mod pipeline;
+mod tonemap;
 mod writer;
+mod zones;
 
 use clap::Parser;
 use cli::Cli;
 
-use ffmpeg_io::get_native_video_info;
+use ffmpeg_io::{get_native_video_info, DecoderConfig};
 use pipeline::run;
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let args = config_file::resolve_args(std::env::args().collect())?;
+    let cli = Cli::parse_from(args);
 
     let input_path = match (&cli.input_positional, &cli.input_flag) {
         (Some(pos), None) => pos.clone(),
@@ -53,7 +59,12 @@ fn main() -> Result<()> {
         input_path
     );
 
-    let (video_info, input_context) = get_native_video_info(&input_path)?;
+    let decoder_config = DecoderConfig {
+        thread_count: cli.decode_threads,
+        max_frame_delay: cli.max_frame_delay,
+        ..Default::default()
+    };
+    let (video_info, input_context) = get_native_video_info(&input_path, decoder_config)?;
     println!(
         "Video resolution: {}x{}",
         video_info.width, video_info.height
@@ -62,6 +73,12 @@ fn main() -> Result<()> {
         println!("Total frames: {}", frames);
     }
 
+    if cli.sdr_preview.is_some() {
+        tonemap::render_sdr_preview(&cli, &video_info)?;
+        println!("Native analysis complete!");
+        return Ok(());
+    }
+
     run(&cli, &video_info, input_context)?;
 
     println!("Native analysis complete!");
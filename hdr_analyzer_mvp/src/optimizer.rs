@@ -1,12 +1,14 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use madvr_parse::{MadVRFrame, MadVRScene};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::io::Write;
+use std::path::Path;
 
 use crate::analysis::histogram::{find_highlight_knee_nits, pq_to_nits};
 
 // --- Optimizer Profile Configuration ---
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct OptimizerProfile {
     /// Maximum delta per frame for target_nits (lower = smoother, higher = more responsive)
     pub max_delta_per_frame: u16,
@@ -71,17 +73,79 @@ impl OptimizerProfile {
         }
     }
 
+    /// Resolve a profile by name, trying the three built-in presets first and otherwise
+    /// treating `name` as a path to a user-defined TOML profile file.
     pub fn from_name(name: &str) -> Result<Self> {
         match name.to_lowercase().as_str() {
             "conservative" => Ok(Self::conservative()),
             "balanced" => Ok(Self::balanced()),
             "aggressive" => Ok(Self::aggressive()),
-            _ => Err(anyhow::anyhow!(
-                "Invalid optimizer profile: '{}'. Valid options: conservative, balanced, aggressive",
-                name
-            )),
+            _ => Self::from_path(Path::new(name)).with_context(|| {
+                format!(
+                    "Invalid optimizer profile: '{}'. Valid options: conservative, balanced, \
+                     aggressive, or a path to a TOML profile file",
+                    name
+                )
+            }),
         }
     }
+
+    /// Load a profile from a TOML file, so power users can tune `max_delta_per_frame`, the
+    /// scene-clamp ranges, the knee multipliers, `extreme_peak_threshold`, and
+    /// `knee_smoothing_window` without recompiling.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| {
+            format!("Failed to read optimizer profile file: {}", path.display())
+        })?;
+        let profile: Self = toml::from_str(&content).with_context(|| {
+            format!("Failed to parse optimizer profile TOML: {}", path.display())
+        })?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Validate a loaded profile's invariants: clamp ranges must be non-inverted, knee
+    /// multipliers must not darken highlights below 1.0x, and the smoothing window must be
+    /// at least one frame.
+    fn validate(&self) -> Result<()> {
+        for (name, (min, max)) in [
+            ("dark_scene_clamp", self.dark_scene_clamp),
+            ("medium_scene_clamp", self.medium_scene_clamp),
+            ("bright_scene_clamp", self.bright_scene_clamp),
+        ] {
+            if min > max {
+                return Err(anyhow::anyhow!(
+                    "{} has an inverted range: min ({}) > max ({})",
+                    name,
+                    min,
+                    max
+                ));
+            }
+        }
+
+        for (name, multiplier) in [
+            ("dark_knee_multiplier", self.dark_knee_multiplier),
+            ("medium_knee_multiplier", self.medium_knee_multiplier),
+            ("bright_knee_multiplier", self.bright_knee_multiplier),
+        ] {
+            if multiplier < 1.0 {
+                return Err(anyhow::anyhow!(
+                    "{} must be >= 1.0, got {}",
+                    name,
+                    multiplier
+                ));
+            }
+        }
+
+        if self.knee_smoothing_window < 1 {
+            return Err(anyhow::anyhow!(
+                "knee_smoothing_window must be >= 1, got {}",
+                self.knee_smoothing_window
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 /// Advanced optimizer with rolling averages and scene-aware heuristics.
@@ -100,11 +164,13 @@ impl OptimizerProfile {
 /// # Arguments
 /// * `scenes` - Scene metadata for scene-aware processing
 /// * `frames` - Mutable slice of frame data to optimize
-/// * `profile` - Optimizer profile configuration
+/// * `scene_profiles` - Optimizer profile to use for each scene, one per entry in `scenes`
+///   (lets a zones file pin a different profile to specific scenes; callers with no zones
+///   just repeat the same profile for every scene)
 pub fn run_optimizer_pass(
     scenes: &[MadVRScene],
     frames: &mut [MadVRFrame],
-    profile: &OptimizerProfile,
+    scene_profiles: &[OptimizerProfile],
 ) {
     const ROLLING_WINDOW_SIZE: usize = 240; // 240 frames as recommended by research
 
@@ -117,7 +183,7 @@ pub fn run_optimizer_pass(
     let mut processed = 0usize;
     let mut prev_target: Option<u16> = None;
 
-    for scene in scenes {
+    for (scene, profile) in scenes.iter().zip(scene_profiles.iter()) {
         let start = scene.start as usize;
         let end = ((scene.end + 1) as usize).min(frames.len());
         if start >= end {
@@ -257,6 +323,152 @@ pub fn apply_target_smoother(
     }
 }
 
+/// Apply zero-phase Gaussian smoothing to target_nits per scene.
+///
+/// Unlike `apply_target_smoother`'s EMA path, this builds a symmetric finite-impulse kernel
+/// from `sigma` and convolves it across each scene's target_nits sequence, avoiding EMA's lag
+/// and overshoot on oscillating targets. Boundary samples are clamp-extended so the kernel
+/// stays normalized at scene edges. After smoothing, re-apply delta limiting with the provided
+/// max_delta to maintain temporal stability, matching the EMA path.
+pub fn apply_gaussian_target_smoother(
+    scenes: &[MadVRScene],
+    frames: &mut [MadVRFrame],
+    sigma: f64,
+    max_delta: u16,
+) {
+    if sigma <= 0.0 {
+        return;
+    }
+
+    let radius = (3.0 * sigma).ceil() as usize;
+    let kernel = gaussian_kernel(radius, sigma);
+
+    for scene in scenes {
+        let start = scene.start as usize;
+        let end = ((scene.end + 1) as usize).min(frames.len());
+        if start >= end {
+            continue;
+        }
+
+        let mut values: Vec<f64> = Vec::with_capacity(end - start);
+        let mut any_none = false;
+        for f in frames.iter().take(end).skip(start) {
+            if let Some(v) = f.target_nits {
+                values.push(v as f64);
+            } else {
+                any_none = true;
+                break;
+            }
+        }
+        if any_none || values.is_empty() {
+            continue;
+        }
+
+        let smoothed = convolve_clamped(&values, &kernel, radius);
+
+        let mut prev: Option<u16> = None;
+        for (idx, f) in frames.iter_mut().take(end).skip(start).enumerate() {
+            let desired = smoothed[idx].round().clamp(0.0, u16::MAX as f64) as u16;
+            let limited = apply_delta_limit(prev, desired, max_delta);
+            f.target_nits = Some(limited);
+            prev = Some(limited);
+        }
+    }
+}
+
+/// Build a symmetric Gaussian kernel of half-width `radius`, normalized to sum to 1.
+fn gaussian_kernel(radius: usize, sigma: f64) -> Vec<f64> {
+    let two_sigma_sq = 2.0 * sigma * sigma;
+    let mut kernel: Vec<f64> = (-(radius as isize)..=radius as isize)
+        .map(|i| (-((i * i) as f64) / two_sigma_sq).exp())
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    if sum > 0.0 {
+        for w in &mut kernel {
+            *w /= sum;
+        }
+    }
+    kernel
+}
+
+/// Convolve `values` with `kernel` (half-width `radius`), clamp-extending out-of-range samples
+/// to the nearest boundary value so the kernel's normalization still holds at scene edges.
+fn convolve_clamped(values: &[f64], kernel: &[f64], radius: usize) -> Vec<f64> {
+    let last = values.len() as isize - 1;
+    (0..values.len())
+        .map(|i| {
+            kernel
+                .iter()
+                .enumerate()
+                .map(|(k, w)| {
+                    let offset = k as isize - radius as isize;
+                    let idx = (i as isize + offset).clamp(0, last) as usize;
+                    w * values[idx]
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Lookahead target planner: pre-ramps target_nits ahead of a sustained rise or drop so that
+/// `apply_delta_limit`'s slope reaches the upcoming level exactly when the new content starts,
+/// instead of visibly popping at the scene boundary. Unlike the per-scene smoothers above, this
+/// intentionally looks across scene cuts within `horizon` frames — that's precisely the case
+/// (a dark scene immediately followed by a bright one) the causal delta limiter can't
+/// anticipate on its own. `horizon` of 0 disables the pass.
+pub fn apply_lookahead_plan(frames: &mut [MadVRFrame], horizon: usize, max_delta: u16) {
+    if horizon == 0 || frames.is_empty() {
+        return;
+    }
+
+    let n = frames.len();
+    let raw: Vec<Option<i64>> = frames
+        .iter()
+        .map(|f| f.target_nits.map(|v| v as i64))
+        .collect();
+    if raw.iter().all(Option::is_none) {
+        return;
+    }
+
+    let mut plan: Vec<i64> = raw.iter().map(|v| v.unwrap_or(0)).collect();
+    let step = max_delta as i64;
+
+    // Pass 1: back-propagate future rises so the slope can reach them in time.
+    for i in (0..n).rev() {
+        let end = (i + horizon).min(n - 1);
+        for j in (i + 1)..=end {
+            let needed = plan[j] - (j - i) as i64 * step;
+            if needed > plan[i] {
+                plan[i] = needed;
+            }
+        }
+    }
+
+    // Pass 2: symmetric ease-down ahead of anticipated drops.
+    for i in (0..n).rev() {
+        let end = (i + horizon).min(n - 1);
+        for j in (i + 1)..=end {
+            let needed = plan[j] + (j - i) as i64 * step;
+            if needed < plan[i] {
+                plan[i] = needed;
+            }
+        }
+    }
+
+    // Re-apply delta limiting causally so the final slope invariant holds exactly.
+    let mut prev: Option<u16> = None;
+    for (idx, f) in frames.iter_mut().enumerate() {
+        if raw[idx].is_none() {
+            prev = None;
+            continue;
+        }
+        let desired = plan[idx].clamp(0, u16::MAX as i64) as u16;
+        let limited = apply_delta_limit(prev, desired, max_delta);
+        f.target_nits = Some(limited);
+        prev = Some(limited);
+    }
+}
+
 /// Apply advanced optimization heuristics to determine target nits.
 ///
 /// This function implements the core tone mapping logic using multiple
@@ -350,6 +562,7 @@ mod tests {
         assert!(OptimizerProfile::from_name("aggressive").is_ok());
         assert!(OptimizerProfile::from_name("BALANCED").is_ok()); // Case insensitive
         assert!(OptimizerProfile::from_name("invalid").is_err());
+        assert!(OptimizerProfile::from_name("/no/such/profile.toml").is_err());
 
         // Test profile properties
         let conservative = OptimizerProfile::from_name("conservative").unwrap();
@@ -364,6 +577,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_optimizer_profile_from_path_round_trip() {
+        let toml_body = r#"
+            max_delta_per_frame = 150
+            extreme_peak_threshold = 3800
+            dark_scene_clamp = [700, 1600]
+            medium_scene_clamp = [550, 1300]
+            bright_scene_clamp = [450, 950]
+            dark_knee_multiplier = 1.15
+            medium_knee_multiplier = 1.08
+            bright_knee_multiplier = 1.0
+            knee_smoothing_window = 7
+        "#;
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_profile_ok.toml");
+        std::fs::write(&path, toml_body).unwrap();
+
+        let profile = OptimizerProfile::from_path(&path).unwrap();
+        assert_eq!(profile.max_delta_per_frame, 150);
+        assert_eq!(profile.dark_scene_clamp, (700, 1600));
+        assert_eq!(profile.knee_smoothing_window, 7);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_optimizer_profile_from_path_rejects_invalid_ranges() {
+        let toml_body = r#"
+            max_delta_per_frame = 150
+            extreme_peak_threshold = 3800
+            dark_scene_clamp = [1600, 700]
+            medium_scene_clamp = [550, 1300]
+            bright_scene_clamp = [450, 950]
+            dark_knee_multiplier = 1.15
+            medium_knee_multiplier = 1.08
+            bright_knee_multiplier = 1.0
+            knee_smoothing_window = 7
+        "#;
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_profile_bad_range.toml");
+        std::fs::write(&path, toml_body).unwrap();
+
+        assert!(OptimizerProfile::from_path(&path).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_optimizer_profile_from_path_rejects_sub_unity_knee_multiplier() {
+        let toml_body = r#"
+            max_delta_per_frame = 150
+            extreme_peak_threshold = 3800
+            dark_scene_clamp = [700, 1600]
+            medium_scene_clamp = [550, 1300]
+            bright_scene_clamp = [450, 950]
+            dark_knee_multiplier = 0.9
+            medium_knee_multiplier = 1.08
+            bright_knee_multiplier = 1.0
+            knee_smoothing_window = 7
+        "#;
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_profile_bad_knee.toml");
+        std::fs::write(&path, toml_body).unwrap();
+
+        assert!(OptimizerProfile::from_path(&path).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_apply_target_smoother_reduces_variation() {
         // Build a synthetic scene with oscillating targets
@@ -435,4 +714,139 @@ mod tests {
         assert_eq!(frames[0].target_nits.unwrap(), 1000);
         assert_eq!(frames[5].target_nits.unwrap(), 500);
     }
+
+    #[test]
+    fn test_apply_gaussian_target_smoother_reduces_variation() {
+        let mut frames: Vec<MadVRFrame> = (0..10)
+            .map(|i| MadVRFrame {
+                target_nits: Some(if i % 2 == 0 { 1000 } else { 500 }),
+                ..Default::default()
+            })
+            .collect();
+        let scenes = vec![MadVRScene {
+            start: 0,
+            end: 9,
+            ..Default::default()
+        }];
+
+        apply_gaussian_target_smoother(&scenes, &mut frames, 2.0, 300);
+
+        let mut max_delta = 0u16;
+        for w in frames.windows(2) {
+            let a = w[0].target_nits.unwrap();
+            let b = w[1].target_nits.unwrap();
+            let d = if a > b { a - b } else { b - a };
+            if d > max_delta {
+                max_delta = d;
+            }
+        }
+        assert!(
+            max_delta < 500,
+            "Gaussian smoother should reduce large adjacent deltas"
+        );
+    }
+
+    #[test]
+    fn test_apply_gaussian_target_smoother_resets_per_scene() {
+        let mut frames: Vec<MadVRFrame> = Vec::new();
+        for _ in 0..5 {
+            frames.push(MadVRFrame {
+                target_nits: Some(1000),
+                ..Default::default()
+            });
+        }
+        for _ in 0..5 {
+            frames.push(MadVRFrame {
+                target_nits: Some(500),
+                ..Default::default()
+            });
+        }
+        let scenes = vec![
+            MadVRScene {
+                start: 0,
+                end: 4,
+                ..Default::default()
+            },
+            MadVRScene {
+                start: 5,
+                end: 9,
+                ..Default::default()
+            },
+        ];
+
+        apply_gaussian_target_smoother(&scenes, &mut frames, 2.0, 300);
+
+        assert_eq!(frames[0].target_nits.unwrap(), 1000);
+        assert_eq!(frames[5].target_nits.unwrap(), 500);
+    }
+
+    #[test]
+    fn test_apply_lookahead_plan_pre_ramps_before_bright_scene() {
+        // Dark scene (target 200) immediately followed by a bright scene (target 2000).
+        let mut frames: Vec<MadVRFrame> = (0..20)
+            .map(|i| MadVRFrame {
+                target_nits: Some(if i < 10 { 200 } else { 2000 }),
+                ..Default::default()
+            })
+            .collect();
+
+        apply_lookahead_plan(&mut frames, 20, 100);
+
+        // With a 100/frame slope and a 1800 nit jump, the planner needs ~18 frames of
+        // head start; the target should already be climbing well before frame 10.
+        assert!(
+            frames[5].target_nits.unwrap() > 200,
+            "planner should start ramping up before the bright scene arrives"
+        );
+        // And by the time the bright scene starts, the delta limit should have caught up.
+        assert_eq!(frames[10].target_nits.unwrap(), 2000);
+    }
+
+    #[test]
+    fn test_apply_lookahead_plan_eases_down_before_dark_cut() {
+        // Bright scene (target 2000) immediately followed by a dark scene (target 200).
+        let mut frames: Vec<MadVRFrame> = (0..20)
+            .map(|i| MadVRFrame {
+                target_nits: Some(if i < 10 { 2000 } else { 200 }),
+                ..Default::default()
+            })
+            .collect();
+
+        apply_lookahead_plan(&mut frames, 20, 100);
+
+        assert!(
+            frames[5].target_nits.unwrap() < 2000,
+            "planner should start easing down before the dark cut arrives"
+        );
+        assert_eq!(frames[10].target_nits.unwrap(), 200);
+    }
+
+    #[test]
+    fn test_apply_lookahead_plan_disabled_when_horizon_zero() {
+        let mut frames: Vec<MadVRFrame> = (0..10)
+            .map(|i| MadVRFrame {
+                target_nits: Some(if i < 5 { 200 } else { 2000 }),
+                ..Default::default()
+            })
+            .collect();
+        let original: Vec<u16> = frames.iter().map(|f| f.target_nits.unwrap()).collect();
+
+        apply_lookahead_plan(&mut frames, 0, 100);
+
+        let after: Vec<u16> = frames.iter().map(|f| f.target_nits.unwrap()).collect();
+        assert_eq!(original, after, "horizon=0 must be a no-op");
+    }
+
+    #[test]
+    fn test_gaussian_kernel_is_normalized_and_symmetric() {
+        let kernel = gaussian_kernel(5, 2.0);
+        let sum: f64 = kernel.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-9, "kernel should sum to 1");
+        for i in 0..kernel.len() / 2 {
+            assert!(
+                (kernel[i] - kernel[kernel.len() - 1 - i]).abs() < 1e-12,
+                "kernel should be symmetric"
+            );
+        }
+    }
 }
@@ -1,8 +1,12 @@
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use madvr_parse::{MadVRFrame, MadVRScene};
+use rayon::prelude::*;
 
 /// Create a copy of a MadVRFrame (MadVRFrame doesn't implement Clone)
 fn copy_frame(frame: &MadVRFrame) -> MadVRFrame {
@@ -22,18 +26,30 @@ use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 
 use ffmpeg_next::{codec, format, frame, software};
 
-use crate::analysis::frame::analyze_native_frame_cropped;
+use crate::analysis::frame::{
+    analyze_native_frame_cropped, compute_edge_histogram, compute_intensity_stats,
+    downsample_luma_thumbnail, luma_mad, TemporalDenoiseRing,
+};
 use crate::analysis::histogram::{
-    apply_histogram_ema, apply_histogram_temporal_median, select_peak_pq,
+    apply_histogram_ema, apply_histogram_temporal_median, compute_chroma_histogram, sample_ycbcr,
+    select_peak_pq,
 };
+use crate::analysis::log_histogram::LogHistogram;
+use crate::analysis::palette::extract_palette;
 use crate::analysis::scene::{
-    calculate_histogram_difference, convert_scene_cuts_to_scenes, cut_allowed,
+    aggregate_scene_rollups, calculate_histogram_difference, convert_scene_cuts_to_scenes,
+    cut_allowed, detect_scene_boundaries_from_diffs, detect_scene_boundaries_sad,
+    hist_scenecut_diff, variance_gate_confirms, AdaptiveSceneDetector, FadeDetector,
+    FlashFadeFilter, GridPrescanConfig, SceneBoundary, SceneDetectMode,
 };
 use crate::cli::Cli;
 use crate::crop::CropRect;
-use crate::ffmpeg_io::{setup_hardware_decoder, TransferFunction, VideoInfo};
+use crate::ffmpeg_io::{
+    setup_hardware_decoder, DecoderConfig, StreamVideoInput, TransferFunction, VideoInfo,
+};
 use crate::optimizer::{run_optimizer_pass, OptimizerProfile};
 use crate::writer::write_measurement_file;
+use crate::zones::{self, Zone};
 
 pub fn format_duration(duration: Duration) -> String {
     let total_seconds = duration.as_secs();
@@ -46,7 +62,7 @@ pub fn format_duration(duration: Duration) -> String {
 pub fn run(
     cli: &Cli,
     video_info: &VideoInfo,
-    mut input_context: format::context::Input,
+    mut input_context: StreamVideoInput,
 ) -> Result<()> {
     match video_info.transfer_function {
         TransferFunction::Hlg => {
@@ -63,31 +79,89 @@ pub fn run(
         TransferFunction::Pq => {}
     }
 
-    if cli.scene_metric.to_lowercase() == "hybrid" {
-        println!("Scene metric: hybrid (prototype, using histogram-only for now)");
+    match cli.scene_metric.to_lowercase().as_str() {
+        "flow" => println!("Scene metric: flow (luma motion MAD only)"),
+        "hybrid" => println!(
+            "Scene metric: hybrid (histogram weight {:.2}, flow weight {:.2})",
+            cli.scene_metric_weight,
+            1.0 - cli.scene_metric_weight
+        ),
+        _ => println!("Scene metric: histogram"),
     }
 
-    let (mut scenes, mut frames) =
-        run_native_analysis_pipeline(cli, video_info, &mut input_context)?;
+    let zones = match &cli.zones_file {
+        Some(path) => {
+            let zones = zones::parse_zones_file(std::path::Path::new(path))?;
+            println!("Loaded {} zone override(s) from {}", zones.len(), path);
+            zones
+        }
+        None => Vec::new(),
+    };
+
+    let (mut scenes, mut frames, log_histograms, ycbcr_samples) =
+        run_native_analysis_pipeline(cli, video_info, &mut input_context, &zones)?;
 
     fix_scene_end_frames(&mut scenes, frames.len());
 
     // Apply histogram smoothing with scene-aware EMA reset (if enabled)
     if cli.hist_bin_ema_beta > 0.0 || cli.hist_temporal_median > 0 {
-        apply_histogram_smoothing_pass(&scenes, &mut frames, cli)?;
+        apply_histogram_smoothing_pass(&scenes, &mut frames, &log_histograms, cli, &zones)?;
     }
 
     precompute_scene_stats(&mut scenes, &frames);
 
+    if cli.scene_cut_sad_diagnostic {
+        run_scene_cut_sad_diagnostic(cli, &frames);
+    }
+
+    if cli.scene_prescan {
+        let input_path = cli
+            .input_positional
+            .as_ref()
+            .unwrap_or(cli.input_flag.as_ref().unwrap());
+        run_scene_prescan_diagnostic(cli, input_path, &frames);
+    }
+
+    if cli.scene_palette {
+        run_scene_palette_diagnostic(cli, &scenes, &ycbcr_samples);
+    }
+
     let optimizer_enabled = !cli.disable_optimizer;
     let mut selected_profile: Option<OptimizerProfile> = None;
     if optimizer_enabled {
         println!("Running intelligent optimizer pass...");
         let optimizer_profile = OptimizerProfile::from_name(&cli.optimizer_profile)?;
-        run_optimizer_pass(&scenes, &mut frames, &optimizer_profile);
+        let mut scene_profiles = Vec::with_capacity(scenes.len());
+        for scene in &scenes {
+            let profile = match zones::zone_covering(&zones, scene.start)
+                .and_then(|zone| zone.optimizer_profile.as_deref())
+            {
+                Some(name) => OptimizerProfile::from_name(name)?,
+                None => optimizer_profile,
+            };
+            scene_profiles.push(profile);
+        }
+        run_optimizer_pass(&scenes, &mut frames, &scene_profiles);
         selected_profile = Some(optimizer_profile);
     }
 
+    // Optional lookahead planning pass: pre-ramp target_nits ahead of bright/dark transitions
+    // before any further smoothing is applied.
+    if optimizer_enabled && cli.lookahead_horizon > 0 {
+        let max_delta = selected_profile
+            .map(|p| p.max_delta_per_frame)
+            .unwrap_or(200);
+        println!(
+            "Applying lookahead target planner (horizon={} frames)...",
+            cli.lookahead_horizon
+        );
+        crate::optimizer::apply_lookahead_plan(
+            &mut frames,
+            cli.lookahead_horizon as usize,
+            max_delta,
+        );
+    }
+
     // Optional post-optimization target_nits smoothing
     if optimizer_enabled && cli.target_smoother.to_lowercase() == "ema" {
         let alpha = cli.smoother_alpha.clamp(0.0, 1.0);
@@ -107,6 +181,36 @@ pub fn run(
             max_delta,
         );
         println!("Target_nits smoothing complete.");
+    } else if optimizer_enabled && cli.target_smoother.to_lowercase() == "gaussian" {
+        let sigma = cli.smoother_sigma;
+        let max_delta = selected_profile
+            .map(|p| p.max_delta_per_frame)
+            .unwrap_or(200);
+        println!(
+            "Applying target_nits Gaussian smoother (sigma={:.3})...",
+            sigma
+        );
+        crate::optimizer::apply_gaussian_target_smoother(&scenes, &mut frames, sigma, max_delta);
+        println!("Target_nits smoothing complete.");
+    }
+
+    let zones_with_target_override: Vec<&Zone> = zones
+        .iter()
+        .filter(|zone| zone.target_peak_nits.is_some())
+        .collect();
+    if !zones_with_target_override.is_empty() {
+        println!(
+            "Pinning target_nits to {} zone override(s)...",
+            zones_with_target_override.len()
+        );
+        for zone in zones_with_target_override {
+            let start = zone.start as usize;
+            let end = ((zone.end + 1) as usize).min(frames.len());
+            let target_nits = zone.target_peak_nits.unwrap();
+            for frame in frames.iter_mut().take(end).skip(start) {
+                frame.target_nits = Some(target_nits as u16);
+            }
+        }
     }
 
     let output_path = match &cli.output {
@@ -137,23 +241,411 @@ pub fn run(
         cli.header_peak_source.as_deref(),
     )?;
 
+    if let Some(hdr10plus_path) = &cli.hdr10plus_json {
+        println!("Writing HDR10+ dynamic metadata: {}", hdr10plus_path);
+        crate::hdr10plus::write_hdr10plus_json(hdr10plus_path, &scenes, &frames)?;
+    }
+
+    if let Some(bmff_path) = &cli.bmff_output {
+        println!("Writing ISO BMFF timed-metadata sidecar: {}", bmff_path);
+        crate::bmff::write_bmff_sidecar(bmff_path, &scenes, &frames, cli.bmff_frame_rate)?;
+    }
+
     Ok(())
 }
 
-fn compute_scene_diff(cli: &Cli, curr_hist: &[f64], prev_hist: &[f64]) -> f64 {
+/// Height (in rows) of the downscaled luma thumbnail compared between consecutive frames for
+/// `--scene-metric flow`/`hybrid`; width is derived to keep the active crop's aspect ratio.
+const SCENE_FLOW_THUMBNAIL_HEIGHT: u32 = 90;
+
+fn compute_scene_diff(
+    cli: &Cli,
+    curr_hist: &[f64],
+    prev_hist: &[f64],
+    curr_luma: Option<&[f64]>,
+    prev_luma: Option<&[f64]>,
+) -> f64 {
+    let flow_diff = || match (curr_luma, prev_luma) {
+        (Some(curr), Some(prev)) => luma_mad(curr, prev),
+        _ => calculate_histogram_difference(curr_hist, prev_hist),
+    };
+
     match cli.scene_metric.to_lowercase().as_str() {
-        // Placeholder for future hybrid (histogram + flow). For now, use histogram difference.
-        "hybrid" => calculate_histogram_difference(curr_hist, prev_hist),
+        "flow" => flow_diff(),
+        "hybrid" => {
+            let hist_diff = calculate_histogram_difference(curr_hist, prev_hist).min(1.0);
+            let weight = cli.scene_metric_weight.clamp(0.0, 1.0);
+            weight * hist_diff + (1.0 - weight) * flow_diff()
+        }
         _ => calculate_histogram_difference(curr_hist, prev_hist),
     }
 }
 
+const HIST_SCENECUT_EDGE_BINS: usize = 32;
+const HIST_SCENECUT_CHROMA_BINS_PER_AXIS: usize = 16;
+
+/// Sample `sample_count` frames spread evenly across the file (skipping the first/last 5% of
+/// the runtime) and aggregate their detected active-area edges into a single consensus crop
+/// rectangle. Opens its own independent decode context so it doesn't disturb the main
+/// pipeline's packet iterator, and mirrors the main pipeline's downscale/pixel-format handling
+/// so the returned rectangle's coordinates line up with the frames actually analyzed.
+fn sample_crop_temporal(input_path: &str, sample_count: usize, downscale: u32) -> Result<CropRect> {
+    let mut input_context =
+        format::input(input_path).context("Failed to reopen input for crop sampling")?;
+    let video_stream = input_context
+        .streams()
+        .best(ffmpeg_next::media::Type::Video)
+        .context("No video stream found while sampling crop")?;
+    let video_stream_index = video_stream.index();
+    let time_base = video_stream.time_base();
+
+    let stream_duration = video_stream.duration();
+    let duration_ts = if stream_duration > 0 {
+        stream_duration
+    } else {
+        let container_duration = input_context.duration();
+        if container_duration > 0 && f64::from(time_base) > 0.0 {
+            ((container_duration as f64 / 1_000_000.0) / f64::from(time_base)) as i64
+        } else {
+            0
+        }
+    };
+
+    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
+        .context("Failed to create decoder context for crop sampling")?;
+    let mut decoder = decoder_context
+        .decoder()
+        .video()
+        .context("Failed to create video decoder for crop sampling")?;
+
+    let mut target_w = decoder.width();
+    let mut target_h = decoder.height();
+    if downscale > 1 {
+        target_w = (target_w / downscale).max(2) & !1;
+        target_h = (target_h / downscale).max(2) & !1;
+    }
+    let need_scaler = decoder.format() != format::Pixel::YUV420P10LE || downscale > 1;
+    let mut scaler: Option<software::scaling::Context> = if need_scaler {
+        Some(
+            software::scaling::Context::get(
+                decoder.format(),
+                decoder.width(),
+                decoder.height(),
+                format::Pixel::YUV420P10LE,
+                target_w,
+                target_h,
+                software::scaling::Flags::FAST_BILINEAR,
+            )
+            .context("Failed to create scaling context for crop sampling")?,
+        )
+    } else {
+        None
+    };
+
+    if sample_count == 0 || duration_ts <= 0 {
+        return Ok(CropRect::full(target_w, target_h));
+    }
+
+    let margin_ts = (duration_ts as f64 * 0.05) as i64;
+    let usable_start = margin_ts;
+    let usable_end = (duration_ts - margin_ts).max(usable_start + 1);
+
+    let mut decoded_frame = frame::Video::empty();
+    let mut scaled_frame = frame::Video::empty();
+    let mut samples: Vec<(u32, u32, u32, u32)> = Vec::with_capacity(sample_count);
+
+    for i in 0..sample_count {
+        let frac = if sample_count == 1 {
+            0.5
+        } else {
+            i as f64 / (sample_count - 1) as f64
+        };
+        let target_ts = usable_start + ((usable_end - usable_start) as f64 * frac) as i64;
+
+        if input_context.seek(target_ts, ..target_ts).is_err() {
+            continue;
+        }
+        decoder.flush();
+
+        'find_frame: for (stream, packet) in input_context.packets() {
+            if stream.index() != video_stream_index {
+                continue;
+            }
+            if decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                if decoded_frame.pts().unwrap_or(i64::MIN) >= target_ts {
+                    let analysis_frame = if let Some(ref mut sc) = scaler {
+                        if sc.run(&decoded_frame, &mut scaled_frame).is_err() {
+                            continue;
+                        }
+                        &scaled_frame
+                    } else {
+                        &decoded_frame
+                    };
+                    if let Some(edges) = crate::crop::detect_crop_edges(analysis_frame) {
+                        samples.push(edges);
+                    }
+                    break 'find_frame;
+                }
+            }
+        }
+    }
+
+    Ok(crate::crop::detect_crop_temporal(
+        &samples, target_w, target_h,
+    ))
+}
+
+/// Resolve the crop rectangle for a single already-decoded frame, honoring `--no-crop` and
+/// `--crop-detect off`. Used by the lazy single-frame/off paths; temporal mode is resolved
+/// eagerly before the main decode loop via `sample_crop_temporal`.
+fn lazy_single_frame_crop(analysis_frame: &frame::Video, cli: &Cli) -> CropRect {
+    if cli.no_crop || cli.crop_detect.eq_ignore_ascii_case("off") {
+        let rect = CropRect::full(analysis_frame.width(), analysis_frame.height());
+        println!(
+            "\nCrop disabled: using full frame {}x{}",
+            rect.width, rect.height
+        );
+        rect
+    } else {
+        let rect = crate::crop::detect_crop(analysis_frame);
+        println!(
+            "\nDetected active video area: {}x{} at offset ({}, {})",
+            rect.width, rect.height, rect.x, rect.y
+        );
+        rect
+    }
+}
+
+/// Capacity (in frames) of the bounded queue between the decode producer and the analysis
+/// consumer in [`run_native_analysis_pipeline`]. Small on purpose: it only needs to absorb
+/// short bursts where one side briefly outpaces the other, not build up an unbounded backlog
+/// of decoded frames in memory.
+const FRAME_QUEUE_CAPACITY: usize = 4;
+
+/// A small bounded single-producer/single-consumer queue used to overlap decode and analysis.
+/// `push` blocks while full and `pop` blocks while empty, so the decode thread and the
+/// analysis consumer naturally throttle each other instead of either racing ahead and piling
+/// up memory. `close` lets the producer signal EOF; once closed and drained, `pop` returns
+/// `None` instead of blocking forever.
+struct BoundedFrameQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    closed: AtomicBool,
+}
+
+impl<T> BoundedFrameQueue<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: capacity.max(1),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn push(&self, item: T) {
+        let mut items = self.items.lock().unwrap();
+        while items.len() >= self.capacity {
+            items = self.not_full.wait(items).unwrap();
+        }
+        items.push_back(item);
+        drop(items);
+        self.not_empty.notify_one();
+    }
+
+    fn close(&self) {
+        // Set the flag under the queue lock so a consumer between its `closed` check and its
+        // wait can't miss this wakeup.
+        let items = self.items.lock().unwrap();
+        self.closed.store(true, Ordering::SeqCst);
+        drop(items);
+        self.not_empty.notify_one();
+    }
+
+    fn pop(&self) -> Option<T> {
+        let mut items = self.items.lock().unwrap();
+        loop {
+            if let Some(item) = items.pop_front() {
+                drop(items);
+                self.not_full.notify_one();
+                return Some(item);
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            items = self.not_empty.wait(items).unwrap();
+        }
+    }
+}
+
+/// One decoded frame handed from the producer to the consumer: `analysis_frame` is `Some` when
+/// the sample-rate policy selected this frame for full analysis (already scaled/converted to
+/// the pipeline's working format), or `None` for a sampled-over frame the consumer should
+/// satisfy from its cached last-analyzed result, exactly as the single-threaded loop used to.
+struct QueuedFrame {
+    index: u32,
+    analysis_frame: Option<frame::Video>,
+}
+
+// SAFETY: `frame::Video` wraps a refcounted `AVFrame*` that isn't shared with any other
+// thread-affine state once decoded -- the decode thread that produces it never touches it
+// again after pushing it onto the queue -- so handing ownership to the consumer thread is
+// sound even though the wrapper type doesn't derive `Send`.
+unsafe impl Send for QueuedFrame {}
+
+/// Bundles the decoder/scaler/input context moved into the producer thread in
+/// [`run_native_analysis_pipeline`]. Grouped into one struct purely so a single `unsafe impl
+/// Send` covers all three non-`Send` FFmpeg wrapper types at once. The trailing `bool` is
+/// `is_p01x_fast_path`, carried alongside the scaler because a hardware decode resolves both
+/// together, lazily, from the first downloaded frame (see `hw_pixel_format` in
+/// [`run_native_analysis_pipeline`]).
+struct DecodeState<'a>(
+    codec::decoder::Video,
+    Option<software::scaling::Context>,
+    &'a mut format::context::Input,
+    bool,
+);
+
+// SAFETY: the producer thread takes exclusive, entire-lifetime ownership of these FFmpeg
+// wrapper types; the consumer (this function's calling thread) never accesses them again once
+// they're moved into `DecodeState`, so crossing the spawn boundary is sound.
+unsafe impl Send for DecodeState<'_> {}
+
+/// Decides whether the P01x bit-shift fast path applies to `source_format` and, if not, builds
+/// the `swscale` context needed to reach the pipeline's YUV420P10LE working format. Called
+/// eagerly in [`run_native_analysis_pipeline`]'s setup for software decodes (the format is known
+/// from `decoder.format()` before any frame arrives) and lazily by its decode-thread producer for
+/// hardware decodes, once the first downloaded CPU frame reveals its real format.
+fn configure_frame_conversion(
+    source_format: format::Pixel,
+    source_width: u32,
+    source_height: u32,
+    target_width: u32,
+    target_height: u32,
+    downscale: u32,
+) -> Result<(bool, Option<software::scaling::Context>)> {
+    let is_p01x_fast_path =
+        matches!(source_format, format::Pixel::P010LE | format::Pixel::P016LE) && downscale == 1;
+    let need_scaler =
+        !is_p01x_fast_path && (source_format != format::Pixel::YUV420P10LE || downscale > 1);
+    let scaler = if need_scaler {
+        Some(
+            software::scaling::Context::get(
+                source_format,
+                source_width,
+                source_height,
+                format::Pixel::YUV420P10LE,
+                target_width,
+                target_height,
+                software::scaling::Flags::FAST_BILINEAR,
+            )
+            .context("Failed to create scaling context")?,
+        )
+    } else {
+        None
+    };
+    Ok((is_p01x_fast_path, scaler))
+}
+
+/// Scales/converts a just-decoded frame into the pipeline's working format (P01x fast path,
+/// `swscale`, or a plain clone when the decoder's native format already matches), exactly as
+/// the analysis loop below used to do inline. Pulled out so the decode-thread producer in
+/// [`run_native_analysis_pipeline`] and its EOF-drain tail share one implementation.
+fn build_analysis_frame(
+    decoded_frame: &frame::Video,
+    is_p01x_fast_path: bool,
+    luma_only: bool,
+    scaler: &mut Option<software::scaling::Context>,
+) -> Result<frame::Video> {
+    if is_p01x_fast_path {
+        Ok(crate::ffmpeg_io::convert_p01x_to_yuv420p10le(
+            decoded_frame,
+            !luma_only,
+        ))
+    } else if let Some(sc) = scaler {
+        let mut scaled = frame::Video::empty();
+        sc.run(decoded_frame, &mut scaled)
+            .context("Failed to scale frame")?;
+        Ok(scaled)
+    } else {
+        Ok(decoded_frame.clone())
+    }
+}
+
+/// Resolves a just-decoded frame into the pipeline's analysis format, downloading it off the
+/// hardware surface first via [`crate::ffmpeg_io::download_hw_frame`] when `hw_pixel_format`
+/// indicates this decoder hands back hardware frames. On a hardware decode, the first call also
+/// resolves `is_p01x_fast_path`/`scaler` from the downloaded frame's real format (the decoder
+/// itself only reports the opaque surface format) and marks `conversion_configured` so later
+/// frames reuse that setup instead of re-probing it every frame.
+#[allow(clippy::too_many_arguments)]
+fn resolve_analysis_frame(
+    decoded_frame: &frame::Video,
+    hw_pixel_format: Option<format::Pixel>,
+    target_width: u32,
+    target_height: u32,
+    downscale: u32,
+    is_p01x_fast_path: &mut bool,
+    scaler: &mut Option<software::scaling::Context>,
+    conversion_configured: &mut bool,
+    luma_only: bool,
+) -> Result<frame::Video> {
+    let source_frame = match hw_pixel_format {
+        Some(hw_fmt) if decoded_frame.format() == hw_fmt => {
+            crate::ffmpeg_io::download_hw_frame(decoded_frame)?
+        }
+        _ => decoded_frame.clone(),
+    };
+
+    if !*conversion_configured {
+        let (fast_path, built_scaler) = configure_frame_conversion(
+            source_frame.format(),
+            source_frame.width(),
+            source_frame.height(),
+            target_width,
+            target_height,
+            downscale,
+        )?;
+        *is_p01x_fast_path = fast_path;
+        *scaler = built_scaler;
+        *conversion_configured = true;
+        if fast_path {
+            println!(
+                "P010/P016 fast path active (post hw-download): converting luma directly (bypassing swscale){}",
+                if luma_only {
+                    ", chroma and hue histogram skipped (--luma-only)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    build_analysis_frame(&source_frame, *is_p01x_fast_path, luma_only, scaler)
+}
+
 fn run_native_analysis_pipeline(
     cli: &Cli,
     video_info: &VideoInfo,
     input_context: &mut format::context::Input,
-) -> Result<(Vec<MadVRScene>, Vec<MadVRFrame>)> {
+    zones: &[Zone],
+) -> Result<(
+    Vec<MadVRScene>,
+    Vec<MadVRFrame>,
+    Vec<Option<LogHistogram>>,
+    Vec<Option<Vec<(f64, f64, f64)>>>,
+)> {
     println!("Starting native analysis pipeline...");
+    println!(
+        "Scene detect mode: {}",
+        cli.scene_detect_mode.to_lowercase()
+    );
     let width = video_info.width;
     let height = video_info.height;
     let total_frames = video_info.total_frames;
@@ -165,28 +657,35 @@ fn run_native_analysis_pipeline(
         .context("No video stream found")?;
     let video_stream_index = video_stream.index();
 
-    let mut decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
+    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
         .context("Failed to create decoder context from stream parameters")?;
 
-    // SAFETY: decoder_context is valid and as_mut_ptr() returns a valid mutable pointer.
-    // Setting thread_count to 0 enables FFmpeg's automatic thread count selection,
-    // which is a safe operation that only affects the decoder's threading behavior.
-    unsafe {
-        let ctx = decoder_context.as_mut_ptr();
-        (*ctx).thread_count = 0;
-    }
+    let decoder_config = DecoderConfig {
+        thread_count: cli.decode_threads,
+        max_frame_delay: cli.max_frame_delay,
+        ..Default::default()
+    };
+    println!(
+        "Decode threads: {} (estimated decode latency: {} frame(s))",
+        decoder_config.resolved_thread_count(),
+        decoder_config.decode_latency_frames()
+    );
 
-    let mut decoder = if let Some(hwaccel) = &cli.hwaccel {
+    let (mut decoder, hw_pixel_format) = if let Some(hwaccel) = &cli.hwaccel {
         println!("Attempting to use hardware acceleration: {}", hwaccel);
-        setup_hardware_decoder(decoder_context, hwaccel)?
+        setup_hardware_decoder(decoder_context, hwaccel, decoder_config)?
     } else {
-        decoder_context
-            .decoder()
-            .video()
-            .context("Failed to create video decoder")?
+        let mut decoder_context = decoder_context;
+        decoder_config.apply(&mut decoder_context);
+        (
+            decoder_context
+                .decoder()
+                .video()
+                .context("Failed to create video decoder")?,
+            None,
+        )
     };
 
-    let mut scaler: Option<software::scaling::Context> = None;
     let downscale = match cli.downscale {
         1 | 2 | 4 => cli.downscale,
         other => {
@@ -203,30 +702,72 @@ fn run_native_analysis_pipeline(
         target_w = (target_w / downscale).max(2) & !1;
         target_h = (target_h / downscale).max(2) & !1;
     }
-    let need_scaler = decoder.format() != format::Pixel::YUV420P10LE || downscale > 1;
-    if need_scaler {
-        scaler = Some(
-            software::scaling::Context::get(
-                decoder.format(),
-                decoder.width(),
-                decoder.height(),
-                format::Pixel::YUV420P10LE,
-                target_w,
-                target_h,
-                software::scaling::Flags::FAST_BILINEAR,
-            )
-            .context("Failed to create scaling context")?,
+
+    // A hw-accelerated decoder (see `hw_pixel_format`) reports the opaque hardware surface pixel
+    // format here, not the pixel format frames will actually have once the decode-thread
+    // producer below downloads them with `download_hw_frame` -- so the P01x fast-path check and
+    // scaler source format can't be resolved until the first downloaded frame reveals its real
+    // format. For a software decode the format is already known, so configure it eagerly as
+    // before.
+    let (mut is_p01x_fast_path, mut scaler) = if hw_pixel_format.is_some() {
+        (false, None)
+    } else {
+        configure_frame_conversion(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            target_w,
+            target_h,
+            downscale,
+        )?
+    };
+    if is_p01x_fast_path {
+        println!(
+            "P010/P016 fast path active: converting luma directly (bypassing swscale){}",
+            if cli.luma_only {
+                ", chroma and hue histogram skipped (--luma-only)"
+            } else {
+                ""
+            }
         );
     }
 
     let mut frames = Vec::new();
+    let mut log_histograms: Vec<Option<LogHistogram>> = Vec::new();
+    let mut ycbcr_samples: Vec<Option<Vec<(f64, f64, f64)>>> = Vec::new();
     let mut scene_cuts = Vec::new();
     let mut previous_histogram: Option<Vec<f64>> = None;
+    let mut previous_edge_histogram: Option<Vec<f64>> = None;
+    let mut previous_chroma_histogram: Option<Vec<f64>> = None;
+    let mut previous_luma_thumbnail: Option<Vec<f64>> = None;
+    let mut previous_intensity_stats: Option<(f64, f64)> = None;
+    let scene_metric_needs_flow =
+        matches!(cli.scene_metric.to_lowercase().as_str(), "flow" | "hybrid");
+    let mut variance_gate_rejections: u32 = 0;
     let smoothing_window = cli.scene_smoothing as usize;
     let mut diff_window: VecDeque<f64> = VecDeque::with_capacity(smoothing_window.max(1));
+    let scene_detect_mode = SceneDetectMode::from_name(&cli.scene_detect_mode)?;
+    let mut adaptive_detector = AdaptiveSceneDetector::new(cli.adaptive_scene_window);
+    let mut flash_filter =
+        FlashFadeFilter::new(cli.flash_confirm_frames, cli.flash_revert_tolerance);
+    let mut fade_detector = FadeDetector::new(cli.fade_detect_window, cli.scene_threshold);
+    let mut temporal_ring = TemporalDenoiseRing::new(4);
     let mut last_cut_frame: u32 = 0;
     let mut frame_count = 0u32;
     let mut crop_rect_opt: Option<CropRect> = None;
+    if !cli.no_crop && cli.crop_detect.eq_ignore_ascii_case("temporal") {
+        let input_path = cli
+            .input_positional
+            .as_ref()
+            .unwrap_or(cli.input_flag.as_ref().unwrap());
+        let rect = sample_crop_temporal(input_path, cli.crop_sample_count, downscale)
+            .context("Temporal crop detection failed")?;
+        println!(
+            "Temporal crop consensus ({} samples): {}x{} at offset ({}, {})",
+            cli.crop_sample_count, rect.width, rect.height, rect.x, rect.y
+        );
+        crop_rect_opt = Some(rect);
+    }
     let mut analysis_duration = Duration::ZERO;
 
     // Frame sampling configuration
@@ -270,45 +811,104 @@ fn run_native_analysis_pipeline(
     }
     pb.set_position(0); // Show initial progress immediately
 
-    for (stream, packet) in input_context.packets() {
-        if stream.index() == video_stream_index {
-            decoder
-                .send_packet(&packet)
-                .context("Failed to send packet to decoder")?;
+    // Decode and analysis now overlap: a producer thread owns the decoder/scaler and pushes
+    // each frame the sample-rate policy selects for analysis into a small bounded queue, while
+    // this thread (the consumer) pulls frames off it and runs the scene-detection/analysis
+    // pipeline below. This keeps a slow decoder (common on hardware-decode paths) from
+    // stalling the rayon-backed analysis work and vice versa. The queue is single-producer/
+    // single-consumer and strictly FIFO, so frames always drain in presentation order --
+    // `QueuedFrame::index` just lets the consumer assert that invariant rather than quietly
+    // trusting it, keeping scene-cut detection (which depends on frame order) deterministic.
+    let queue: Arc<BoundedFrameQueue<QueuedFrame>> = Arc::new(BoundedFrameQueue::new(FRAME_QUEUE_CAPACITY));
+    let producer_queue = Arc::clone(&queue);
+    let luma_only = cli.luma_only;
+    let decode_state = DecodeState(decoder, scaler, input_context, is_p01x_fast_path);
+
+    std::thread::scope(|scope| -> Result<()> {
+        let handle = scope.spawn(move || -> Result<()> {
+            let DecodeState(mut decoder, mut scaler, input_context, mut is_p01x_fast_path) =
+                decode_state;
+            // For a hardware decode, `is_p01x_fast_path`/`scaler` above are just placeholders --
+            // `resolve_analysis_frame` fills them in from the first downloaded frame's real
+            // format and flips this to `true` so later frames reuse that setup.
+            let mut conversion_configured = hw_pixel_format.is_none();
+            let mut frame_idx = 0u32;
+
+            for (stream, packet) in input_context.packets() {
+                if stream.index() != video_stream_index {
+                    continue;
+                }
+                decoder
+                    .send_packet(&packet)
+                    .context("Failed to send packet to decoder")?;
+                let mut decoded_frame = frame::Video::empty();
+                while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                    let should_analyze = frame_idx % sample_rate == 0 || frame_idx == 0;
+                    let analysis_frame = if should_analyze {
+                        Some(resolve_analysis_frame(
+                            &decoded_frame,
+                            hw_pixel_format,
+                            target_w,
+                            target_h,
+                            downscale,
+                            &mut is_p01x_fast_path,
+                            &mut scaler,
+                            &mut conversion_configured,
+                            luma_only,
+                        )?)
+                    } else {
+                        None
+                    };
+                    producer_queue.push(QueuedFrame {
+                        index: frame_idx,
+                        analysis_frame,
+                    });
+                    frame_idx += 1;
+                }
+            }
 
+            decoder
+                .send_eof()
+                .context("Failed to send EOF to decoder")?;
             let mut decoded_frame = frame::Video::empty();
-            let mut scaled_frame = frame::Video::empty();
             while decoder.receive_frame(&mut decoded_frame).is_ok() {
-                // Determine if we should analyze this frame or use cached data
-                let should_analyze =
-                    frame_count % sample_rate == 0 || last_analyzed_frame.is_none();
+                let should_analyze = frame_idx % sample_rate == 0 || frame_idx == 0;
+                let analysis_frame = if should_analyze {
+                    Some(resolve_analysis_frame(
+                        &decoded_frame,
+                        hw_pixel_format,
+                        target_w,
+                        target_h,
+                        downscale,
+                        &mut is_p01x_fast_path,
+                        &mut scaler,
+                        &mut conversion_configured,
+                        luma_only,
+                    )?)
+                } else {
+                    None
+                };
+                producer_queue.push(QueuedFrame {
+                    index: frame_idx,
+                    analysis_frame,
+                });
+                frame_idx += 1;
+            }
 
-                let analyzed_frame = if should_analyze {
-                    let analysis_frame = if let Some(ref mut sc) = scaler {
-                        sc.run(&decoded_frame, &mut scaled_frame)
-                            .context("Failed to scale frame")?;
-                        &scaled_frame
-                    } else {
-                        &decoded_frame
-                    };
+            producer_queue.close();
+            Ok(())
+        });
 
+        while let Some(queued) = queue.pop() {
+            debug_assert_eq!(
+                queued.index, frame_count,
+                "frame queue must drain in presentation order"
+            );
+
+            let (analyzed_frame, frame_log_hist, frame_ycbcr) =
+                if let Some(analysis_frame) = queued.analysis_frame {
                     if crop_rect_opt.is_none() {
-                        if cli.no_crop {
-                            let rect =
-                                CropRect::full(analysis_frame.width(), analysis_frame.height());
-                            println!(
-                                "\nCrop disabled: using full frame {}x{}",
-                                rect.width, rect.height
-                            );
-                            crop_rect_opt = Some(rect);
-                        } else {
-                            let rect = crate::crop::detect_crop(analysis_frame);
-                            println!(
-                                "\nDetected active video area: {}x{} at offset ({}, {})",
-                                rect.width, rect.height, rect.x, rect.y
-                            );
-                            crop_rect_opt = Some(rect);
-                        }
+                        crop_rect_opt = Some(lazy_single_frame_crop(&analysis_frame, cli));
                     }
                     let rect = crop_rect_opt.as_ref().unwrap();
 
@@ -317,23 +917,86 @@ fn run_native_analysis_pipeline(
                     } else {
                         None
                     };
-                    let frame_result = analyze_native_frame_cropped(
-                        analysis_frame,
+                    let (frame_result, frame_log_histogram) = analyze_native_frame_cropped(
+                        &analysis_frame,
                         width,
                         height,
                         rect,
                         &cli.pre_denoise,
                         transfer_function,
                         cli.hlg_peak_nits,
+                        Some(&mut temporal_ring),
+                        cli.median_variance_threshold,
+                        cli.activity_weighted_apl,
+                        cli.log_histogram_precision,
+                        cli.luma_only,
                     )?;
                     if let Some(start) = analysis_start {
                         analysis_duration += start.elapsed();
                     }
 
+                    let (edge_histogram, chroma_histogram) = if cli.hist_scenecut {
+                        (
+                            Some(compute_edge_histogram(
+                                analysis_frame.data(0),
+                                analysis_frame.stride(0),
+                                rect,
+                                HIST_SCENECUT_EDGE_BINS,
+                            )),
+                            Some(compute_chroma_histogram(
+                                &analysis_frame,
+                                rect,
+                                HIST_SCENECUT_CHROMA_BINS_PER_AXIS,
+                            )),
+                        )
+                    } else {
+                        (None, None)
+                    };
+
+                    let frame_ycbcr_samples = if cli.scene_palette {
+                        Some(sample_ycbcr(
+                            &analysis_frame,
+                            rect,
+                            cli.scene_palette_sample_stride,
+                        ))
+                    } else {
+                        None
+                    };
+
+                    let intensity_stats = compute_intensity_stats(
+                        analysis_frame.data(0),
+                        analysis_frame.stride(0),
+                        rect,
+                    );
+
+                    let luma_thumbnail = if scene_metric_needs_flow {
+                        Some(downsample_luma_thumbnail(
+                            analysis_frame.data(0),
+                            analysis_frame.stride(0),
+                            rect,
+                            SCENE_FLOW_THUMBNAIL_HEIGHT,
+                        ))
+                    } else {
+                        None
+                    };
+
                     // Scene detection on analyzed frames
                     if let Some(ref prev_hist) = previous_histogram {
-                        let raw_diff =
-                            compute_scene_diff(cli, &frame_result.lum_histogram, prev_hist);
+                        let raw_diff = match (&edge_histogram, &chroma_histogram) {
+                            (Some(edge), Some(chroma)) => hist_scenecut_diff(
+                                edge,
+                                previous_edge_histogram.as_deref().unwrap_or(edge),
+                                chroma,
+                                previous_chroma_histogram.as_deref().unwrap_or(chroma),
+                            ),
+                            _ => compute_scene_diff(
+                                cli,
+                                &frame_result.lum_histogram,
+                                prev_hist,
+                                luma_thumbnail.as_deref(),
+                                previous_luma_thumbnail.as_deref(),
+                            ),
+                        };
                         let diff_for_threshold = if smoothing_window > 0 {
                             diff_window.push_back(raw_diff);
                             if diff_window.len() > smoothing_window {
@@ -345,115 +1008,113 @@ fn run_native_analysis_pipeline(
                             raw_diff
                         };
 
-                        if diff_for_threshold > cli.scene_threshold
-                            && cut_allowed(Some(last_cut_frame), frame_count, cli.min_scene_length)
-                        {
+                        let zone = zones::zone_covering(zones, frame_count);
+                        let threshold =
+                            zone.and_then(|z| z.scene_threshold)
+                                .unwrap_or(if cli.hist_scenecut {
+                                    cli.hist_threshold
+                                } else {
+                                    cli.scene_threshold
+                                });
+                        let min_scene_length = zone
+                            .and_then(|z| z.min_scene_length)
+                            .unwrap_or(cli.min_scene_length);
+                        let mut is_cut_candidate = match scene_detect_mode {
+                            SceneDetectMode::Fixed => diff_for_threshold > threshold,
+                            SceneDetectMode::Adaptive => {
+                                adaptive_detector.evaluate(diff_for_threshold, cli.adaptive_scene_k)
+                            }
+                        };
+                        if is_cut_candidate {
+                            if let Some((prev_mean, prev_variance)) = previous_intensity_stats {
+                                let (mean_cur, variance_cur) = intensity_stats;
+                                if !variance_gate_confirms(
+                                    mean_cur,
+                                    variance_cur,
+                                    prev_mean,
+                                    prev_variance,
+                                    cli.variance_gate_sensitivity,
+                                ) {
+                                    is_cut_candidate = false;
+                                    variance_gate_rejections += 1;
+                                }
+                            }
+                        }
+
+                        // A zone's start frame forces a cut regardless of the histogram/flow
+                        // metric, bypassing the flash/fade confirmation lookahead entirely.
+                        let forced_zone_cut = zones
+                            .iter()
+                            .any(|z| z.start == frame_count && frame_count > 0);
+                        if forced_zone_cut && cut_allowed(Some(last_cut_frame), frame_count, 0) {
                             scene_cuts.push(frame_count);
                             last_cut_frame = frame_count;
+                            temporal_ring.reset();
+                            diff_window.clear();
+                            adaptive_detector.reset();
+                        } else if let Some(confirmed_frame) = flash_filter.observe(
+                            frame_count,
+                            is_cut_candidate,
+                            &frame_result.lum_histogram,
+                        ) {
+                            if cut_allowed(Some(last_cut_frame), confirmed_frame, min_scene_length)
+                            {
+                                scene_cuts.push(confirmed_frame);
+                                last_cut_frame = confirmed_frame;
+                                temporal_ring.reset();
+                                diff_window.clear();
+                                adaptive_detector.reset();
+                            }
+                        }
+                        if !forced_zone_cut {
+                            if let Some(fade_boundary) =
+                                fade_detector.observe(frame_count, diff_for_threshold)
+                            {
+                                if cut_allowed(
+                                    Some(last_cut_frame),
+                                    fade_boundary,
+                                    min_scene_length,
+                                ) {
+                                    scene_cuts.push(fade_boundary);
+                                    last_cut_frame = fade_boundary;
+                                    temporal_ring.reset();
+                                    diff_window.clear();
+                                    adaptive_detector.reset();
+                                }
+                            }
                         }
+                    } else {
+                        flash_filter.observe(frame_count, false, &frame_result.lum_histogram);
                     }
                     previous_histogram = Some(frame_result.lum_histogram.clone());
+                    previous_edge_histogram = edge_histogram;
+                    previous_chroma_histogram = chroma_histogram;
+                    previous_luma_thumbnail = luma_thumbnail;
+                    previous_intensity_stats = Some(intensity_stats);
                     last_analyzed_frame = Some(copy_frame(&frame_result));
-                    frame_result
+                    (frame_result, frame_log_histogram, frame_ycbcr_samples)
                 } else {
-                    // Use cached frame data for skipped frames
-                    copy_frame(last_analyzed_frame.as_ref().unwrap())
+                    // Use cached frame data for skipped frames; no fresh per-pixel samples to
+                    // feed a log histogram or palette for this frame.
+                    (
+                        copy_frame(last_analyzed_frame.as_ref().unwrap()),
+                        None,
+                        None,
+                    )
                 };
 
-                frames.push(analyzed_frame);
-                frame_count += 1;
+            frames.push(analyzed_frame);
+            log_histograms.push(frame_log_hist);
+            ycbcr_samples.push(frame_ycbcr);
+            frame_count += 1;
 
-                // Update progress display
-                pb.set_position(frame_count as u64);
-            }
+            // Update progress display
+            pb.set_position(frame_count as u64);
         }
-    }
 
-    decoder
-        .send_eof()
-        .context("Failed to send EOF to decoder")?;
-    let mut decoded_frame = frame::Video::empty();
-    let mut scaled_frame = frame::Video::empty();
-    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-        // Determine if we should analyze this frame or use cached data
-        let should_analyze = frame_count % sample_rate == 0 || last_analyzed_frame.is_none();
-
-        let analyzed_frame = if should_analyze {
-            let analysis_frame = if let Some(ref mut sc) = scaler {
-                sc.run(&decoded_frame, &mut scaled_frame)
-                    .context("Failed to scale final frame")?;
-                &scaled_frame
-            } else {
-                &decoded_frame
-            };
-
-            if crop_rect_opt.is_none() {
-                if cli.no_crop {
-                    let rect = CropRect::full(analysis_frame.width(), analysis_frame.height());
-                    println!(
-                        "\nCrop disabled: using full frame {}x{}",
-                        rect.width, rect.height
-                    );
-                    crop_rect_opt = Some(rect);
-                } else {
-                    let rect = crate::crop::detect_crop(analysis_frame);
-                    println!(
-                        "\nDetected active video area: {}x{} at offset ({}, {})",
-                        rect.width, rect.height, rect.x, rect.y
-                    );
-                    crop_rect_opt = Some(rect);
-                }
-            }
-            let rect = crop_rect_opt.as_ref().unwrap();
-
-            let analysis_start = if cli.profile_performance {
-                Some(Instant::now())
-            } else {
-                None
-            };
-            let frame_result = analyze_native_frame_cropped(
-                analysis_frame,
-                width,
-                height,
-                rect,
-                &cli.pre_denoise,
-                transfer_function,
-                cli.hlg_peak_nits,
-            )?;
-            if let Some(start) = analysis_start {
-                analysis_duration += start.elapsed();
-            }
-
-            if let Some(ref prev_hist) = previous_histogram {
-                let raw_diff = compute_scene_diff(cli, &frame_result.lum_histogram, prev_hist);
-                let diff_for_threshold = if smoothing_window > 0 {
-                    diff_window.push_back(raw_diff);
-                    if diff_window.len() > smoothing_window {
-                        diff_window.pop_front();
-                    }
-                    let sum: f64 = diff_window.iter().sum();
-                    sum / (diff_window.len() as f64)
-                } else {
-                    raw_diff
-                };
-                if diff_for_threshold > cli.scene_threshold
-                    && cut_allowed(Some(last_cut_frame), frame_count, cli.min_scene_length)
-                {
-                    scene_cuts.push(frame_count);
-                    last_cut_frame = frame_count;
-                }
-            }
-            previous_histogram = Some(frame_result.lum_histogram.clone());
-            last_analyzed_frame = Some(copy_frame(&frame_result));
-            frame_result
-        } else {
-            copy_frame(last_analyzed_frame.as_ref().unwrap())
-        };
-
-        frames.push(analyzed_frame);
-        frame_count += 1;
-        pb.set_position(frame_count as u64);
-    }
+        handle.join().expect("decode thread panicked")?;
+        Ok(())
+    })?;
 
     // Finalize progress display
     pb.finish_with_message("Complete");
@@ -491,9 +1152,13 @@ fn run_native_analysis_pipeline(
             format_duration(analysis_duration),
             analysis_fps
         );
+        println!(
+            "Variance gate: suppressed {} candidate cut(s) (flat/noisy content)",
+            variance_gate_rejections
+        );
     }
 
-    Ok((scenes, frames))
+    Ok((scenes, frames, log_histograms, ycbcr_samples))
 }
 
 fn fix_scene_end_frames(scenes: &mut [MadVRScene], total_frames: usize) {
@@ -526,7 +1191,9 @@ fn fix_scene_end_frames(scenes: &mut [MadVRScene], total_frames: usize) {
 fn apply_histogram_smoothing_pass(
     scenes: &[MadVRScene],
     frames: &mut [MadVRFrame],
+    log_histograms: &[Option<LogHistogram>],
     cli: &Cli,
+    zones: &[Zone],
 ) -> Result<()> {
     println!(
         "Applying histogram smoothing (EMA beta={}, temporal median window={})...",
@@ -536,8 +1203,10 @@ fn apply_histogram_smoothing_pass(
     let ema_beta = cli.hist_bin_ema_beta;
     let temporal_window = cli.hist_temporal_median;
 
-    // Determine peak source (default to histogram99 for balanced/aggressive, max for conservative)
-    let peak_source = cli.peak_source.as_deref().unwrap_or_else(|| {
+    // Default peak source (histogram99 for balanced/aggressive, max for conservative), used
+    // for the summary line below; the per-scene loop re-resolves this against whatever
+    // optimizer_profile a zones-file override pins to that scene.
+    let default_peak_source = cli.peak_source.as_deref().unwrap_or_else(|| {
         match cli.optimizer_profile.to_lowercase().as_str() {
             "conservative" => "max",
             _ => "histogram99", // balanced and aggressive default to histogram99
@@ -553,10 +1222,41 @@ fn apply_histogram_smoothing_pass(
             continue;
         }
 
+        // Determine peak source for this scene: an explicit --peak-source always wins;
+        // otherwise fall back to the zone covering this scene's optimizer_profile (if a
+        // zones file pins one), else the global --optimizer-profile.
+        let profile_name = zones::zone_covering(zones, scene.start)
+            .and_then(|zone| zone.optimizer_profile.as_deref())
+            .unwrap_or(&cli.optimizer_profile);
+        let peak_source = cli.peak_source.as_deref().unwrap_or_else(|| {
+            match profile_name.to_lowercase().as_str() {
+                "conservative" => "max",
+                _ => "histogram99",
+            }
+        });
+
         // Reset EMA state at scene boundary
         let mut ema_state = vec![0.0; 256];
         let mut temporal_history: VecDeque<Vec<f64>> = VecDeque::with_capacity(temporal_window);
 
+        // Aggregate this scene's per-frame log histograms (if enabled) into one scene-wide
+        // histogram, so the P99/P99.9 peak query below is far less sensitive to single-frame
+        // noise than either the coarse 256-bin histogram or a per-frame log histogram alone.
+        let scene_log_histogram = if cli.log_histogram_precision > 0 {
+            let mut scene_hist = LogHistogram::new(cli.log_histogram_precision);
+            for entry in log_histograms
+                .iter()
+                .take(end_idx)
+                .skip(start_idx)
+                .flatten()
+            {
+                scene_hist.merge(entry);
+            }
+            Some(scene_hist)
+        } else {
+            None
+        };
+
         for frame in frames.iter_mut().take(end_idx).skip(start_idx) {
             // Store original peak for reference
             let direct_max_pq = frame.peak_pq_2020;
@@ -583,7 +1283,12 @@ fn apply_histogram_smoothing_pass(
             }
 
             // Recompute peak based on peak_source
-            frame.peak_pq_2020 = select_peak_pq(&frame.lum_histogram, direct_max_pq, peak_source);
+            frame.peak_pq_2020 = select_peak_pq(
+                &frame.lum_histogram,
+                direct_max_pq,
+                peak_source,
+                scene_log_histogram.as_ref(),
+            );
 
             // Recompute avg_pq from smoothed histogram using v5 semantics
             let sdr_peak_pq = crate::analysis::histogram::nits_to_pq(100.0);
@@ -615,8 +1320,8 @@ fn apply_histogram_smoothing_pass(
     }
 
     println!(
-        "Histogram smoothing completed. Peak source: {}",
-        peak_source
+        "Histogram smoothing completed. Peak source: {} (per-scene overrides may differ)",
+        default_peak_source
     );
     Ok(())
 }
@@ -642,3 +1347,318 @@ fn precompute_scene_stats(scenes: &mut [MadVRScene], frames: &[MadVRFrame]) {
         }
     }
 }
+
+/// Informational-only diagnostic pass: re-derives scene boundaries with
+/// [`detect_scene_boundaries_sad`] (true histogram SAD against an adaptive running-average
+/// threshold, corroborated by mean-luminance shift) and prints a per-scene MaxCLL/MaxFALL
+/// rollup via [`aggregate_scene_rollups`]. Does not alter `scenes`/`frames` or the measurement
+/// file -- the primary fixed/adaptive detector above remains authoritative.
+fn run_scene_cut_sad_diagnostic(cli: &Cli, frames: &[MadVRFrame]) {
+    println!("Running scene-cut SAD diagnostic pass...");
+
+    let boundaries = detect_scene_boundaries_sad(
+        frames,
+        cli.min_scene_length,
+        1.5,
+        cli.scene_threshold,
+        None,
+        f64::INFINITY,
+    );
+    let rollups = aggregate_scene_rollups(&boundaries, frames);
+
+    println!(
+        "  SAD diagnostic found {} scene(s) ({} boundaries):",
+        rollups.len(),
+        boundaries.len()
+    );
+    for (i, rollup) in rollups.iter().enumerate() {
+        println!(
+            "    scene {:>4}: frames {:>6}-{:<6} MaxCLL={:>5} nits MaxFALL={:>5} nits",
+            i, rollup.start, rollup.end, rollup.max_cll_nits, rollup.max_fall_nits
+        );
+    }
+}
+
+/// Minimum per-worker span, in estimated frames, for [`prescan_scenes_grid`]'s timeline split.
+/// Splitting into more, smaller workers than this just adds reopen/seek overhead without
+/// shortening the critical path.
+const GRID_PRESCAN_MIN_CHUNK_FRAMES: u32 = 48;
+
+/// Decodes `input_path` independently of the main analysis pipeline, downscales each frame's
+/// luma plane to a small fixed grid, and looks for a MAD spike against the previous frame's
+/// grid -- the same signal `--scene-metric flow` computes inline during the main pass, just run
+/// ahead of time over `std::thread::available_parallelism` concurrently-decoded chunks of the
+/// timeline instead of one serialized decode.
+///
+/// Frame indices are approximate: each worker seeks to its chunk's start timestamp (landing on
+/// the nearest preceding keyframe, per FFmpeg's usual seek semantics) and counts frames from
+/// there, so a handful of frames' slop at each chunk boundary is expected -- `min_scene_len`
+/// already absorbs slop of that size, but the returned ranges shouldn't be treated as
+/// frame-exact.
+pub fn prescan_scenes_grid(
+    input_path: &str,
+    config: GridPrescanConfig,
+) -> Result<Vec<MadVRScene>> {
+    let probe_context =
+        format::input(input_path).context("Failed to open input for scene prescan")?;
+    let video_stream = probe_context
+        .streams()
+        .best(ffmpeg_next::media::Type::Video)
+        .context("No video stream found while prescanning scenes")?;
+    let time_base = video_stream.time_base();
+    let stream_duration = video_stream.duration();
+    let duration_ts = if stream_duration > 0 {
+        stream_duration
+    } else {
+        let container_duration = probe_context.duration();
+        if container_duration > 0 && f64::from(time_base) > 0.0 {
+            ((container_duration as f64 / 1_000_000.0) / f64::from(time_base)) as i64
+        } else {
+            0
+        }
+    };
+    let nb_frames = video_stream.frames().max(0) as u32;
+    let avg_frame_rate = video_stream.avg_frame_rate();
+    let fps = if avg_frame_rate.numerator() > 0 && avg_frame_rate.denominator() > 0 {
+        avg_frame_rate.numerator() as f64 / avg_frame_rate.denominator() as f64
+    } else {
+        24.0
+    };
+    let total_frames = if nb_frames > 0 {
+        nb_frames
+    } else if duration_ts > 0 && f64::from(time_base) > 0.0 {
+        (duration_ts as f64 * f64::from(time_base) * fps) as u32
+    } else {
+        0
+    };
+    drop(probe_context);
+
+    if duration_ts <= 0 || total_frames == 0 {
+        // Nothing to split a timeline we can't measure into; report it as one whole-file scene
+        // rather than failing the prescan outright.
+        return Ok(convert_scene_cuts_to_scenes(Vec::new(), total_frames.max(1)));
+    }
+
+    let worker_count = thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1)
+        .min((total_frames / GRID_PRESCAN_MIN_CHUNK_FRAMES).max(1) as usize)
+        .max(1);
+
+    let chunk_ts = (duration_ts / worker_count as i64).max(1);
+    let ranges: Vec<(i64, i64)> = (0..worker_count)
+        .map(|i| {
+            let start = i as i64 * chunk_ts;
+            let end = if i + 1 == worker_count {
+                duration_ts
+            } else {
+                start + chunk_ts
+            };
+            (start, end)
+        })
+        .collect();
+
+    let chunk_results: Vec<Result<Vec<f64>>> = ranges
+        .par_iter()
+        .map(|&(start_ts, end_ts)| {
+            scan_grid_diffs_in_range(input_path, start_ts, end_ts, config.grid_size)
+        })
+        .collect();
+
+    let mut diffs = Vec::with_capacity(total_frames as usize);
+    for chunk in chunk_results {
+        diffs.extend(chunk?);
+    }
+
+    let cuts =
+        detect_scene_boundaries_from_diffs(&diffs, config.threshold, config.min_scene_len);
+    Ok(convert_scene_cuts_to_scenes(cuts, diffs.len().max(1) as u32))
+}
+
+/// Single [`prescan_scenes_grid`] worker: decodes `input_path` from `start_ts` up to (but not
+/// including) `end_ts`, in its own independent decode context, and returns the MAD between each
+/// decoded frame's downscaled luma grid and the previous one's (the chunk's first frame has no
+/// in-chunk predecessor, so its diff is reported as `0.0`).
+fn scan_grid_diffs_in_range(
+    input_path: &str,
+    start_ts: i64,
+    end_ts: i64,
+    grid_size: u32,
+) -> Result<Vec<f64>> {
+    let mut input_context = format::input(input_path)
+        .context("Failed to reopen input for scene prescan worker")?;
+    let video_stream = input_context
+        .streams()
+        .best(ffmpeg_next::media::Type::Video)
+        .context("No video stream found in scene prescan worker")?;
+    let video_stream_index = video_stream.index();
+    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
+        .context("Failed to create decoder context for scene prescan worker")?;
+    let mut decoder = decoder_context
+        .decoder()
+        .video()
+        .context("Failed to create video decoder for scene prescan worker")?;
+
+    // A failed seek just leaves the demuxer at its current position (the start of the file for
+    // the first worker); non-fatal, since the pts check below still bounds the decoded range.
+    let _ = input_context.seek(start_ts, ..start_ts);
+    decoder.flush();
+
+    // `downsample_luma_thumbnail` reads 2-byte-per-sample 10-bit little-endian luma, so a
+    // decoder whose native output isn't already YUV420P10LE needs converting first -- same
+    // requirement `sample_crop_temporal` has for `detect_crop_edges`.
+    let mut scaler: Option<software::scaling::Context> =
+        if decoder.format() != format::Pixel::YUV420P10LE {
+            Some(
+                software::scaling::Context::get(
+                    decoder.format(),
+                    decoder.width(),
+                    decoder.height(),
+                    format::Pixel::YUV420P10LE,
+                    decoder.width(),
+                    decoder.height(),
+                    software::scaling::Flags::FAST_BILINEAR,
+                )
+                .context("Failed to create scaling context for scene prescan worker")?,
+            )
+        } else {
+            None
+        };
+
+    let crop_rect = CropRect::full(decoder.width(), decoder.height());
+    let mut decoded_frame = frame::Video::empty();
+    let mut scaled_frame = frame::Video::empty();
+    let mut prev_thumbnail: Option<Vec<f64>> = None;
+    let mut diffs = Vec::new();
+
+    'packets: for (stream, packet) in input_context.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            let pts = decoded_frame.pts().unwrap_or(i64::MIN);
+            if pts < start_ts {
+                continue;
+            }
+            if pts >= end_ts {
+                break 'packets;
+            }
+            let analysis_frame = if let Some(ref mut sc) = scaler {
+                if sc.run(&decoded_frame, &mut scaled_frame).is_err() {
+                    continue;
+                }
+                &scaled_frame
+            } else {
+                &decoded_frame
+            };
+            let thumbnail = downsample_luma_thumbnail(
+                analysis_frame.data(0),
+                analysis_frame.stride(0),
+                &crop_rect,
+                grid_size,
+            );
+            diffs.push(
+                prev_thumbnail
+                    .as_ref()
+                    .map(|prev| luma_mad(&thumbnail, prev))
+                    .unwrap_or(0.0),
+            );
+            prev_thumbnail = Some(thumbnail);
+        }
+    }
+
+    Ok(diffs)
+}
+
+/// Informational-only diagnostic pass: runs [`prescan_scenes_grid`] and prints a per-scene
+/// MaxCLL/MaxFALL rollup (via [`aggregate_scene_rollups`]) from the scenes it finds. Like
+/// `--scene-cut-sad-diagnostic`, this does not alter `scenes`/`frames` or the measurement file.
+fn run_scene_prescan_diagnostic(cli: &Cli, input_path: &str, frames: &[MadVRFrame]) {
+    println!("Running grid-luma scene prescan...");
+
+    let config = GridPrescanConfig {
+        grid_size: cli.scene_prescan_grid_size,
+        threshold: cli.scene_prescan_threshold,
+        min_scene_len: cli.scene_prescan_min_len,
+    };
+    let scenes = match prescan_scenes_grid(input_path, config) {
+        Ok(scenes) => scenes,
+        Err(err) => {
+            eprintln!("  Scene prescan failed: {}", err);
+            return;
+        }
+    };
+
+    let boundaries: Vec<SceneBoundary> = scenes
+        .iter()
+        .skip(1)
+        .map(|scene| SceneBoundary { frame: scene.start })
+        .collect();
+    let rollups = aggregate_scene_rollups(&boundaries, frames);
+
+    println!(
+        "  Grid prescan found {} scene(s) ({} workers available):",
+        rollups.len(),
+        thread::available_parallelism()
+            .map(std::num::NonZeroUsize::get)
+            .unwrap_or(1)
+    );
+    for (i, rollup) in rollups.iter().enumerate() {
+        println!(
+            "    scene {:>4}: frames {:>6}-{:<6} MaxCLL={:>5} nits MaxFALL={:>5} nits",
+            i, rollup.start, rollup.end, rollup.max_cll_nits, rollup.max_fall_nits
+        );
+    }
+}
+
+/// Informational-only diagnostic pass: aggregates each scene's `--scene-palette`-gated
+/// per-frame Y/Cb/Cr samples and runs [`extract_palette`] (median-cut quantization) to print
+/// the scene's N dominant colors. Like `--scene-cut-sad-diagnostic`, this does not alter
+/// `scenes`/`frames` or the measurement file -- the madVR binary format has no field for
+/// arbitrary per-scene color data, so this is console output only.
+fn run_scene_palette_diagnostic(
+    cli: &Cli,
+    scenes: &[MadVRScene],
+    ycbcr_samples: &[Option<Vec<(f64, f64, f64)>>],
+) {
+    println!("Running scene-palette extraction pass...");
+
+    for (i, scene) in scenes.iter().enumerate() {
+        let start_idx = scene.start as usize;
+        let end_idx = ((scene.end + 1) as usize).min(ycbcr_samples.len());
+        if start_idx >= ycbcr_samples.len() || start_idx >= end_idx {
+            continue;
+        }
+
+        let scene_samples: Vec<(f64, f64, f64)> = ycbcr_samples[start_idx..end_idx]
+            .iter()
+            .flatten()
+            .flatten()
+            .copied()
+            .collect();
+
+        let palette = extract_palette(&scene_samples, cli.scene_palette_colors);
+        let peak_nits = scene.peak_nits as f64;
+
+        println!(
+            "    scene {:>4}: frames {:>6}-{:<6} {} color(s):",
+            i,
+            scene.start,
+            scene.end,
+            palette.len()
+        );
+        for color in &palette {
+            let (r, g, b) = color.to_rgb_nits(peak_nits);
+            println!(
+                "        weight={:>5.1}%  rgb=({:>6.1}, {:>6.1}, {:>6.1}) nits",
+                color.weight * 100.0,
+                r,
+                g,
+                b
+            );
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::{Context, Result};
+use ffmpeg_next::{codec, format, frame, media, software};
+use image::{Rgb, RgbImage};
+
+use crate::analysis::histogram::pq_to_nits;
+use crate::analysis::hlg::hlg_signal_to_nits;
+use crate::cli::Cli;
+use crate::ffmpeg_io::{TransferFunction, VideoInfo};
+
+/// Renders a single tone-mapped SDR still (`cli.sdr_preview`) from a representative frame
+/// near the middle of the file, so a conversion can be sanity-checked on any display.
+///
+/// Opens its own decode session (mirrors `sample_crop_temporal`'s independent-context
+/// pattern) rather than disturbing the main analysis pipeline's packet iterator.
+pub fn render_sdr_preview(cli: &Cli, video_info: &VideoInfo) -> Result<()> {
+    let out_path = cli
+        .sdr_preview
+        .as_ref()
+        .context("render_sdr_preview called without --sdr-preview")?;
+    let input_path = cli
+        .input_positional
+        .as_ref()
+        .or(cli.input_flag.as_ref())
+        .context("No input file specified")?;
+
+    let operator = ToneMapOperator::from_str(&cli.tone_map);
+    let saturation = cli.saturation;
+
+    let mut input_context =
+        format::input(input_path).context("Failed to reopen input for SDR preview")?;
+    let video_stream = input_context
+        .streams()
+        .best(media::Type::Video)
+        .context("No video stream found while rendering SDR preview")?;
+    let video_stream_index = video_stream.index();
+
+    let stream_duration = video_stream.duration();
+    let time_base = video_stream.time_base();
+    let duration_ts = if stream_duration > 0 {
+        stream_duration
+    } else {
+        let container_duration = input_context.duration();
+        if container_duration > 0 && f64::from(time_base) > 0.0 {
+            ((container_duration as f64 / 1_000_000.0) / f64::from(time_base)) as i64
+        } else {
+            0
+        }
+    };
+    let target_ts = duration_ts / 2;
+
+    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())
+        .context("Failed to create decoder context for SDR preview")?;
+    let mut decoder = decoder_context
+        .decoder()
+        .video()
+        .context("Failed to create video decoder for SDR preview")?;
+
+    let width = decoder.width();
+    let height = decoder.height();
+    let mut scaler = software::scaling::Context::get(
+        decoder.format(),
+        width,
+        height,
+        format::Pixel::RGB48LE,
+        width,
+        height,
+        software::scaling::Flags::BILINEAR,
+    )
+    .context("Failed to create RGB scaling context for SDR preview")?;
+
+    if target_ts > 0 {
+        let _ = input_context.seek(target_ts, ..target_ts);
+        decoder.flush();
+    }
+
+    let mut decoded_frame = frame::Video::empty();
+    let mut rgb_frame = frame::Video::empty();
+    let mut found = false;
+
+    'outer: for (stream, packet) in input_context.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            if decoded_frame.pts().unwrap_or(i64::MIN) >= target_ts {
+                scaler
+                    .run(&decoded_frame, &mut rgb_frame)
+                    .context("Failed to scale frame to RGB for SDR preview")?;
+                found = true;
+                break 'outer;
+            }
+        }
+    }
+
+    if !found {
+        anyhow::bail!("Could not decode a frame for SDR preview");
+    }
+
+    let hdr_max = resolve_hdr_max(&cli.hdr_max, &rgb_frame, width, height, video_info)?;
+    println!(
+        "Rendering SDR preview ({:?} operator, hdr_max={:.0} nits, saturation={:.2})...",
+        operator, hdr_max, saturation
+    );
+
+    let mut image = RgbImage::new(width, height);
+    let stride = rgb_frame.stride(0);
+    let data = rgb_frame.data(0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let offset = y as usize * stride + x as usize * 6;
+            let r16 = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let g16 = u16::from_le_bytes([data[offset + 2], data[offset + 3]]);
+            let b16 = u16::from_le_bytes([data[offset + 4], data[offset + 5]]);
+
+            let (r_lin, g_lin, b_lin) = (
+                signal_to_linear(
+                    r16,
+                    video_info.transfer_function,
+                    cli.hlg_peak_nits,
+                    hdr_max,
+                ),
+                signal_to_linear(
+                    g16,
+                    video_info.transfer_function,
+                    cli.hlg_peak_nits,
+                    hdr_max,
+                ),
+                signal_to_linear(
+                    b16,
+                    video_info.transfer_function,
+                    cli.hlg_peak_nits,
+                    hdr_max,
+                ),
+            );
+
+            let (l, a, b) = linear_srgb_to_oklab(r_lin, g_lin, b_lin);
+            let l_mapped = operator.apply(l);
+            let (a, b) = (a * saturation, b * saturation);
+            let (r_out, g_out, b_out) = oklab_to_linear_srgb(l_mapped, a, b);
+
+            let pixel = Rgb([
+                linear_to_srgb8(r_out),
+                linear_to_srgb8(g_out),
+                linear_to_srgb8(b_out),
+            ]);
+            image.put_pixel(x, y, pixel);
+        }
+    }
+
+    image
+        .save(out_path)
+        .with_context(|| format!("Failed to write SDR preview to {}", out_path))?;
+    println!("SDR preview written: {}", out_path);
+    Ok(())
+}
+
+/// Converts a 16-bit RGB code value carrying the source transfer function into normalized
+/// linear light, where 1.0 corresponds to `hdr_max` nits.
+fn signal_to_linear(
+    code: u16,
+    transfer_function: TransferFunction,
+    hlg_peak_nits: f64,
+    hdr_max: f64,
+) -> f64 {
+    let signal = code as f64 / 65535.0;
+    let nits = match transfer_function {
+        TransferFunction::Hlg => hlg_signal_to_nits(signal, hlg_peak_nits),
+        _ => pq_to_nits(signal),
+    };
+    (nits / hdr_max).max(0.0)
+}
+
+/// Resolves `--hdr-max` to an absolute nits value: either parsed directly as a number, or,
+/// if given as a percentile (trailing '%'), measured from the decoded preview frame itself.
+fn resolve_hdr_max(
+    spec: &str,
+    rgb_frame: &frame::Video,
+    width: u32,
+    height: u32,
+    video_info: &VideoInfo,
+) -> Result<f64> {
+    let trimmed = spec.trim();
+    if let Some(pct_str) = trimmed.strip_suffix('%') {
+        let pct: f64 = pct_str
+            .parse()
+            .with_context(|| format!("Invalid --hdr-max percentile: {}", spec))?;
+        let stride = rgb_frame.stride(0);
+        let data = rgb_frame.data(0);
+        let mut nits: Vec<f64> = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let offset = y as usize * stride + x as usize * 6;
+                let r16 = u16::from_le_bytes([data[offset], data[offset + 1]]);
+                let signal = r16 as f64 / 65535.0;
+                nits.push(match video_info.transfer_function {
+                    TransferFunction::Hlg => hlg_signal_to_nits(signal, 1000.0),
+                    _ => pq_to_nits(signal),
+                });
+            }
+        }
+        nits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let idx = (((nits.len() - 1) as f64) * (pct / 100.0)).round() as usize;
+        Ok(nits[idx.min(nits.len() - 1)].max(1.0))
+    } else {
+        trimmed
+            .parse()
+            .with_context(|| format!("Invalid --hdr-max nits value: {}", spec))
+    }
+}
+
+/// Selectable tone-mapping operators for `--sdr-preview`, applied to the Oklab L channel.
+#[derive(Debug, Clone, Copy)]
+pub enum ToneMapOperator {
+    Reinhard,
+    Hable,
+    Linear,
+}
+
+impl ToneMapOperator {
+    fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "reinhard" => ToneMapOperator::Reinhard,
+            "linear" => ToneMapOperator::Linear,
+            _ => ToneMapOperator::Hable,
+        }
+    }
+
+    fn apply(self, l: f64) -> f64 {
+        let l = l.max(0.0);
+        match self {
+            ToneMapOperator::Reinhard => l / (1.0 + l),
+            ToneMapOperator::Linear => l.min(1.0),
+            ToneMapOperator::Hable => hable(l) / hable(HABLE_WHITE),
+        }
+    }
+}
+
+// Uncharted2-style filmic curve constants (Hable 2010).
+const HABLE_A: f64 = 0.15;
+const HABLE_B: f64 = 0.50;
+const HABLE_C: f64 = 0.10;
+const HABLE_D: f64 = 0.20;
+const HABLE_E: f64 = 0.02;
+const HABLE_F: f64 = 0.30;
+/// Reference white point for the Hable curve, in the same hdr_max-normalized units as `l`.
+const HABLE_WHITE: f64 = 4.0;
+
+fn hable(x: f64) -> f64 {
+    ((x * (HABLE_A * x + HABLE_C * HABLE_B) + HABLE_D * HABLE_E)
+        / (x * (HABLE_A * x + HABLE_B) + HABLE_D * HABLE_F))
+        - HABLE_E / HABLE_F
+}
+
+/// Converts linear sRGB (0.0-1.0, but not clamped -- HDR highlights may exceed 1.0) to Oklab.
+fn linear_srgb_to_oklab(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let l = 0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b;
+    let m = 0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b;
+    let s = 0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b;
+
+    let l_ = l.cbrt();
+    let m_ = m.cbrt();
+    let s_ = s.cbrt();
+
+    (
+        0.210_454_255_3 * l_ + 0.793_617_785_0 * m_ - 0.004_072_046_8 * s_,
+        1.977_998_495_1 * l_ - 2.428_592_205_0 * m_ + 0.450_593_709_9 * s_,
+        0.025_904_037_1 * l_ + 0.782_771_766_2 * m_ - 0.808_675_766_0 * s_,
+    )
+}
+
+/// Inverse of `linear_srgb_to_oklab`.
+fn oklab_to_linear_srgb(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let l_ = l + 0.396_337_777_4 * a + 0.215_803_757_3 * b;
+    let m_ = l - 0.105_561_345_8 * a - 0.063_854_172_8 * b;
+    let s_ = l - 0.089_484_177_5 * a - 1.291_485_548_0 * b;
+
+    let l = l_ * l_ * l_;
+    let m = m_ * m_ * m_;
+    let s = s_ * s_ * s_;
+
+    (
+        4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+        -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+        -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701_0 * s,
+    )
+}
+
+/// Clamps a linear light value and applies the sRGB OETF, returning an 8-bit code value.
+fn linear_to_srgb8(linear: f64) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64, eps: f64) {
+        assert!((a - b).abs() <= eps, "expected {a} ≈ {b} within {eps}");
+    }
+
+    #[test]
+    fn test_oklab_round_trip() {
+        let (r, g, b) = (0.3, 0.6, 0.9);
+        let (l, a, bb) = linear_srgb_to_oklab(r, g, b);
+        let (r2, g2, b2) = oklab_to_linear_srgb(l, a, bb);
+        approx_eq(r, r2, 1e-6);
+        approx_eq(g, g2, 1e-6);
+        approx_eq(b, b2, 1e-6);
+    }
+
+    #[test]
+    fn test_reinhard_compresses_toward_one() {
+        let op = ToneMapOperator::Reinhard;
+        assert!(op.apply(100.0) < 1.0);
+        approx_eq(op.apply(0.0), 0.0, 1e-9);
+    }
+
+    #[test]
+    fn test_linear_clamps_at_one() {
+        let op = ToneMapOperator::Linear;
+        approx_eq(op.apply(2.0), 1.0, 1e-9);
+        approx_eq(op.apply(0.5), 0.5, 1e-9);
+    }
+
+    #[test]
+    fn test_hable_maps_white_to_near_one() {
+        let op = ToneMapOperator::Hable;
+        approx_eq(op.apply(HABLE_WHITE), 1.0, 1e-6);
+    }
+
+    #[test]
+    fn test_from_str_defaults_to_hable() {
+        assert!(matches!(
+            ToneMapOperator::from_str("nonsense"),
+            ToneMapOperator::Hable
+        ));
+        assert!(matches!(
+            ToneMapOperator::from_str("Reinhard"),
+            ToneMapOperator::Reinhard
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! User-supplied "zones" file for per-frame-range overrides of scene detection and optimizer
+//! parameters, mirroring how batch encoders let you pin behavior to a frame range instead of
+//! re-tuning global flags for one difficult section (credits, fades, a too-bright title card).
+//!
+//! Format is plain text, one zone per line: `start end key=value key=value ...`. Blank lines
+//! and lines starting with `#` are ignored. Recognized keys: `scene_threshold`,
+//! `min_scene_length`, `optimizer_profile`, `target_peak_nits`.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// A single frame-range override parsed from a zones file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Zone {
+    pub start: u32,
+    pub end: u32,
+    pub scene_threshold: Option<f64>,
+    pub min_scene_length: Option<u32>,
+    pub optimizer_profile: Option<String>,
+    pub target_peak_nits: Option<u32>,
+}
+
+/// Parse a zones file into a list of [`Zone`]s. Every zone's `start` also forces a scene cut
+/// there (see `run_native_analysis_pipeline`'s scene-detection loop), regardless of what the
+/// histogram/flow metric says.
+pub fn parse_zones_file(path: &Path) -> Result<Vec<Zone>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read zones file: {}", path.display()))?;
+
+    let mut zones = Vec::new();
+    for (line_no, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let start: u32 = fields
+            .next()
+            .with_context(|| format!("zones file line {}: missing start frame", line_no + 1))?
+            .parse()
+            .with_context(|| format!("zones file line {}: invalid start frame", line_no + 1))?;
+        let end: u32 = fields
+            .next()
+            .with_context(|| format!("zones file line {}: missing end frame", line_no + 1))?
+            .parse()
+            .with_context(|| format!("zones file line {}: invalid end frame", line_no + 1))?;
+        if start > end {
+            return Err(anyhow::anyhow!(
+                "zones file line {}: start ({}) > end ({})",
+                line_no + 1,
+                start,
+                end
+            ));
+        }
+
+        let mut zone = Zone {
+            start,
+            end,
+            ..Default::default()
+        };
+
+        for field in fields {
+            let (key, value) = field.split_once('=').with_context(|| {
+                format!(
+                    "zones file line {}: expected key=value, got '{}'",
+                    line_no + 1,
+                    field
+                )
+            })?;
+            match key {
+                "scene_threshold" => {
+                    zone.scene_threshold = Some(value.parse().with_context(|| {
+                        format!(
+                            "zones file line {}: invalid scene_threshold '{}'",
+                            line_no + 1,
+                            value
+                        )
+                    })?)
+                }
+                "min_scene_length" => {
+                    zone.min_scene_length = Some(value.parse().with_context(|| {
+                        format!(
+                            "zones file line {}: invalid min_scene_length '{}'",
+                            line_no + 1,
+                            value
+                        )
+                    })?)
+                }
+                "optimizer_profile" => zone.optimizer_profile = Some(value.to_string()),
+                "target_peak_nits" => {
+                    let nits: u32 = value.parse().with_context(|| {
+                        format!(
+                            "zones file line {}: invalid target_peak_nits '{}'",
+                            line_no + 1,
+                            value
+                        )
+                    })?;
+                    // `target_peak_nits` ends up truncated into `MadVRFrame::target_nits: u16`
+                    // (see `run_native_analysis_pipeline`'s zone-override pass), so anything
+                    // above that range would silently wrap instead of erroring; 10000 nits is
+                    // already far past any real mastering display, so it doubles as a sanity
+                    // ceiling.
+                    if nits > 10_000 {
+                        return Err(anyhow::anyhow!(
+                            "zones file line {}: target_peak_nits must be at most 10000, got {}",
+                            line_no + 1,
+                            nits
+                        ));
+                    }
+                    zone.target_peak_nits = Some(nits);
+                }
+                other => {
+                    return Err(anyhow::anyhow!(
+                        "zones file line {}: unknown key '{}'",
+                        line_no + 1,
+                        other
+                    ))
+                }
+            }
+        }
+
+        zones.push(zone);
+    }
+
+    Ok(zones)
+}
+
+/// Return the zone covering `frame`, if any. When zones overlap, the first one listed wins.
+pub fn zone_covering(zones: &[Zone], frame: u32) -> Option<&Zone> {
+    zones.iter().find(|z| frame >= z.start && frame <= z.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zones_file_rejects_out_of_range_target_peak_nits() {
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_zones_bad_nits.txt");
+        std::fs::write(&path, "0 100 target_peak_nits=50000\n").unwrap();
+
+        let err = parse_zones_file(&path).unwrap_err();
+        assert!(err.to_string().contains("target_peak_nits"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_zones_file_accepts_in_range_target_peak_nits() {
+        let path = std::env::temp_dir().join("hdr_analyzer_mvp_test_zones_good_nits.txt");
+        std::fs::write(&path, "0 100 target_peak_nits=4000\n").unwrap();
+
+        let zones = parse_zones_file(&path).unwrap();
+        assert_eq!(zones[0].target_peak_nits, Some(4000));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
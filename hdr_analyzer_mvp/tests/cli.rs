@@ -1,5 +1,6 @@
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
+use std::fs;
 use std::process::Command;
 
 #[allow(deprecated)]
@@ -56,3 +57,37 @@ fn test_invalid_downscale_value() {
         .assert()
         .failure();
 }
+
+#[test]
+fn test_config_file_with_unknown_key_fails() {
+    let toml_body = "not_a_real_flag = 42\n";
+    let path = std::env::temp_dir().join("hdr_analyzer_mvp_cli_test_unknown_key.toml");
+    fs::write(&path, toml_body).unwrap();
+
+    analyzer_cmd()
+        .arg("--config")
+        .arg(&path)
+        .arg("input.mkv")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("argument file"));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_config_file_with_out_of_range_value_fails() {
+    let toml_body = "madvr_version = 99\n";
+    let path = std::env::temp_dir().join("hdr_analyzer_mvp_cli_test_bad_version.toml");
+    fs::write(&path, toml_body).unwrap();
+
+    // Exercises the `@file` argument-file syntax, as opposed to `--config file` above.
+    analyzer_cmd()
+        .arg(format!("@{}", path.display()))
+        .arg("input.mkv")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("5 or 6"));
+
+    let _ = fs::remove_file(&path);
+}
@@ -7,8 +7,11 @@ pub struct Args {
     #[arg(required = false)]
     pub input: Vec<String>,
 
-    /// Controls the --hdr10plus-peak-source flag in dovi_tool generate.
-    #[arg(long, value_enum, default_value_t = PeakSource::Histogram99)]
+    /// Controls the --hdr10plus-peak-source flag in dovi_tool generate. Accepts the named
+    /// presets (max-scl-luminance, histogram, histogram99) or an arbitrary percentile spec
+    /// like p99.5 or 99.5% for content that needs finer control (e.g. 99.5 for
+    /// specular-highlight-heavy sources, 95 for noisy/grainy ones).
+    #[arg(long, default_value = "histogram99")]
     pub peak_source: PeakSource,
 
     /// Comma-separated list of nits values for the Dolby Vision trim pass (e.g., '100,600,1000').
@@ -44,6 +47,32 @@ pub struct Args {
     #[arg(long, default_value_t = 1000)]
     pub hlg_peak_nits: u32,
 
+    /// x265 adaptive quantization mode (0-4) for the HLG->PQ transcode. Higher modes bias bits
+    /// toward detail/texture, which helps grain-heavy sources. Omit to use the x265 default.
+    #[arg(long)]
+    pub hlg_aq_mode: Option<u8>,
+
+    /// x265 adaptive quantization strength for the HLG->PQ transcode. Omit to use the x265
+    /// default.
+    #[arg(long)]
+    pub hlg_aq_strength: Option<f64>,
+
+    /// x265 quantizer curve compression (0.0-1.0) for the HLG->PQ transcode. Lower values bias
+    /// bits toward low-complexity frames; higher values flatten the curve (good for animation).
+    /// Omit to use the x265 default.
+    #[arg(long)]
+    pub hlg_qcomp: Option<f64>,
+
+    /// x265 psycho-visual rate-distortion strength for the HLG->PQ transcode. Omit to use the
+    /// x265 default.
+    #[arg(long)]
+    pub hlg_psy_rd: Option<f64>,
+
+    /// Escape hatch: raw extra x265 params ("key=val:key2=val2") appended to the HLG->PQ
+    /// transcode's -x265-params, after the other --hlg-* knobs.
+    #[arg(long)]
+    pub hlg_x265_params: Option<String>,
+
     /// After muxing, run verification: our verifier on the measurements and DV checks.
     #[arg(long)]
     pub verify: bool,
@@ -61,6 +90,37 @@ pub struct Args {
     #[arg(long, value_enum, default_value_t = OptimizerProfile::Conservative)]
     pub optimizer_profile: OptimizerProfile,
 
+    /// Force the transfer characteristics instead of relying on detection, overriding whatever
+    /// HDR format check_hdr_format/get_static_metadata would otherwise land on. Useful for
+    /// raw/stripped elementary streams or files mediainfo/ffprobe mis-tag.
+    #[arg(long, value_enum)]
+    pub transfer: Option<TransferCharacteristics>,
+
+    /// Force the color primaries instead of relying on detection.
+    #[arg(long, value_enum)]
+    pub primaries: Option<ColorPrimaries>,
+
+    /// Force the matrix coefficients instead of relying on detection.
+    #[arg(long, value_enum)]
+    pub matrix: Option<MatrixCoefficients>,
+
+    /// Force the mastering display metadata instead of relying on detection, in the same
+    /// "G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)" layout x265/dovi_tool use for --master-display
+    /// (chroma coordinates in 0.00002 units, luminance in 0.0001 cd/m² units).
+    #[arg(long, value_name = "SPEC")]
+    pub mastering_display: Option<String>,
+
+    /// Force MaxCLL/MaxFALL instead of relying on detection, as "maxcll,maxfall" in cd/m².
+    #[arg(long, value_name = "MAXCLL,MAXFALL")]
+    pub content_light: Option<String>,
+
+    /// Select which video track to analyze, for containers with multiple video tracks (e.g. a
+    /// BT.2020 PQ main track alongside a Dolby Vision enhancement layer or a secondary angle).
+    /// 0-based index among detected video tracks, not the container's own track-ID numbering.
+    /// Omit to auto-select the first HDR-signaled track.
+    #[arg(long)]
+    pub video_track: Option<usize>,
+
     /// Do not delete the source file and intermediate files after successful conversion.
     #[arg(long)]
     pub keep_source: bool,
@@ -68,6 +128,47 @@ pub struct Args {
     /// Hardware acceleration hint for analysis and encoding.
     #[arg(long, value_enum, default_value_t = HwAccel::None)]
     pub hwaccel: HwAccel,
+
+    /// Watch a directory for new .mkv files and convert them automatically as they appear
+    /// (drop-box mode), instead of processing a fixed batch once. Runs until interrupted.
+    #[arg(long, value_name = "DIR")]
+    pub watch: Option<String>,
+
+    /// Number of files to convert concurrently, in both batch mode and --watch. Default: 1
+    /// (serial, matching prior behavior).
+    #[arg(long, default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Output container: mkv (remuxed file, default) or cmaf (fragmented-MP4 init segment +
+    /// media segments with the Dolby Vision configuration box, for HLS/DASH packaging).
+    #[arg(long, value_enum, default_value_t = OutputFormat::Mkv)]
+    pub output_format: OutputFormat,
+
+    /// CMAF segment duration in seconds. Only used with --output-format=cmaf. Default: 4.0
+    #[arg(long, default_value_t = 4.0)]
+    pub segment_duration: f64,
+
+    /// CMAF low-latency chunk (sub-fragment) duration in seconds, shorter than
+    /// --segment-duration. Each chunk's moof/mdat is written out as soon as it's ready
+    /// instead of buffering a whole segment. Only used with --output-format=cmaf. 0 disables
+    /// chunking (plain per-segment fragments). Default: 0
+    #[arg(long, default_value_t = 0.0)]
+    pub chunk_duration: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Mkv,
+    Cmaf,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Mkv => write!(f, "mkv"),
+            OutputFormat::Cmaf => write!(f, "cmaf"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -85,7 +186,7 @@ impl std::fmt::Display for HwAccel {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PeakSource {
     /// Use max-scl from metadata (most conservative; can look dim).
     MaxSclLuminance,
@@ -93,15 +194,114 @@ pub enum PeakSource {
     Histogram,
     /// (Default) Use the 99th percentile from histogram (good balance of detail vs brightness).
     Histogram99,
+    /// An arbitrary percentile (0.0-100.0), e.g. 99.5 for specular-highlight-heavy content or
+    /// 95 for noisy/grainy content. Parsed from a `pN.N` or `N.N%` spec.
+    Percentile(f64),
 }
 
 impl std::fmt::Display for PeakSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Map enum variants to string values expected by dovi_tool CLI
+        // Map enum variants to the string values expected by dovi_tool's
+        // --hdr10plus-peak-source flag, which accepts arbitrary "histogramN" percentiles.
         match self {
             PeakSource::MaxSclLuminance => write!(f, "max-scl-luminance"),
             PeakSource::Histogram => write!(f, "histogram"),
             PeakSource::Histogram99 => write!(f, "histogram99"),
+            PeakSource::Percentile(pct) => {
+                if pct.fract().abs() < f64::EPSILON {
+                    write!(f, "histogram{}", *pct as i64)
+                } else {
+                    write!(f, "histogram{pct}")
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for PeakSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+
+        // Percentile spec: trailing '%' (e.g. "99.5%") or leading 'p'/'P' (e.g. "p99.5"),
+        // the same way a percentage-or-scalar `Level` type would parse a trailing '%' suffix.
+        if let Some(pct) = trimmed.strip_suffix('%') {
+            return parse_percentile(pct);
+        }
+        if let Some(rest) = trimmed.strip_prefix(['p', 'P']) {
+            if rest.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                return parse_percentile(rest);
+            }
+        }
+
+        match trimmed.to_lowercase().as_str() {
+            "max-scl-luminance" | "max_scl_luminance" | "maxsclluminance" => {
+                Ok(PeakSource::MaxSclLuminance)
+            }
+            "histogram" => Ok(PeakSource::Histogram),
+            "histogram99" => Ok(PeakSource::Histogram99),
+            other => Err(format!(
+                "invalid --peak-source '{other}': expected max-scl-luminance, histogram, \
+                 histogram99, or a percentile like p99.5 / 99.5%"
+            )),
+        }
+    }
+}
+
+fn parse_percentile(raw: &str) -> Result<PeakSource, String> {
+    let value: f64 = raw
+        .parse()
+        .map_err(|_| format!("invalid percentile '{raw}' in --peak-source"))?;
+    if !(0.0..=100.0).contains(&value) {
+        return Err(format!(
+            "--peak-source percentile must be between 0 and 100, got {value}"
+        ));
+    }
+    Ok(PeakSource::Percentile(value))
+}
+
+/// Transfer characteristics a user can force with `--transfer`, using the same `pq`/`hlg` names
+/// the rav1e/x265 CLIs use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TransferCharacteristics {
+    Bt709,
+    #[value(alias = "smpte2084")]
+    Pq,
+    Hlg,
+}
+
+/// Color primaries a user can force with `--primaries`. Stored as the ISO/IEC 23001-8 `nclx`
+/// code the ISOBMFF `colr` box (and the HEVC VUI) use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorPrimaries {
+    Bt709,
+    Bt2020,
+}
+
+impl ColorPrimaries {
+    pub fn nclx_code(self) -> u16 {
+        match self {
+            ColorPrimaries::Bt709 => 1,
+            ColorPrimaries::Bt2020 => 9,
+        }
+    }
+}
+
+/// Matrix coefficients a user can force with `--matrix`. Stored as the ISO/IEC 23001-8 `nclx`
+/// code the ISOBMFF `colr` box (and the HEVC VUI) use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MatrixCoefficients {
+    Bt709,
+    #[value(name = "bt2020")]
+    Bt2020Nc,
+}
+
+impl MatrixCoefficients {
+    pub fn nclx_code(self) -> u16 {
+        match self {
+            MatrixCoefficients::Bt709 => 1,
+            MatrixCoefficients::Bt2020Nc => 9,
         }
     }
 }
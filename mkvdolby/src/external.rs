@@ -3,46 +3,136 @@ use colored::Colorize;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::sync::mpsc;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How a bounded [`run_command`]/[`run_command_live`]/[`run_command_inherit_stderr`] invocation
+/// ended. `Success`/`Failed` reflect the child's own exit status; `TimedOut`/`Cancelled` mean the
+/// child was killed before it could exit on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandOutcome {
+    Success,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+impl CommandOutcome {
+    /// Mirrors the old `Result<bool>` call sites: only `Success` counts as success.
+    pub fn success(self) -> bool {
+        matches!(self, CommandOutcome::Success)
+    }
+}
+
+/// Spawns a thread that reads `pipe` to EOF into a shared buffer, for commands run without
+/// incremental streaming (i.e. [`run_command`]) that still need to support a deadline.
+fn spawn_collector(mut pipe: impl Read + Send + 'static) -> (thread::JoinHandle<()>, Arc<Mutex<Vec<u8>>>) {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let buf_thread = buf.clone();
+    let handle = thread::spawn(move || {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => buf_thread.lock().unwrap().extend_from_slice(&chunk[..n]),
+            }
+        }
+    });
+    (handle, buf)
+}
+
+/// Polls `child` until it exits, honoring an optional `deadline` and cancellation flag. Kills
+/// the child and returns `TimedOut`/`Cancelled` if either fires first.
+fn wait_for_exit(
+    child: &mut Child,
+    deadline: Option<Instant>,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(if status.success() {
+                CommandOutcome::Success
+            } else {
+                CommandOutcome::Failed
+            });
+        }
+        if cancel.map_or(false, |c| c.load(Ordering::Relaxed)) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(CommandOutcome::Cancelled);
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(CommandOutcome::TimedOut);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
 
 /// Find a specific tool, checking local directory first, then PATH.
-pub fn find_tool(tool_name: &str) -> Option<PathBuf> {
-    // 1. Check current directory
-    let local_path = Path::new(".").join(tool_name);
-    if local_path.exists() {
-        // Simple check, on unix we might wanna check executable bit but simple existence is usually enough
-        return Some(local_path);
-    }
-
-    // 2. Check PATH
-    // "which" command is a simple cross-platform way if we don't want extra deps,
-    // or just try to spawn it.
-    // However, explicit checking is better for error messages.
-    // For simplicity without 'which' crate:
-    if Command::new("which")
-        .arg(tool_name)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+/// True if `path` is an existing file and (on Unix) has an executable permission bit set for
+/// someone. On Windows there is no permission bit to check, so any existing file counts,
+/// including the bare-name candidate `candidate_names` adds besides the `PATHEXT` ones.
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = path.metadata() else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
     {
-        return Some(PathBuf::from(tool_name));
+        true
+    }
+}
+
+/// Candidate filenames for `tool_name` in one PATH directory: the bare name on Unix, or the
+/// bare name plus each `%PATHEXT%` extension on Windows (so `ffmpeg` resolves to `ffmpeg.exe`).
+fn candidate_names(tool_name: &str) -> Vec<String> {
+    if cfg!(windows) {
+        let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string());
+        let mut names: Vec<String> = pathext
+            .split(';')
+            .filter(|e| !e.is_empty())
+            .map(|ext| format!("{tool_name}{ext}"))
+            .collect();
+        names.push(tool_name.to_string());
+        names
+    } else {
+        vec![tool_name.to_string()]
+    }
+}
+
+/// Resolves `tool_name` to an absolute, executable path: current directory first (matching the
+/// old behavior), then each directory in `$PATH`, in order. Done entirely in-process -- no
+/// `which`/`where` subprocess -- so a `which` shadowed in the local directory can't spoof the
+/// result, and the returned path is absolute so a spawned `Command` doesn't depend on inheriting
+/// the same `PATH` itself.
+pub fn find_tool(tool_name: &str) -> Option<PathBuf> {
+    for name in candidate_names(tool_name) {
+        let local_path = Path::new(".").join(&name);
+        if is_executable(&local_path) {
+            return local_path.canonicalize().ok().or(Some(local_path));
+        }
     }
-    
-     // Windows fallback
-    if cfg!(target_os = "windows") {
-         if Command::new("where")
-            .arg(tool_name)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false) {
-                 return Some(PathBuf::from(tool_name));
+
+    let path_var = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&path_var) {
+        for name in candidate_names(tool_name) {
+            let candidate = dir.join(&name);
+            if is_executable(&candidate) {
+                return Some(candidate);
             }
+        }
     }
 
     None
@@ -50,120 +140,568 @@ pub fn find_tool(tool_name: &str) -> Option<PathBuf> {
 
 /// Run a command and log its output to a file.
 /// Returns true if success code.
-pub fn run_command(cmd: &mut Command, log_path: &Path) -> Result<bool> {
+pub fn run_command(
+    cmd: &mut Command,
+    log_path: &Path,
+    timeout: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
     let log_file = File::create(log_path).context("Failed to create log file")?;
     let mut writer = std::io::BufWriter::new(log_file);
 
     // Write command line for debugging
     writeln!(writer, "Running command: {:?}", cmd)?;
     writer.flush()?;
-    
-    // Redirect stderr to stdout to capture everything
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped()); 
 
-    // Actually, std::process::Command doesn't support "stderr -> stdout" fd redirection easily without shell.
-    // Better to pipe both.
+    // Redirect stderr to stdout to capture everything
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     let mut child = cmd.spawn().context("Failed to spawn command")?;
 
-    let _stdout = child.stdout.take().expect("Failed to open stdout");
-    let _stderr = child.stderr.take().expect("Failed to open stderr");
+    let stdout = child.stdout.take().expect("Failed to open stdout");
+    let stderr = child.stderr.take().expect("Failed to open stderr");
+
+    // Collect both streams on background threads so we can still poll for a deadline/cancel
+    // while the child is running instead of blocking on `wait_with_output`.
+    let (out_handle, out_buf) = spawn_collector(stdout);
+    let (err_handle, err_buf) = spawn_collector(stderr);
+
+    let deadline = timeout.map(|d| Instant::now() + d);
+    let outcome = wait_for_exit(&mut child, deadline, cancel.as_ref())?;
+
+    let _ = out_handle.join();
+    let _ = err_handle.join();
+
+    writer.write_all(&out_buf.lock().unwrap())?;
+    writer.write_all(&err_buf.lock().unwrap())?;
 
-    // We want to stream both to the log file.
-    // We can use threads to drive this.
-    
-    // Simplification: For non-live commands, just wait_with_output is easier,
-    // but we want to log it potentially.
-    // Let's use wait_with_output for simple commands and dump to file.
-    
-    let output = child.wait_with_output()?;
-    
-    writer.write_all(&output.stdout)?;
-    writer.write_all(&output.stderr)?;
-    
-    Ok(output.status.success())
+    match outcome {
+        CommandOutcome::TimedOut => writeln!(writer, "Command timed out and was killed")?,
+        CommandOutcome::Cancelled => writeln!(writer, "Command was cancelled and killed")?,
+        _ => {}
+    }
+
+    Ok(outcome)
+}
+
+/// A stream's carry-over bytes that haven't yet formed a complete UTF-8 sequence, so the
+/// `\r`->`\n` log rewrite doesn't corrupt a multi-byte character split across two reads.
+#[derive(Default)]
+struct LineBuffer(Vec<u8>);
+
+impl LineBuffer {
+    /// Appends `data`, writes out whatever now forms a complete UTF-8 prefix (with `\r`
+    /// rewritten to `\n`) to `log_writer`, and keeps any trailing incomplete bytes for next time.
+    fn forward_to_log(&mut self, data: &[u8], log_writer: &mut impl Write) {
+        self.0.extend_from_slice(data);
+        let valid_len = match std::str::from_utf8(&self.0) {
+            Ok(_) => self.0.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        if valid_len > 0 {
+            let s = String::from_utf8_lossy(&self.0[..valid_len]);
+            let _ = log_writer.write_all(s.replace('\r', "\n").as_bytes());
+            self.0.drain(..valid_len);
+        }
+    }
 }
 
 /// Run a command and stream output to both terminal (stderr mainly for progress) and a log file.
 /// This matches `run_command_live` from Python.
-pub fn run_command_live(cmd: &mut Command, log_path: &Path) -> Result<bool> {
+pub fn run_command_live(
+    cmd: &mut Command,
+    log_path: &Path,
+    timeout: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
     let log_file = File::create(log_path).context("Failed to create log file")?;
-    // We clone the file handle for the threads
     let mut log_writer = std::io::BufWriter::new(log_file);
-    
+
     writeln!(log_writer, "Running command live: {:?}", cmd)?;
 
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     let mut child = cmd.spawn().context("Failed to spawn command")?;
-
     let stdout = child.stdout.take().expect("Stdout capture failed");
     let stderr = child.stderr.take().expect("Stderr capture failed");
+    let deadline = timeout.map(|d| Instant::now() + d);
+
+    #[cfg(unix)]
+    let outcome = pump_live_nonblocking(&mut child, stdout, stderr, &mut log_writer, deadline, cancel.as_ref())?;
+    #[cfg(not(unix))]
+    let outcome = pump_live_threaded(&mut child, stdout, stderr, &mut log_writer, deadline, cancel.as_ref())?;
+
+    match outcome {
+        CommandOutcome::TimedOut => writeln!(log_writer, "Command timed out and was killed")?,
+        CommandOutcome::Cancelled => writeln!(log_writer, "Command was cancelled and killed")?,
+        _ => {}
+    }
+    Ok(outcome)
+}
+
+/// Default pump: forwards both streams on the calling thread instead of spawning a reader
+/// thread per pipe. Both child fds are set non-blocking so a single loop can poll whichever has
+/// data ready, `ErrorKind::WouldBlock` meaning "nothing available right now" rather than EOF.
+#[cfg(unix)]
+fn pump_live_nonblocking(
+    child: &mut Child,
+    mut stdout: std::process::ChildStdout,
+    mut stderr: std::process::ChildStderr,
+    log_writer: &mut impl Write,
+    deadline: Option<Instant>,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
+    use std::os::unix::io::AsRawFd;
+
+    set_nonblocking(stdout.as_raw_fd());
+    set_nonblocking(stderr.as_raw_fd());
+
+    let mut stdout_handle = std::io::stdout();
+    let mut stderr_handle = std::io::stderr();
+    let mut out_buf = LineBuffer::default();
+    let mut err_buf = LineBuffer::default();
+    let mut chunk = [0u8; 4096];
+    let (mut out_eof, mut err_eof) = (false, false);
+
+    loop {
+        let mut made_progress = false;
+
+        if !out_eof {
+            match stdout.read(&mut chunk) {
+                Ok(0) => out_eof = true,
+                Ok(n) => {
+                    made_progress = true;
+                    let _ = stdout_handle.write_all(&chunk[..n]);
+                    let _ = stdout_handle.flush();
+                    out_buf.forward_to_log(&chunk[..n], log_writer);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => out_eof = true,
+            }
+        }
+        if !err_eof {
+            match stderr.read(&mut chunk) {
+                Ok(0) => err_eof = true,
+                Ok(n) => {
+                    made_progress = true;
+                    let _ = stderr_handle.write_all(&chunk[..n]);
+                    let _ = stderr_handle.flush();
+                    err_buf.forward_to_log(&chunk[..n], log_writer);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => err_eof = true,
+            }
+        }
+
+        if out_eof && err_eof {
+            if let Some(status) = child.try_wait()? {
+                return Ok(if status.success() {
+                    CommandOutcome::Success
+                } else {
+                    CommandOutcome::Failed
+                });
+            }
+        }
+
+        if cancel.map_or(false, |c| c.load(Ordering::Relaxed)) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(CommandOutcome::Cancelled);
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(CommandOutcome::TimedOut);
+        }
+
+        if !made_progress {
+            thread::sleep(Duration::from_millis(15));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_nonblocking(fd: std::os::unix::io::RawFd) {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL, 0);
+        if flags >= 0 {
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+        }
+    }
+}
 
-    // Channels to send output back to main thread or just distinct threads handling writing
-    // The Python script uses `select`. In Rust, threads are easier for cross-platform.
-    
+/// Windows fallback: a reader thread per pipe feeding a channel, polled with a deadline so a
+/// hung child can still be killed rather than wedging this call forever.
+#[cfg(not(unix))]
+fn pump_live_threaded(
+    child: &mut Child,
+    stdout: std::process::ChildStdout,
+    stderr: std::process::ChildStderr,
+    log_writer: &mut impl Write,
+    deadline: Option<Instant>,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
     let (tx, rx) = mpsc::channel();
     let tx_err = tx.clone();
-    
+
     let t_out = thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        // We read byte by byte or chunk to preserve exact output (including \r)
-        // copy() might buffer too much? 
-        // Let's just read chunks.
-        let mut reader = reader;
+        let mut reader = BufReader::new(stdout);
         let mut binding = [0u8; 1024];
         while let Ok(n) = reader.read(&mut binding) {
             if n == 0 { break; }
             let _ = tx.send((false, binding[..n].to_vec()));
         }
     });
-
     let t_err = thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        let mut reader = reader;
-         let mut binding = [0u8; 1024];
+        let mut reader = BufReader::new(stderr);
+        let mut binding = [0u8; 1024];
         while let Ok(n) = reader.read(&mut binding) {
             if n == 0 { break; }
             let _ = tx_err.send((true, binding[..n].to_vec()));
         }
     });
 
-    // Main loop: receive from channel, write to log + screen
     let mut stdout_handle = std::io::stdout();
     let mut stderr_handle = std::io::stderr();
-    
-    for (is_err, data) in rx {
-        // Write to log (replacing \r with \n for readability in logs, as python did)
-        // Python: chunk.decode(...).replace('\r', '\n')
-        // We'll just write raw bytes to log? Or try sanitize.
-        // Valid utf8 is safer for replacing strings.
-        let s = String::from_utf8_lossy(&data);
-        let clean_s = s.replace('\r', "\n");
-        let _ = log_writer.write_all(clean_s.as_bytes());
-        
-        // Write to terminal (raw)
-        if is_err {
-            let _ = stderr_handle.write_all(&data);
-            let _ = stderr_handle.flush();
-        } else {
-             let _ = stdout_handle.write_all(&data);
-             let _ = stdout_handle.flush();
+    let mut out_buf = LineBuffer::default();
+    let mut err_buf = LineBuffer::default();
+    let poll_interval = Duration::from_millis(200);
+    let mut outcome = None;
+
+    loop {
+        let wait_for = match deadline {
+            Some(d) => poll_interval.min(d.saturating_duration_since(Instant::now())),
+            None => poll_interval,
+        };
+        match rx.recv_timeout(wait_for) {
+            Ok((is_err, data)) => {
+                if is_err {
+                    let _ = stderr_handle.write_all(&data);
+                    let _ = stderr_handle.flush();
+                    err_buf.forward_to_log(&data, log_writer);
+                } else {
+                    let _ = stdout_handle.write_all(&data);
+                    let _ = stdout_handle.flush();
+                    out_buf.forward_to_log(&data, log_writer);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break, // both senders dropped: EOF on both streams
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if cancel.map_or(false, |c| c.load(Ordering::Relaxed)) {
+                    let _ = child.kill();
+                    outcome = Some(CommandOutcome::Cancelled);
+                    break;
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    let _ = child.kill();
+                    outcome = Some(CommandOutcome::TimedOut);
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = t_out.join();
+    let _ = t_err.join();
+
+    Ok(match outcome {
+        Some(o) => {
+            let _ = child.wait();
+            o
+        }
+        None => {
+            let status = child.wait()?;
+            if status.success() {
+                CommandOutcome::Success
+            } else {
+                CommandOutcome::Failed
+            }
+        }
+    })
+}
+
+/// Incrementally-parsed progress from an ffmpeg/dovi_tool invocation. Fields are `None` until
+/// the corresponding key has appeared at least once in the tool's output.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    pub out_time_ms: Option<u64>,
+    pub speed: Option<f64>,
+    pub done: bool,
+}
+
+/// Parses one line of ffmpeg/dovi_tool output into `progress`, recognizing:
+/// - ffmpeg's `-progress pipe:1` machine-readable `key=value` lines (`frame=`, `fps=`,
+///   `out_time_ms=`, `speed=`, `progress=continue|end`)
+/// - ffmpeg's interactive stderr summary line (`frame=  123 fps= 45 ... time=00:00:05.00
+///   ... speed=1.2x`)
+/// - dovi_tool's bare percentage lines (e.g. "42%" or "Progress: 42%")
+///
+/// Returns `true` if the line produced a recognizable update.
+fn parse_progress_line(line: &str, progress: &mut Progress) -> bool {
+    let line = line.trim();
+    if line.is_empty() {
+        return false;
+    }
+
+    // ffmpeg's interactive stderr summary packs several `key=value` pairs onto one
+    // whitespace-separated line; check for it before the single-pair machine-readable case.
+    if line.starts_with("frame=") && line.contains("time=") {
+        // ffmpeg pads values after '=' (e.g. "frame=  123"); join each key to its value first.
+        let mut joined = line.to_string();
+        while joined.contains("= ") {
+            joined = joined.replace("= ", "=");
+        }
+        for token in joined.split_whitespace() {
+            if let Some(v) = token.strip_prefix("frame=") {
+                progress.frame = v.parse().ok();
+            } else if let Some(v) = token.strip_prefix("fps=") {
+                progress.fps = v.parse().ok();
+            } else if let Some(v) = token.strip_prefix("speed=") {
+                progress.speed = v.trim_end_matches('x').parse().ok();
+            } else if let Some(v) = token.strip_prefix("time=") {
+                if let Some(ms) = parse_ffmpeg_timestamp(v) {
+                    progress.out_time_ms = Some(ms);
+                }
+            }
+        }
+        return true;
+    }
+
+    // ffmpeg `-progress pipe:1` machine-readable `key=value` line.
+    if let Some((key, value)) = line.split_once('=') {
+        let value = value.trim();
+        return match key.trim() {
+            "frame" => {
+                progress.frame = value.parse().ok();
+                true
+            }
+            "fps" => {
+                progress.fps = value.parse().ok();
+                true
+            }
+            "out_time_ms" if value != "N/A" => {
+                progress.out_time_ms = value.parse().ok();
+                true
+            }
+            "speed" => {
+                progress.speed = value.trim_end_matches('x').parse().ok();
+                true
+            }
+            "progress" => {
+                progress.done = value == "end";
+                true
+            }
+            _ => false,
+        };
+    }
+
+    // dovi_tool's bare percentage line, e.g. "42%" or "Progress: 42%".
+    if let Some(pct) = line.strip_suffix('%') {
+        let pct = pct.rsplit(' ').next().unwrap_or(pct);
+        if pct.parse::<f64>().is_ok() {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Parses an ffmpeg `HH:MM:SS.ms` timestamp into milliseconds.
+fn parse_ffmpeg_timestamp(s: &str) -> Option<u64> {
+    let mut parts = s.splitn(3, ':');
+    let h: f64 = parts.next()?.parse().ok()?;
+    let m: f64 = parts.next()?.parse().ok()?;
+    let sec: f64 = parts.next()?.parse().ok()?;
+    Some(((h * 3600.0 + m * 60.0 + sec) * 1000.0).round() as u64)
+}
+
+enum StreamMsg {
+    Raw(bool, Vec<u8>),
+    Progress(Progress),
+}
+
+/// Reads `pipe` in 1 KiB chunks, forwarding every chunk as `StreamMsg::Raw` and, whenever a
+/// complete line has accumulated, parsing it for progress and forwarding `StreamMsg::Progress`
+/// updates. Lines can arrive split across read chunks, so partial lines are buffered until a
+/// `\n` or `\r` (ffmpeg rewrites its progress line in place with `\r`) is seen.
+fn pump_with_progress(pipe: impl Read, is_err: bool, tx: mpsc::Sender<StreamMsg>) {
+    let mut reader = BufReader::new(pipe);
+    let mut chunk = [0u8; 1024];
+    let mut line_buf: Vec<u8> = Vec::new();
+    let mut progress = Progress::default();
+
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let _ = tx.send(StreamMsg::Raw(is_err, chunk[..n].to_vec()));
+
+        line_buf.extend_from_slice(&chunk[..n]);
+        while let Some(pos) = line_buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+            let line_bytes: Vec<u8> = line_buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line_bytes);
+            if parse_progress_line(&line, &mut progress) {
+                let _ = tx.send(StreamMsg::Progress(progress.clone()));
+                if progress.done {
+                    progress = Progress::default();
+                }
+            }
+        }
+    }
+}
+
+/// Like [`run_command_live`], but also parses ffmpeg/dovi_tool progress output incrementally
+/// and invokes `on_progress` for each recognized update, so callers can drive a progress bar
+/// with a known total instead of dumping raw stderr.
+pub fn run_command_progress(
+    cmd: &mut Command,
+    log_path: &Path,
+    mut on_progress: impl FnMut(Progress),
+) -> Result<bool> {
+    let log_file = File::create(log_path).context("Failed to create log file")?;
+    let mut log_writer = std::io::BufWriter::new(log_file);
+
+    writeln!(log_writer, "Running command (progress): {:?}", cmd)?;
+
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+
+    let mut child = cmd.spawn().context("Failed to spawn command")?;
+
+    let stdout = child.stdout.take().expect("Stdout capture failed");
+    let stderr = child.stderr.take().expect("Stderr capture failed");
+
+    let (tx, rx) = mpsc::channel();
+    let tx_err = tx.clone();
+
+    let t_out = thread::spawn(move || pump_with_progress(stdout, false, tx));
+    let t_err = thread::spawn(move || pump_with_progress(stderr, true, tx_err));
+
+    let mut stdout_handle = std::io::stdout();
+    let mut stderr_handle = std::io::stderr();
+
+    for msg in rx {
+        match msg {
+            StreamMsg::Raw(is_err, data) => {
+                let s = String::from_utf8_lossy(&data);
+                let clean_s = s.replace('\r', "\n");
+                let _ = log_writer.write_all(clean_s.as_bytes());
+
+                if is_err {
+                    let _ = stderr_handle.write_all(&data);
+                    let _ = stderr_handle.flush();
+                } else {
+                    let _ = stdout_handle.write_all(&data);
+                    let _ = stdout_handle.flush();
+                }
+            }
+            StreamMsg::Progress(p) => on_progress(p),
         }
     }
-    
-    // Close up
+
     let _ = t_out.join();
     let _ = t_err.join();
-    
+
     let status = child.wait()?;
     Ok(status.success())
 }
 
+/// A tool's minimum supported version, how to ask it for its version, and how to pull the
+/// semver triple out of whatever it prints. `version_arg` is passed as a single argument (tools
+/// here all accept a single `--version`-style flag); `regex` must have three capture groups
+/// (major, minor, patch) -- a missing patch group (e.g. "2.1") is treated as patch 0.
+struct VersionSpec {
+    tool: &'static str,
+    min_version: (u32, u32, u32),
+    version_arg: &'static str,
+    regex: &'static str,
+}
+
+const VERSION_TABLE: &[VersionSpec] = &[
+    VersionSpec {
+        tool: "ffmpeg",
+        min_version: (6, 0, 0),
+        version_arg: "-version",
+        regex: r"ffmpeg version n?(\d+)\.(\d+)(?:\.(\d+))?",
+    },
+    VersionSpec {
+        tool: "dovi_tool",
+        min_version: (2, 1, 0),
+        version_arg: "--version",
+        regex: r"dovi_tool (\d+)\.(\d+)(?:\.(\d+))?",
+    },
+    VersionSpec {
+        tool: "mkvmerge",
+        min_version: (75, 0, 0),
+        version_arg: "--version",
+        regex: r"mkvmerge v(\d+)\.(\d+)(?:\.(\d+))?",
+    },
+];
+
+/// The result of checking one tool against its [`VersionSpec`]: the version found (if the tool
+/// could be located and its output parsed) and whether it meets `min_version`.
+pub struct DependencyReport {
+    pub tool: String,
+    pub path: Option<PathBuf>,
+    pub version: Option<(u32, u32, u32)>,
+    pub satisfies_min: bool,
+}
+
+/// Runs each tool in [`VERSION_TABLE`] with its version flag, extracts a semver triple with its
+/// regex, and reports whether it meets the minimum this codebase was tested against. A tool
+/// that can't be found or whose output doesn't match the regex is reported with `version: None`
+/// and `satisfies_min: false` rather than erroring, so the caller can print one unified table.
+pub fn verify_versions() -> Vec<DependencyReport> {
+    VERSION_TABLE
+        .iter()
+        .map(|spec| {
+            let path = find_tool(spec.tool);
+            let Some(path) = path else {
+                return DependencyReport {
+                    tool: spec.tool.to_string(),
+                    path: None,
+                    version: None,
+                    satisfies_min: false,
+                };
+            };
+
+            let output = Command::new(&path).arg(spec.version_arg).output();
+            let version = output.ok().and_then(|o| {
+                let combined = format!(
+                    "{}\n{}",
+                    String::from_utf8_lossy(&o.stdout),
+                    String::from_utf8_lossy(&o.stderr)
+                );
+                extract_version(&combined, spec.regex)
+            });
+
+            let satisfies_min = version.map_or(false, |v| v >= spec.min_version);
+            DependencyReport {
+                tool: spec.tool.to_string(),
+                path: Some(path),
+                version,
+                satisfies_min,
+            }
+        })
+        .collect()
+}
+
+/// Applies `pattern` (three capture groups: major, minor, optional patch) to `text` and returns
+/// the first match as a `(major, minor, patch)` triple, defaulting patch to 0 if absent.
+fn extract_version(text: &str, pattern: &str) -> Option<(u32, u32, u32)> {
+    let re = regex::Regex::new(pattern).ok()?;
+    let caps = re.captures(text)?;
+    let major = caps.get(1)?.as_str().parse().ok()?;
+    let minor = caps.get(2)?.as_str().parse().ok()?;
+    let patch = caps.get(3).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+    Some((major, minor, patch))
+}
+
 pub fn check_dependencies() -> Result<()> {
     let required = ["ffmpeg", "mkvmerge"];
     let mut missing = false;
@@ -188,6 +726,40 @@ pub fn check_dependencies() -> Result<()> {
     if missing {
         anyhow::bail!("Missing dependencies");
     }
+
+    println!("{}", "Checking tool versions:".bold());
+    let mut outdated = false;
+    for report in verify_versions() {
+        let version_str = report
+            .version
+            .map(|(a, b, c)| format!("{a}.{b}.{c}"))
+            .unwrap_or_else(|| "unknown".to_string());
+        if report.path.is_none() {
+            // Already reported as missing above (ffmpeg/mkvmerge/dovi_tool); mediainfo/ffprobe
+            // aren't in VERSION_TABLE, so this only covers the required trio.
+            continue;
+        }
+        if report.satisfies_min {
+            println!("  {} {} {}", "OK".green(), report.tool, version_str);
+        } else {
+            let spec = VERSION_TABLE.iter().find(|s| s.tool == report.tool).unwrap();
+            let (min_a, min_b, min_c) = spec.min_version;
+            println!(
+                "  {} {} {} (requires >= {}.{}.{})",
+                "OUTDATED".red(),
+                report.tool,
+                version_str,
+                min_a,
+                min_b,
+                min_c
+            );
+            outdated = true;
+        }
+    }
+
+    if outdated {
+        anyhow::bail!("One or more tools are older than the minimum version this codebase was tested against");
+    }
     Ok(())
 }
 
@@ -195,9 +767,9 @@ pub fn check_dependencies() -> Result<()> {
 pub fn get_command_output(cmd: &mut Command) -> Result<String> {
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::null()); // Silence stderr for data fetching commands usually
-    
+
     let output = cmd.output().context("Failed to execute command")?;
-    
+
     if output.status.success() {
         let s = String::from_utf8(output.stdout).context("Command output is not valid UTF-8")?;
         Ok(s)
@@ -206,11 +778,57 @@ pub fn get_command_output(cmd: &mut Command) -> Result<String> {
     }
 }
 
+/// Process-lifetime cache for [`get_command_output_cached`], keyed by a command's binary and
+/// arguments (the arguments already include whatever input path the command reads, so no
+/// separate path component is needed).
+static COMMAND_CACHE: std::sync::OnceLock<Mutex<std::collections::HashMap<(String, String), Result<String, String>>>> =
+    std::sync::OnceLock::new();
+
+fn command_cache() -> &'static Mutex<std::collections::HashMap<(String, String), Result<String, String>>> {
+    COMMAND_CACHE.get_or_init(|| Mutex::new(std::collections::HashMap::new()))
+}
+
+fn command_cache_key(cmd: &Command) -> (String, String) {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let args = cmd
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("\u{1}");
+    (program, args)
+}
+
+/// Same as [`get_command_output`], but memoized for the process lifetime. `mediainfo`/`ffprobe`
+/// can otherwise get invoked on the same input several times in one run (HDR format check,
+/// static metadata, duration, ...), which is slow on network mounts or large files. Callers that
+/// need a guaranteed-fresh read (e.g. after the file has changed on disk) should call the
+/// uncached `get_command_output` directly, or `clear_command_cache` first.
+pub fn get_command_output_cached(cmd: &mut Command) -> Result<String> {
+    let key = command_cache_key(cmd);
+    if let Some(cached) = command_cache().lock().unwrap().get(&key) {
+        return cached.clone().map_err(|e| anyhow::anyhow!(e));
+    }
+    let result = get_command_output(cmd);
+    let cached = result.as_ref().map(|s| s.clone()).map_err(|e| e.to_string());
+    command_cache().lock().unwrap().insert(key, cached);
+    result
+}
+
+/// Clears the process-lifetime cache used by [`get_command_output_cached`].
+pub fn clear_command_cache() {
+    command_cache().lock().unwrap().clear();
+}
+
 /// Run a command, inheriting stderr (so progress bars work naturally) but capturing/logging stdout.
-pub fn run_command_inherit_stderr(cmd: &mut Command, log_path: &Path) -> Result<bool> {
+pub fn run_command_inherit_stderr(
+    cmd: &mut Command,
+    log_path: &Path,
+    timeout: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<CommandOutcome> {
     let log_file = File::create(log_path).context("Failed to create log file")?;
     let mut log_writer = std::io::BufWriter::new(log_file);
-    
+
     writeln!(log_writer, "Running command (stderr inherited): {:?}", cmd)?;
 
     cmd.stdout(Stdio::piped());
@@ -222,7 +840,7 @@ pub fn run_command_inherit_stderr(cmd: &mut Command, log_path: &Path) -> Result<
 
     // We only need one thread for stdout
     let (tx, rx) = mpsc::channel();
-    
+
     let t_out = thread::spawn(move || {
         let reader = BufReader::new(stdout);
         let mut reader = reader;
@@ -234,20 +852,61 @@ pub fn run_command_inherit_stderr(cmd: &mut Command, log_path: &Path) -> Result<
     });
 
     let mut stdout_handle = std::io::stdout();
-    
-    for data in rx {
-        // Write to log
-        let s = String::from_utf8_lossy(&data);
-        let clean_s = s.replace('\r', "\n");
-        let _ = log_writer.write_all(clean_s.as_bytes());
-        
-        // Write to terminal
-        let _ = stdout_handle.write_all(&data);
-        let _ = stdout_handle.flush();
-    }
-    
+
+    let deadline = timeout.map(|d| Instant::now() + d);
+    let poll_interval = Duration::from_millis(200);
+    let mut outcome = None;
+
+    loop {
+        let wait_for = match deadline {
+            Some(d) => poll_interval.min(d.saturating_duration_since(Instant::now())),
+            None => poll_interval,
+        };
+        match rx.recv_timeout(wait_for) {
+            Ok(data) => {
+                let s = String::from_utf8_lossy(&data);
+                let clean_s = s.replace('\r', "\n");
+                let _ = log_writer.write_all(clean_s.as_bytes());
+
+                let _ = stdout_handle.write_all(&data);
+                let _ = stdout_handle.flush();
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+                    let _ = child.kill();
+                    outcome = Some(CommandOutcome::Cancelled);
+                    break;
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    let _ = child.kill();
+                    outcome = Some(CommandOutcome::TimedOut);
+                    break;
+                }
+            }
+        }
+    }
+
     let _ = t_out.join();
-    
-    let status = child.wait()?;
-    Ok(status.success())
+
+    let outcome = match outcome {
+        Some(o) => {
+            let _ = child.wait();
+            o
+        }
+        None => {
+            let status = child.wait()?;
+            if status.success() {
+                CommandOutcome::Success
+            } else {
+                CommandOutcome::Failed
+            }
+        }
+    };
+    match outcome {
+        CommandOutcome::TimedOut => writeln!(log_writer, "Command timed out and was killed")?,
+        CommandOutcome::Cancelled => writeln!(log_writer, "Command was cancelled and killed")?,
+        _ => {}
+    }
+    Ok(outcome)
 }
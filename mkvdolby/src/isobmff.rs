@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::metadata::HdrFormat;
+
+/// A single parsed ISOBMFF box header: its four-character-code type, and the (offset, length)
+/// of its payload within the file (after the size+type header).
+struct BoxHeader {
+    box_type: [u8; 4],
+    payload_offset: u64,
+    payload_len: u64,
+}
+
+/// Walks the sibling boxes within `[start, end)` of `file`. Handles both the ordinary 32-bit
+/// size and the 64-bit `largesize` extension (size field == 1), a size of 0 meaning "box
+/// extends to the end of the range", and `uuid` boxes' extra 16-byte extended type.
+fn read_boxes(file: &mut File, start: u64, end: u64) -> std::io::Result<Vec<BoxHeader>> {
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let box_type: [u8; 4] = header[4..8].try_into().unwrap();
+
+        let mut header_len = 8u64;
+        if size == 1 {
+            let mut ext = [0u8; 8];
+            file.read_exact(&mut ext)?;
+            size = u64::from_be_bytes(ext);
+            header_len = 16;
+        } else if size == 0 {
+            size = end - pos;
+        }
+        if &box_type == b"uuid" {
+            header_len += 16;
+        }
+        if size < header_len || pos + size > end {
+            break;
+        }
+
+        boxes.push(BoxHeader {
+            box_type,
+            payload_offset: pos + header_len,
+            payload_len: size - header_len,
+        });
+        pos += size;
+    }
+    Ok(boxes)
+}
+
+fn find_box<'a>(boxes: &'a [BoxHeader], name: &[u8; 4]) -> Option<&'a BoxHeader> {
+    boxes.iter().find(|b| &b.box_type == name)
+}
+
+fn read_payload(file: &mut File, b: &BoxHeader) -> std::io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(b.payload_offset))?;
+    let mut buf = vec![0u8; b.payload_len as usize];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// A HEVC visual sample entry's fixed fields (reserved[6], data_reference_index, then the
+/// video-specific fixed block: version/revision/vendor, temporal/spatial quality, width/height,
+/// resolution, frame_count, compressorname, depth, pre_defined) occupy 78 bytes before its
+/// child boxes (`hvcC`, `colr`, `mdcv`, `clli`, `dvcC`/`dvvC`, ...) begin.
+const VISUAL_SAMPLE_ENTRY_FIXED_SIZE: u64 = 78;
+
+/// Parses the file's ISOBMFF box tree in-process -- `moov > trak > mdia > minf > stbl > stsd`,
+/// then the `hvc1`/`hev1` HEVC sample entry and its child boxes -- to classify HDR format and
+/// read static metadata directly, without shelling out to mediainfo/dovi_tool.
+///
+/// Returns `None` for non-ISOBMFF inputs (no `ftyp` box -- e.g. MPEG-TS or Matroska), or if the
+/// expected box chain isn't present, so the caller can fall back to the external-tool path.
+pub fn parse_isobmff_hdr(input_file: &str) -> Option<(HdrFormat, HashMap<String, f64>)> {
+    let tracks = parse_isobmff_hdr_tracks(input_file)?;
+    let (_, format, meta) = tracks.into_iter().next()?;
+    Some((format, meta))
+}
+
+/// Like [`parse_isobmff_hdr`], but walks every `trak` in `moov` instead of stopping at the
+/// first one with an `hvc1`/`hev1` sample entry. Returns one `(track_index, format, metadata)`
+/// entry per video track found, in track order, with `track_index` 0-based among those video
+/// tracks (not the container's own `trak`/track-ID numbering). Returns `None` for non-ISOBMFF
+/// inputs, same as `parse_isobmff_hdr`; returns `Some(vec![])` if `moov` has no video track with
+/// a recognized sample entry.
+pub fn parse_isobmff_hdr_tracks(input_file: &str) -> Option<Vec<(usize, HdrFormat, HashMap<String, f64>)>> {
+    let path = Path::new(input_file);
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    let top = read_boxes(&mut file, 0, file_len).ok()?;
+    find_box(&top, b"ftyp")?;
+    let moov = find_box(&top, b"moov")?;
+    let moov_boxes =
+        read_boxes(&mut file, moov.payload_offset, moov.payload_offset + moov.payload_len).ok()?;
+
+    let mut tracks = Vec::new();
+    for trak in moov_boxes.iter().filter(|b| &b.box_type == b"trak") {
+        let Some((format, meta)) = classify_video_trak(&mut file, trak) else {
+            continue;
+        };
+        tracks.push((tracks.len(), format, meta));
+    }
+
+    Some(tracks)
+}
+
+/// Classifies a single `trak` box's HDR format and static metadata, by walking
+/// `mdia > minf > stbl > stsd` down to its `hvc1`/`hev1` sample entry. Returns `None` if the
+/// track isn't a recognized HEVC video track (e.g. it's an audio/subtitle track, or uses a
+/// sample entry this parser doesn't know about).
+fn classify_video_trak(file: &mut File, trak: &BoxHeader) -> Option<(HdrFormat, HashMap<String, f64>)> {
+    let trak_boxes = read_boxes(file, trak.payload_offset, trak.payload_offset + trak.payload_len).ok()?;
+    let mdia = find_box(&trak_boxes, b"mdia")?;
+    let mdia_boxes =
+        read_boxes(file, mdia.payload_offset, mdia.payload_offset + mdia.payload_len).ok()?;
+    let minf = find_box(&mdia_boxes, b"minf")?;
+    let minf_boxes =
+        read_boxes(file, minf.payload_offset, minf.payload_offset + minf.payload_len).ok()?;
+    let stbl = find_box(&minf_boxes, b"stbl")?;
+    let stbl_boxes =
+        read_boxes(file, stbl.payload_offset, stbl.payload_offset + stbl.payload_len).ok()?;
+    let stsd = find_box(&stbl_boxes, b"stsd")?;
+
+    // stsd is a FullBox (4-byte version/flags) followed by a 4-byte entry_count, then the
+    // sample entries themselves.
+    if stsd.payload_len < 8 {
+        return None;
+    }
+    let entries = read_boxes(
+        file,
+        stsd.payload_offset + 8,
+        stsd.payload_offset + stsd.payload_len,
+    )
+    .ok()?;
+    let sample_entry = entries
+        .iter()
+        .find(|b| &b.box_type == b"hvc1" || &b.box_type == b"hev1")?;
+    if sample_entry.payload_len <= VISUAL_SAMPLE_ENTRY_FIXED_SIZE {
+        return None;
+    }
+
+    let child_start = sample_entry.payload_offset + VISUAL_SAMPLE_ENTRY_FIXED_SIZE;
+    let child_end = sample_entry.payload_offset + sample_entry.payload_len;
+    let children = read_boxes(file, child_start, child_end).ok()?;
+
+    let has_dv = find_box(&children, b"dvcC").is_some() || find_box(&children, b"dvvC").is_some();
+
+    let mut transfer_characteristics: Option<u16> = None;
+    if let Some(colr) = find_box(&children, b"colr") {
+        if let Ok(payload) = read_payload(file, colr) {
+            // "nclx" colour_type: 2-byte primaries, 2-byte transfer, 2-byte matrix, 1-byte
+            // full_range flag.
+            if payload.len() >= 11 && &payload[0..4] == b"nclx" {
+                transfer_characteristics = Some(u16::from_be_bytes([payload[6], payload[7]]));
+            }
+        }
+    }
+
+    let mut meta = HashMap::new();
+    if let Some(mdcv) = find_box(&children, b"mdcv") {
+        if let Ok(payload) = read_payload(file, mdcv) {
+            // SMPTE ST 2086 mastering display color volume: 3x(primary x,y) + white point
+            // (x,y) as u16 in 0.00002 units, then max/min luminance as u32 in 0.0001 cd/m^2
+            // units.
+            if payload.len() >= 24 {
+                let u16_at = |off: usize| u16::from_be_bytes(payload[off..off + 2].try_into().unwrap());
+                for (i, label) in ["r", "g", "b"].iter().enumerate() {
+                    meta.insert(format!("primary_{label}_x"), u16_at(i * 4) as f64 * 0.00002);
+                    meta.insert(format!("primary_{label}_y"), u16_at(i * 4 + 2) as f64 * 0.00002);
+                }
+                meta.insert("white_point_x".to_string(), u16_at(12) as f64 * 0.00002);
+                meta.insert("white_point_y".to_string(), u16_at(14) as f64 * 0.00002);
+
+                let max_lum = u32::from_be_bytes(payload[16..20].try_into().unwrap());
+                let min_lum = u32::from_be_bytes(payload[20..24].try_into().unwrap());
+                meta.insert("max_dml".to_string(), max_lum as f64 * 0.0001);
+                meta.insert("min_dml".to_string(), min_lum as f64 * 0.0001);
+            }
+        }
+    }
+    if let Some(clli) = find_box(&children, b"clli") {
+        if let Ok(payload) = read_payload(file, clli) {
+            if payload.len() >= 4 {
+                let max_cll = u16::from_be_bytes(payload[0..2].try_into().unwrap());
+                let max_fall = u16::from_be_bytes(payload[2..4].try_into().unwrap());
+                meta.insert("max_cll".to_string(), max_cll as f64);
+                meta.insert("max_fall".to_string(), max_fall as f64);
+            }
+        }
+    }
+
+    let format = if has_dv {
+        // A baked-in Dolby Vision configuration record implies at least HDR10+-grade
+        // source metadata is already present.
+        HdrFormat::Hdr10Plus
+    } else {
+        match transfer_characteristics {
+            Some(18) => HdrFormat::Hlg, // ARIB STD-B67
+            Some(16) => {
+                // SMPTE ST 2084 (PQ)
+                if meta.contains_key("max_cll") || meta.contains_key("max_dml") {
+                    HdrFormat::Hdr10WithMeasurements
+                } else {
+                    HdrFormat::Hdr10Unsupported
+                }
+            }
+            _ => HdrFormat::Unsupported,
+        }
+    };
+
+    Some((format, meta))
+}
+
+/// Reads the movie duration (in seconds) straight from `moov > mvhd`, without mediainfo. `mvhd`
+/// is a FullBox: 1-byte version + 3-byte flags, then (version 0) 32-bit creation/modification
+/// time, 32-bit timescale, 32-bit duration, or (version 1) the same fields widened to 64 bits.
+pub fn parse_isobmff_duration(input_file: &str) -> Option<f64> {
+    let path = Path::new(input_file);
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    let top = read_boxes(&mut file, 0, file_len).ok()?;
+    find_box(&top, b"ftyp")?;
+    let moov = find_box(&top, b"moov")?;
+    let moov_boxes =
+        read_boxes(&mut file, moov.payload_offset, moov.payload_offset + moov.payload_len).ok()?;
+    let mvhd = find_box(&moov_boxes, b"mvhd")?;
+    let payload = read_payload(&mut file, mvhd).ok()?;
+    if payload.is_empty() {
+        return None;
+    }
+    let version = payload[0];
+    let (timescale, duration) = if version == 1 {
+        if payload.len() < 4 + 8 + 8 + 4 + 8 {
+            return None;
+        }
+        let timescale = u32::from_be_bytes(payload[20..24].try_into().unwrap());
+        let duration = u64::from_be_bytes(payload[24..32].try_into().unwrap());
+        (timescale, duration)
+    } else {
+        if payload.len() < 4 + 4 + 4 + 4 + 4 {
+            return None;
+        }
+        let timescale = u32::from_be_bytes(payload[12..16].try_into().unwrap());
+        let duration = u32::from_be_bytes(payload[16..20].try_into().unwrap()) as u64;
+        (timescale, duration)
+    };
+    if timescale == 0 {
+        return None;
+    }
+    Some(duration as f64 / timescale as f64)
+}
+
+/// Reserves 4 bytes for the box size, writes `fourcc` and whatever `body` appends, then
+/// back-patches the size with the box's total length (GStreamer's muxer uses this same
+/// reserve-then-patch pattern for building ISOBMFF boxes incrementally).
+fn write_box(buf: &mut Vec<u8>, fourcc: &[u8; 4], body: impl FnOnce(&mut Vec<u8>)) {
+    let size_offset = buf.len();
+    buf.extend_from_slice(&[0u8; 4]); // placeholder, patched below
+    buf.extend_from_slice(fourcc);
+    body(buf);
+    let size = (buf.len() - size_offset) as u32;
+    buf[size_offset..size_offset + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+/// Builds the `colr`/`mdcv`/`clli` boxes that signal static HDR metadata in an MP4 video sample
+/// entry, ready to splice in after the sample entry's existing child boxes (e.g. `hvcC`).
+/// Returns an empty buffer for `HdrFormat::Unsupported`, since there's nothing useful to signal.
+pub fn build_hdr_boxes(metadata: &HashMap<String, f64>, format: HdrFormat) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if format == HdrFormat::Unsupported {
+        return buf;
+    }
+
+    let transfer: u16 = if format == HdrFormat::Hlg { 18 } else { 16 }; // ARIB STD-B67 or ST 2084
+    // BT.2020 (code 9) is the default for both fields; `--primaries`/`--matrix` can override it.
+    let primaries = metadata.get("color_primaries").copied().unwrap_or(9.0) as u16;
+    let matrix = metadata.get("matrix_coefficients").copied().unwrap_or(9.0) as u16;
+
+    write_box(&mut buf, b"colr", |v| {
+        v.extend_from_slice(b"nclx");
+        v.extend_from_slice(&primaries.to_be_bytes()); // colour_primaries
+        v.extend_from_slice(&transfer.to_be_bytes()); // transfer_characteristics
+        v.extend_from_slice(&matrix.to_be_bytes()); // matrix_coefficients
+        v.push(0); // full_range_flag (0) + 7 reserved bits
+    });
+
+    let max_dml = metadata.get("max_dml").copied().unwrap_or(1000.0);
+    let min_dml = metadata.get("min_dml").copied().unwrap_or(0.0050);
+    write_box(&mut buf, b"mdcv", |v| {
+        // display_primaries[3] (R, G, B) then white_point, each an (x, y) pair in 0.00002 units;
+        // BT.2020 primaries/D65 white point as a sane default when not supplied.
+        let r_x = metadata.get("primary_r_x").copied().unwrap_or(0.708);
+        let r_y = metadata.get("primary_r_y").copied().unwrap_or(0.292);
+        let g_x = metadata.get("primary_g_x").copied().unwrap_or(0.170);
+        let g_y = metadata.get("primary_g_y").copied().unwrap_or(0.797);
+        let b_x = metadata.get("primary_b_x").copied().unwrap_or(0.131);
+        let b_y = metadata.get("primary_b_y").copied().unwrap_or(0.046);
+        let wp_x = metadata.get("white_point_x").copied().unwrap_or(0.3127);
+        let wp_y = metadata.get("white_point_y").copied().unwrap_or(0.3290);
+        for coord in [r_x, r_y, g_x, g_y, b_x, b_y, wp_x, wp_y] {
+            v.extend_from_slice(&((coord / 0.00002).round() as u16).to_be_bytes());
+        }
+        v.extend_from_slice(&((max_dml / 0.0001).round() as u32).to_be_bytes());
+        v.extend_from_slice(&((min_dml / 0.0001).round() as u32).to_be_bytes());
+    });
+
+    let max_cll = metadata.get("max_cll").copied().unwrap_or(1000.0).round() as u16;
+    let max_fall = metadata.get("max_fall").copied().unwrap_or(400.0).round() as u16;
+    write_box(&mut buf, b"clli", |v| {
+        v.extend_from_slice(&max_cll.to_be_bytes());
+        v.extend_from_slice(&max_fall.to_be_bytes());
+    });
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads one box's `(size, fourcc, payload)` off the front of `buf` and returns the rest.
+    fn take_box(buf: &[u8]) -> ((u32, [u8; 4], &[u8]), &[u8]) {
+        let size = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+        let mut fourcc = [0u8; 4];
+        fourcc.copy_from_slice(&buf[4..8]);
+        let payload = &buf[8..size as usize];
+        ((size, fourcc, payload), &buf[size as usize..])
+    }
+
+    #[test]
+    fn test_build_hdr_boxes_empty_for_unsupported() {
+        assert!(build_hdr_boxes(&HashMap::new(), HdrFormat::Unsupported).is_empty());
+    }
+
+    #[test]
+    fn test_build_hdr_boxes_layout_and_back_patched_sizes() {
+        let mut metadata = HashMap::new();
+        metadata.insert("color_primaries".to_string(), 9.0);
+        metadata.insert("matrix_coefficients".to_string(), 9.0);
+        metadata.insert("max_dml".to_string(), 1000.0);
+        metadata.insert("min_dml".to_string(), 0.0050);
+        metadata.insert("max_cll".to_string(), 1000.0);
+        metadata.insert("max_fall".to_string(), 400.0);
+
+        let buf = build_hdr_boxes(&metadata, HdrFormat::Hdr10WithMeasurements);
+
+        let ((colr_size, colr_fourcc, colr_payload), rest) = take_box(&buf);
+        assert_eq!(&colr_fourcc, b"colr");
+        assert_eq!(colr_size as usize, 8 + colr_payload.len());
+        assert_eq!(colr_payload.len(), 11); // "nclx" + 3x u16 + 1 reserved byte
+        assert_eq!(&colr_payload[0..4], b"nclx");
+        assert_eq!(u16::from_be_bytes(colr_payload[4..6].try_into().unwrap()), 9); // primaries
+        assert_eq!(u16::from_be_bytes(colr_payload[6..8].try_into().unwrap()), 16); // ST 2084
+        assert_eq!(u16::from_be_bytes(colr_payload[8..10].try_into().unwrap()), 9); // matrix
+        assert_eq!(colr_payload[10], 0);
+
+        let ((mdcv_size, mdcv_fourcc, mdcv_payload), rest) = take_box(rest);
+        assert_eq!(&mdcv_fourcc, b"mdcv");
+        assert_eq!(mdcv_size as usize, 8 + mdcv_payload.len());
+        assert_eq!(mdcv_payload.len(), 8 * 2 + 4 + 4); // 8 coords (u16) + max/min display mastering luminance (u32)
+        let max_dml = u32::from_be_bytes(mdcv_payload[16..20].try_into().unwrap());
+        let min_dml = u32::from_be_bytes(mdcv_payload[20..24].try_into().unwrap());
+        assert_eq!(max_dml, (1000.0 / 0.0001) as u32);
+        assert_eq!(min_dml, (0.0050 / 0.0001_f64).round() as u32);
+
+        let ((clli_size, clli_fourcc, clli_payload), rest) = take_box(rest);
+        assert_eq!(&clli_fourcc, b"clli");
+        assert_eq!(clli_size as usize, 8 + clli_payload.len());
+        assert_eq!(u16::from_be_bytes(clli_payload[0..2].try_into().unwrap()), 1000);
+        assert_eq!(u16::from_be_bytes(clli_payload[2..4].try_into().unwrap()), 400);
+
+        assert!(rest.is_empty());
+        assert_eq!(
+            buf.len(),
+            colr_size as usize + mdcv_size as usize + clli_size as usize
+        );
+    }
+
+    #[test]
+    fn test_build_hdr_boxes_hlg_uses_arib_transfer() {
+        let buf = build_hdr_boxes(&HashMap::new(), HdrFormat::Hlg);
+        let ((_, _, colr_payload), _) = take_box(&buf);
+        assert_eq!(u16::from_be_bytes(colr_payload[6..8].try_into().unwrap()), 18);
+    }
+}
@@ -3,11 +3,15 @@ use colored::Colorize;
 
 mod cli;
 mod external;
+mod isobmff;
 mod metadata;
 mod pipeline;
+mod progress;
 mod verify;
+mod watch;
 
 use cli::Args;
+use progress::ParallelProgress;
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
@@ -33,6 +37,9 @@ fn main() -> anyhow::Result<()> {
             cli::PeakSource::Histogram99 => {
                 // Already default, no op
             }
+            cli::PeakSource::Percentile(_) => {
+                // User specified an exact percentile explicitly; leave it as-is.
+            }
         }
     }
     // Note: We need to propagate the modified peak_source to the pipeline maybe via a modified Args struct
@@ -56,8 +63,25 @@ fn main() -> anyhow::Result<()> {
         anyhow::bail!("--trim-targets cannot be empty");
     }
 
+    // Validate x265 rate-control overrides up front so a bad value fails before a long
+    // encode starts, rather than erroring out mid-transcode.
+    if let Some(aq_mode) = final_args.hlg_aq_mode {
+        if aq_mode > 4 {
+            anyhow::bail!("--hlg-aq-mode must be between 0 and 4, got {aq_mode}");
+        }
+    }
+    if let Some(qcomp) = final_args.hlg_qcomp {
+        if !(0.0..=1.0).contains(&qcomp) {
+            anyhow::bail!("--hlg-qcomp must be between 0.0 and 1.0, got {qcomp}");
+        }
+    }
+
     println!("{} mkvdolby", "Starting".green().bold());
 
+    if let Some(dir) = &final_args.watch {
+        return watch::run_watch(dir, &final_args);
+    }
+
     // Process files
     let mut files = final_args.input.clone();
     if files.is_empty() {
@@ -76,34 +100,55 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
-    let mut had_failure = false;
-    for file in files {
-        // Skip already converted
-        if file.ends_with(".DV.mkv") {
-            println!(
-                "{}",
-                format!("Skipping already converted file: {}", file).yellow()
-            );
-            continue;
-        }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(final_args.jobs.max(1))
+        .build()
+        .map_err(|err| anyhow::anyhow!("Failed to build conversion thread pool: {err}"))?;
 
-        match pipeline::convert_file(&file, &final_args) {
-            Ok(success) => {
-                if !success {
-                    had_failure = true;
-                }
-            }
-            Err(e) => {
+    // One aggregate bar over all files plus one child bar per in-flight file, so the rayon
+    // conversion pool renders as a grouped job view instead of each worker's `println!`s
+    // interleaving on a single line.
+    let parallel_progress = ParallelProgress::new(files.len() as u64, "Converting files");
+
+    let had_failure = std::sync::atomic::AtomicBool::new(false);
+    pool.install(|| {
+        use rayon::prelude::*;
+        files.par_iter().for_each(|file| {
+            let worker = parallel_progress.add_worker(1, file);
+
+            // Skip already converted
+            if file.ends_with(".DV.mkv") {
                 println!(
                     "{}",
-                    format!("Error processing file '{}': {}", file, e).red()
+                    format!("Skipping already converted file: {}", file).yellow()
                 );
-                had_failure = true;
+                worker.finish();
+                parallel_progress.inc_aggregate(1);
+                return;
             }
-        }
-    }
 
-    if had_failure {
+            match pipeline::convert_file(file, &final_args) {
+                Ok(success) => {
+                    if !success {
+                        had_failure.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+                Err(e) => {
+                    println!(
+                        "{}",
+                        format!("Error processing file '{}': {}", file, e).red()
+                    );
+                    had_failure.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+            worker.inc();
+            worker.finish();
+            parallel_progress.inc_aggregate(1);
+        });
+    });
+    parallel_progress.finish();
+
+    if had_failure.load(std::sync::atomic::Ordering::Relaxed) {
         std::process::exit(1);
     }
     Ok(())
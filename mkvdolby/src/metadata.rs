@@ -7,6 +7,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::external;
+use crate::isobmff;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum HdrFormat {
@@ -18,7 +19,6 @@ pub enum HdrFormat {
 }
 
 impl HdrFormat {
-    #[allow(dead_code)]
     pub fn name(&self) -> &'static str {
         match self {
             HdrFormat::Hdr10Plus => "HDR10+",
@@ -31,10 +31,9 @@ impl HdrFormat {
 }
 
 pub fn get_mediainfo_json(input_file: &str) -> Result<Value> {
-    // Basic cache logic could be added using OnceLock or just re-run (fast enough)
     let mut cmd = Command::new("mediainfo");
     cmd.arg("--Output=JSON").arg(input_file);
-    let out = external::get_command_output(&mut cmd)?;
+    let out = external::get_command_output_cached(&mut cmd)?;
     serde_json::from_str(&out).context("Failed to parse mediainfo JSON")
 }
 
@@ -52,7 +51,7 @@ pub fn get_ffprobe_json(input_file: &str) -> Result<Value> {
         "%+#1",
         input_file,
     ]);
-    let out = external::get_command_output(&mut cmd)?;
+    let out = external::get_command_output_cached(&mut cmd)?;
     serde_json::from_str(&out).context("Failed to parse ffprobe JSON")
 }
 
@@ -101,17 +100,22 @@ pub fn find_details_file(input_file: &Path) -> Option<PathBuf> {
 pub fn check_hdr_format(input_file: &str) -> HdrFormat {
     let path = Path::new(input_file);
 
+    // 0. Native ISOBMFF box-tree parse (no external process). Only succeeds for MP4-family
+    // inputs with the expected hvc1/hev1 sample entry; falls through to mediainfo/ffprobe for
+    // MPEG-TS, Matroska, or anything the parser doesn't recognize.
+    if let Some((format, _meta)) = isobmff::parse_isobmff_hdr(input_file) {
+        if format != HdrFormat::Unsupported {
+            return format;
+        }
+    }
+
     // 1. MediaInfo Text Check
-    let mi_text = match Command::new("mediainfo")
-        .args([
-            "--Inform=Video;%HDR_Format%/%HDR_Format_Compatibility%",
-            input_file,
-        ])
-        .output()
-    {
-        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
-        Err(_) => String::new(),
-    };
+    let mut mi_cmd = Command::new("mediainfo");
+    mi_cmd.args([
+        "--Inform=Video;%HDR_Format%/%HDR_Format_Compatibility%",
+        input_file,
+    ]);
+    let mi_text = external::get_command_output_cached(&mut mi_cmd).unwrap_or_default();
 
     let measurements = find_measurements_file(path).is_some();
 
@@ -158,13 +162,148 @@ pub fn check_hdr_format(input_file: &str) -> HdrFormat {
     HdrFormat::Unsupported
 }
 
+/// Enumerates every video track in `input_file` and classifies each one's HDR format and static
+/// metadata, for containers that carry more than one (e.g. a BT.2020 PQ main track alongside a
+/// Dolby Vision enhancement layer or a secondary angle). `track_index` is 0-based among the
+/// video tracks found, not the container's own track-ID numbering.
+///
+/// Only the native ISOBMFF box-tree parse can currently see every track; mediainfo/ffprobe are
+/// single-stream APIs here (see `check_hdr_format`/`get_static_metadata`), so non-MP4 inputs (or
+/// MP4s the parser doesn't recognize) fall back to reporting just the one track those functions
+/// already look at.
+pub fn detect_hdr_tracks(input_file: &str) -> Vec<(usize, HdrFormat, HashMap<String, f64>)> {
+    if let Some(tracks) = isobmff::parse_isobmff_hdr_tracks(input_file) {
+        if !tracks.is_empty() {
+            return tracks;
+        }
+    }
+    vec![(0, check_hdr_format(input_file), get_static_metadata(input_file))]
+}
+
+/// Picks which track from `detect_hdr_tracks`'s output to analyze. Honors an explicit
+/// `--video-track` request if that index was actually detected; otherwise, or if the requested
+/// index wasn't found, auto-selects the first HDR-signaled track rather than blindly track 0,
+/// falling back to track 0 if none of them carry recognized HDR metadata. Returns `None` only if
+/// no video tracks were detected at all.
+pub fn select_hdr_track(
+    tracks: &[(usize, HdrFormat, HashMap<String, f64>)],
+    requested: Option<usize>,
+) -> Option<usize> {
+    if let Some(requested) = requested {
+        if let Some((idx, _, _)) = tracks.iter().find(|(idx, _, _)| *idx == requested) {
+            return Some(*idx);
+        }
+        // Requested index wasn't detected: fall through to the same auto-selection the `None`
+        // case uses, rather than returning `None` (which callers treat as "no HDR track", not
+        // "bad --video-track value").
+    }
+    tracks
+        .iter()
+        .find(|(_, format, _)| *format != HdrFormat::Unsupported)
+        .or_else(|| tracks.first())
+        .map(|(idx, _, _)| *idx)
+}
+
+/// Reads HDR static metadata (mastering display luminance, MaxCLL, MaxFALL) directly from
+/// the container's own side data via ffprobe, rather than relying on MediaInfo's text
+/// summary. Returns `None` if ffprobe fails or the video stream carries no such side data
+/// (e.g. plain HDR10 without mastering metadata baked in, or SDR sources).
+pub fn get_container_static_metadata(input_file: &str) -> Option<HashMap<String, f64>> {
+    let json = get_ffprobe_json(input_file).ok()?;
+    let streams = json.get("streams")?.as_array()?;
+    let video_stream = streams
+        .iter()
+        .find(|s| s.get("codec_type").and_then(|v| v.as_str()) == Some("video"))?;
+    let side_data_list = video_stream.get("side_data_list")?.as_array()?;
+
+    let mut meta = HashMap::new();
+    for side_data in side_data_list {
+        match side_data.get("side_data_type").and_then(|v| v.as_str()) {
+            Some("Mastering display metadata") => {
+                if let Some(v) = side_data.get("max_luminance").and_then(parse_rational_str) {
+                    meta.insert("max_dml".to_string(), v);
+                }
+                if let Some(v) = side_data.get("min_luminance").and_then(parse_rational_str) {
+                    meta.insert("min_dml".to_string(), v);
+                }
+            }
+            Some("Content light level metadata") => {
+                if let Some(v) = side_data.get("max_content").and_then(|v| v.as_f64()) {
+                    meta.insert("max_cll".to_string(), v);
+                }
+                if let Some(v) = side_data.get("max_average").and_then(|v| v.as_f64()) {
+                    meta.insert("max_fall".to_string(), v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if meta.is_empty() {
+        None
+    } else {
+        Some(meta)
+    }
+}
+
+/// ffprobe reports mastering-display luminance as a rational string like "10000000/10000".
+fn parse_rational_str(value: &Value) -> Option<f64> {
+    let s = value.as_str()?;
+    let (num, den) = s.split_once('/')?;
+    let num: f64 = num.parse().ok()?;
+    let den: f64 = den.parse().ok()?;
+    if den == 0.0 {
+        None
+    } else {
+        Some(num / den)
+    }
+}
+
 pub fn get_static_metadata(input_file: &str) -> HashMap<String, f64> {
+    let mut meta = default_static_metadata();
+
+    // Try the native in-process ISOBMFF box parse first (no external process at all); ffprobe
+    // side data and MediaInfo's text parse below still win on conflict since they're the
+    // longer-established sources, but this fills in values for MP4-family inputs instantly.
+    if let Some((_format, isobmff_meta)) = isobmff::parse_isobmff_hdr(input_file) {
+        meta.extend(isobmff_meta);
+    }
+
+    apply_external_metadata_overrides(&mut meta, input_file);
+    meta
+}
+
+/// Like `get_static_metadata`, but seeded with a specific track's already-parsed ISOBMFF
+/// metadata (from `detect_hdr_tracks`) instead of re-parsing whichever track
+/// `isobmff::parse_isobmff_hdr` happens to match first. Use this once a track has been selected
+/// via `select_hdr_track`, so a non-default track's mastering metadata isn't silently discarded
+/// in favor of track 0's.
+pub fn get_static_metadata_for_track(input_file: &str, track_meta: &HashMap<String, f64>) -> HashMap<String, f64> {
+    let mut meta = default_static_metadata();
+    meta.extend(track_meta.clone());
+    apply_external_metadata_overrides(&mut meta, input_file);
+    meta
+}
+
+fn default_static_metadata() -> HashMap<String, f64> {
     let mut meta = HashMap::new();
-    // Default values
     meta.insert("max_dml".to_string(), 1000.0);
     meta.insert("min_dml".to_string(), 0.0050);
     meta.insert("max_cll".to_string(), 1000.0);
     meta.insert("max_fall".to_string(), 400.0);
+    meta
+}
+
+/// Layers the ffprobe container side-data, MediaInfo text, and `Details.txt` override sources
+/// on top of `meta` in place -- the same precedence `get_static_metadata` has always used, just
+/// factored out so it can be shared with `get_static_metadata_for_track`.
+fn apply_external_metadata_overrides(meta: &mut HashMap<String, f64>, input_file: &str) {
+    // Read side data straight from the container first; MediaInfo's text parse below still
+    // wins on conflict since it's the longer-established source, but this fills in values
+    // MediaInfo misses (or can't find without a Details.txt sidecar).
+    if let Some(container_meta) = get_container_static_metadata(input_file) {
+        meta.extend(container_meta);
+    }
 
     // Try MediaInfo
     if let Ok(json) = get_mediainfo_json(input_file) {
@@ -250,8 +389,47 @@ pub fn get_static_metadata(input_file: &str) -> HashMap<String, f64> {
             }
         }
     }
+}
 
-    meta
+/// Parses a `--mastering-display` spec in the "G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)" layout
+/// x265/dovi_tool use for `--master-display`, into the same `primary_*`/`white_point_*`/
+/// `max_dml`/`min_dml` keys `get_static_metadata` produces (chroma coordinates in 0.00002 units,
+/// luminance in 0.0001 cd/m² units -- the same units the ISOBMFF `mdcv` box uses).
+pub fn parse_mastering_display(spec: &str) -> Option<HashMap<String, f64>> {
+    let capture_pair = |tag: &str| -> Option<(f64, f64)> {
+        let start = spec.find(&format!("{tag}("))? + tag.len() + 1;
+        let end = start + spec[start..].find(')')?;
+        let (x, y) = spec[start..end].split_once(',')?;
+        Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+    };
+    let (g_x, g_y) = capture_pair("G")?;
+    let (b_x, b_y) = capture_pair("B")?;
+    let (r_x, r_y) = capture_pair("R")?;
+    let (wp_x, wp_y) = capture_pair("WP")?;
+    let (max_dml, min_dml) = capture_pair("L")?;
+
+    let mut meta = HashMap::new();
+    meta.insert("primary_g_x".to_string(), g_x * 0.00002);
+    meta.insert("primary_g_y".to_string(), g_y * 0.00002);
+    meta.insert("primary_b_x".to_string(), b_x * 0.00002);
+    meta.insert("primary_b_y".to_string(), b_y * 0.00002);
+    meta.insert("primary_r_x".to_string(), r_x * 0.00002);
+    meta.insert("primary_r_y".to_string(), r_y * 0.00002);
+    meta.insert("white_point_x".to_string(), wp_x * 0.00002);
+    meta.insert("white_point_y".to_string(), wp_y * 0.00002);
+    meta.insert("max_dml".to_string(), max_dml * 0.0001);
+    meta.insert("min_dml".to_string(), min_dml * 0.0001);
+    Some(meta)
+}
+
+/// Parses a `--content-light` spec ("maxcll,maxfall") into `get_static_metadata`'s `max_cll`/
+/// `max_fall` keys.
+pub fn parse_content_light(spec: &str) -> Option<HashMap<String, f64>> {
+    let (cll, fall) = spec.split_once(',')?;
+    let mut meta = HashMap::new();
+    meta.insert("max_cll".to_string(), cll.trim().parse().ok()?);
+    meta.insert("max_fall".to_string(), fall.trim().parse().ok()?);
+    Some(meta)
 }
 
 pub fn generate_extra_json(
@@ -280,6 +458,10 @@ pub fn generate_extra_json(
 }
 
 pub fn get_duration_from_mediainfo(input_file: &str) -> Option<f64> {
+    // Native ISOBMFF `mvhd` read first -- no external process at all for MP4-family inputs.
+    if let Some(duration) = isobmff::parse_isobmff_duration(input_file) {
+        return Some(duration);
+    }
     if let Ok(json) = get_mediainfo_json(input_file) {
         if let Some(tracks) = json
             .get("media")
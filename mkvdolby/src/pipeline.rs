@@ -4,9 +4,10 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::cli::{Args, HwAccel, PeakSource};
-use crate::external::{self, run_command, run_command_live};
+use crate::cli::{Args, HwAccel, OutputFormat, PeakSource, TransferCharacteristics};
+use crate::external::{self, run_command, run_command_live, run_command_progress};
 use crate::metadata::{self, HdrFormat};
+use crate::progress::Progress as TranscodeProgress;
 
 pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
     let input_path = Path::new(input_file);
@@ -18,15 +19,24 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
         return Ok(false);
     }
 
-    // Output filename: name.DV.mkv
+    // Output filename: name.DV.mkv (mkv) or name.cmaf/ (cmaf)
     let stem = input_path.file_stem().unwrap().to_string_lossy();
     let dir = input_path.parent().unwrap_or(Path::new("."));
     let output_file = dir.join(format!("{}.DV.mkv", stem));
-
-    if output_file.exists() {
+    let cmaf_output_dir = dir.join(format!("{}.cmaf", stem));
+
+    let output_exists = match args.output_format {
+        OutputFormat::Mkv => output_file.exists(),
+        OutputFormat::Cmaf => cmaf_output_dir.join("init.mp4").exists(),
+    };
+    if output_exists {
+        let existing = match args.output_format {
+            OutputFormat::Mkv => &output_file,
+            OutputFormat::Cmaf => &cmaf_output_dir,
+        };
         println!(
             "{}",
-            format!("Output file '{:?}' already exists. Skipping.", output_file).yellow()
+            format!("Output '{:?}' already exists. Skipping.", existing).yellow()
         );
         return Ok(true);
     }
@@ -41,8 +51,52 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
     let temp_dir = dir.join(&temp_dir_name);
     fs::create_dir_all(&temp_dir).context("Failed to create temp directory")?;
 
-    // Determine format
-    let mut hdr_type = metadata::check_hdr_format(input_file);
+    // Determine format. Enumerate every detected video track so multi-track containers (e.g. a
+    // BT.2020 PQ main track alongside a Dolby Vision enhancement layer) are reported rather than
+    // silently analyzing whatever mediainfo/ffprobe happen to see first.
+    let tracks = metadata::detect_hdr_tracks(input_file);
+    let selected_track = metadata::select_hdr_track(&tracks, args.video_track);
+
+    if tracks.len() > 1 {
+        println!("{}", "Detected video tracks:".cyan());
+        for (idx, format, _) in &tracks {
+            let marker = if Some(*idx) == selected_track {
+                " (selected)"
+            } else {
+                ""
+            };
+            println!("  track {idx}: {}{marker}", format.name());
+        }
+    }
+    if let Some(requested) = args.video_track {
+        if selected_track != Some(requested) {
+            println!(
+                "{}",
+                format!("--video-track {requested} not found; falling back to auto-selection.")
+                    .yellow()
+            );
+        }
+    }
+
+    let mut hdr_type = selected_track
+        .and_then(|idx| tracks.iter().find(|(i, _, _)| *i == idx))
+        .map(|(_, format, _)| *format)
+        .unwrap_or(HdrFormat::Unsupported);
+
+    // --transfer forces the HDR format outright, for raw/stripped streams or files
+    // mediainfo/ffprobe mis-tag, overriding whatever detection landed on above.
+    if let Some(transfer) = args.transfer {
+        hdr_type = match transfer {
+            TransferCharacteristics::Hlg => HdrFormat::Hlg,
+            TransferCharacteristics::Pq => HdrFormat::Hdr10WithMeasurements,
+            TransferCharacteristics::Bt709 => HdrFormat::Unsupported,
+        };
+        println!(
+            "{}",
+            format!("--transfer override: forcing HDR format to {}", hdr_type.name()).cyan()
+        );
+    }
+
     let mut measurements_file: Option<PathBuf> = None;
     let mut hdr10plus_json: Option<PathBuf> = None;
     let mut bl_source_file = PathBuf::from(input_file);
@@ -137,15 +191,41 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
         }
     }
 
-    // Static Metadata
-    let static_meta = metadata::get_static_metadata(input_file);
+    // Static Metadata, seeded from the selected track's own ISOBMFF data when one was detected,
+    // rather than always re-deriving it from whichever track happens to match first.
+    let mut static_meta = match selected_track.and_then(|idx| tracks.iter().find(|(i, _, _)| *i == idx)) {
+        Some((_, _, track_meta)) => metadata::get_static_metadata_for_track(input_file, track_meta),
+        None => metadata::get_static_metadata(input_file),
+    };
     // TODO: Validate metadata (logic in metadata.rs, just print warnings)
 
+    // CLI overrides take precedence over anything detected above.
+    if let Some(primaries) = args.primaries {
+        static_meta.insert("color_primaries".to_string(), primaries.nclx_code() as f64);
+    }
+    if let Some(matrix) = args.matrix {
+        static_meta.insert("matrix_coefficients".to_string(), matrix.nclx_code() as f64);
+    }
+    if let Some(spec) = &args.mastering_display {
+        match metadata::parse_mastering_display(spec) {
+            Some(overrides) => static_meta.extend(overrides),
+            None => {
+                anyhow::bail!("--mastering-display '{spec}' is not a valid G()B()R()WP()L() spec")
+            }
+        }
+    }
+    if let Some(spec) = &args.content_light {
+        match metadata::parse_content_light(spec) {
+            Some(overrides) => static_meta.extend(overrides),
+            None => anyhow::bail!("--content-light '{spec}' is not a valid \"maxcll,maxfall\" spec"),
+        }
+    }
+
     // Generate extra.json
     let extra_json_path = temp_dir.join("extra.json");
     // Parse trim targets
     // Assuming trim_targets logic is simple: use args, or override from Details.txt if enabled
-    let final_trims: Vec<u32> = args
+    let mut final_trims: Vec<u32> = args
         .trim_targets
         .split(',')
         .filter_map(|s| s.trim().parse().ok())
@@ -157,9 +237,28 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
 
     if args.trim_from_details {
         if let Some(_details) = metadata::find_details_file(input_path) {
-            // Logic to parse details for trims would go here
-            // Using stub or simplified logic
-            // For now, sticking to CLI defaults unless exact logic ported
+            // Details.txt is present. Parsing its real display peak into a trim target is a
+            // known follow-up; for now its MaxCLL/MaxFALL override is already folded into
+            // static_meta above, so we keep the CLI-specified trim targets as-is here.
+        } else {
+            // No Details.txt: derive a single trim target straight from the container's own
+            // static metadata (read above, directly from the file) so trimming still works
+            // on any well-tagged source. A present-but-zero MaxCLL means "unknown" -- fall
+            // back to the mastering display's max luminance rather than treating it as 0 nits.
+            let max_cll = *static_meta.get("max_cll").unwrap_or(&0.0);
+            let max_dml = *static_meta.get("max_dml").unwrap_or(&1000.0);
+            let derived_peak = if max_cll > 0.0 { max_cll } else { max_dml };
+            if derived_peak > 0.0 {
+                println!(
+                    "{}",
+                    format!(
+                        "No Details.txt found; deriving DV trim target from container metadata ({:.0} nits).",
+                        derived_peak
+                    )
+                    .cyan()
+                );
+                final_trims = vec![derived_peak.round() as u32];
+            }
         }
     }
 
@@ -203,7 +302,20 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
     ]);
 
     println!("{}", "Extracting BL to HEVC...".green());
-    if !run_command_live(&mut ffmpeg_cmd, &temp_dir.join("ffmpeg_extract_bl.log"))? {
+    // `-stats` makes ffmpeg print its interactive `frame=... time=...` summary to stderr, which
+    // `run_command_progress` parses into a real ETA against the source's known duration, instead
+    // of the raw byte-pumping `run_command_live` does for every other external call here.
+    let total_ms = metadata::get_duration_from_mediainfo(input_file).map(|secs| (secs * 1000.0) as u64);
+    let bar = total_ms.map(|total| TranscodeProgress::new(total, "Extracting BL to HEVC"));
+    let success = run_command_progress(&mut ffmpeg_cmd, &temp_dir.join("ffmpeg_extract_bl.log"), |p| {
+        if let (Some(bar), Some(total), Some(ms)) = (&bar, total_ms, p.out_time_ms) {
+            bar.set_position(ms.min(total));
+        }
+    })?;
+    if let Some(bar) = &bar {
+        bar.finish();
+    }
+    if !success {
         return Ok(false);
     }
 
@@ -221,41 +333,72 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
     ]);
 
     println!("{}", "Injecting RPU...".green());
-    if !run_command(&mut dovi_cmd, &temp_dir.join("dovi_inject.log"))? {
+    if !run_command(&mut dovi_cmd, &temp_dir.join("dovi_inject.log"), None, None)?.success() {
         return Ok(false);
     }
 
-    // Mux
-    let mut mkvmerge_cmd = Command::new("mkvmerge");
-    mkvmerge_cmd.arg("-q").arg("-o").arg(&output_file);
-    if args.drop_tags {
-        mkvmerge_cmd.arg("--no-global-tags");
-    }
-    if args.drop_chapters {
-        mkvmerge_cmd.arg("--no-chapters");
-    }
+    match args.output_format {
+        OutputFormat::Mkv => {
+            let mut mkvmerge_cmd = Command::new("mkvmerge");
+            mkvmerge_cmd.arg("-q").arg("-o").arg(&output_file);
+            if args.drop_tags {
+                mkvmerge_cmd.arg("--no-global-tags");
+            }
+            if args.drop_chapters {
+                mkvmerge_cmd.arg("--no-chapters");
+            }
 
-    mkvmerge_cmd.arg(&bl_rpu_hevc);
-    mkvmerge_cmd.arg("--no-video").arg(input_file);
+            mkvmerge_cmd.arg(&bl_rpu_hevc);
+            mkvmerge_cmd.arg("--no-video").arg(input_file);
 
-    println!("{}", "Muxing final MKV...".green());
-    if !run_command(&mut mkvmerge_cmd, &temp_dir.join("mkvmerge.log"))? {
-        return Ok(false);
-    }
+            println!("{}", "Muxing final MKV...".green());
+            if !run_command(&mut mkvmerge_cmd, &temp_dir.join("mkvmerge.log"), None, None)?.success() {
+                return Ok(false);
+            }
 
-    // Optional post-mux verification
-    if args.verify {
-        println!("{}", "Running post-mux verification (--verify)...".green());
-        let measurements_file_path = measurements_file.clone(); // Need pathbuf, it's optional
-        let ok = crate::verify::verify_post_mux(
-            input_file,
-            &output_file,
-            measurements_file_path.as_deref(),
-            &temp_dir,
-        );
-        if !ok {
-            println!("{}", "Inconsistencies detected during verification.".red());
-            return Ok(false);
+            // Optional post-mux verification
+            if args.verify {
+                println!("{}", "Running post-mux verification (--verify)...".green());
+                let measurements_file_path = measurements_file.clone(); // Need pathbuf, it's optional
+                let ok = crate::verify::verify_post_mux(
+                    input_file,
+                    &output_file,
+                    measurements_file_path.as_deref(),
+                    &temp_dir,
+                );
+                if !ok {
+                    println!("{}", "Inconsistencies detected during verification.".red());
+                    return Ok(false);
+                }
+            }
+
+            println!(
+                "{}",
+                format!("✓ Success! Created: {:?}", output_file.file_name().unwrap())
+                    .green()
+                    .bold()
+            );
+        }
+        OutputFormat::Cmaf => {
+            if args.verify {
+                println!(
+                    "{}",
+                    "--verify is only supported for --output-format=mkv; skipping.".yellow()
+                );
+            }
+            if !mux_cmaf(&bl_rpu_hevc, &temp_dir, &cmaf_output_dir, args)? {
+                return Ok(false);
+            }
+
+            println!(
+                "{}",
+                format!(
+                    "✓ Success! Created CMAF segments in: {:?}",
+                    cmaf_output_dir.file_name().unwrap()
+                )
+                .green()
+                .bold()
+            );
         }
     }
 
@@ -265,13 +408,66 @@ pub fn convert_file(input_file: &str, args: &Args) -> Result<bool> {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    Ok(true)
+}
+
+/// Packages a DV-RPU HEVC elementary stream into CMAF: first wraps it in a plain fragmented
+/// MP4 (mp4muxer embeds the dvcC/dvvC Dolby Vision configuration box), then hands that to
+/// shaka-packager to cut it into an init segment plus media segments suitable for HLS/DASH.
+fn mux_cmaf(bl_rpu_hevc: &Path, temp_dir: &Path, output_dir: &Path, args: &Args) -> Result<bool> {
+    fs::create_dir_all(output_dir).context("Failed to create CMAF output directory")?;
+
+    let staged_mp4 = temp_dir.join("BL_RPU.mp4");
+    let mut mux_cmd = Command::new("mp4muxer");
+    mux_cmd.args([
+        "-i",
+        bl_rpu_hevc.to_str().unwrap(),
+        "-o",
+        staged_mp4.to_str().unwrap(),
+    ]);
+
+    println!("{}", "Wrapping DV RPU elementary stream in fMP4...".green());
+    if !run_command(&mut mux_cmd, &temp_dir.join("mp4muxer.log"), None, None)?.success() {
+        return Ok(false);
+    }
+
+    let init_path = output_dir.join("init.mp4");
+    let segment_template = output_dir.join("chunk_$Number$.m4s");
+
+    let mut pkg_cmd = Command::new("packager");
+    pkg_cmd.arg(format!(
+        "in={},stream=video,init_segment={},segment_template={}",
+        staged_mp4.display(),
+        init_path.display(),
+        segment_template.display(),
+    ));
+    pkg_cmd
+        .arg("--segment_duration")
+        .arg(args.segment_duration.to_string());
+
+    if args.chunk_duration > 0.0 {
+        // CMAF low-latency chunking: each chunk's moof/mdat is flushed to disk as soon as
+        // it's ready, instead of buffering the whole segment.
+        pkg_cmd
+            .arg("--fragment_duration")
+            .arg(args.chunk_duration.to_string())
+            .arg("--low_latency_dash_mode");
+    }
+
     println!(
         "{}",
-        format!("✓ Success! Created: {:?}", output_file.file_name().unwrap())
-            .green()
-            .bold()
+        format!(
+            "Packaging CMAF segments ({}s segments{})...",
+            args.segment_duration,
+            if args.chunk_duration > 0.0 {
+                format!(", {}s chunks", args.chunk_duration)
+            } else {
+                String::new()
+            }
+        )
+        .green()
     );
-    Ok(true)
+    Ok(run_command_live(&mut pkg_cmd, &temp_dir.join("packager.log"), None, None)?.success())
 }
 
 fn add_optimizer_args(args_vec: &mut Vec<String>, args: &Args) {
@@ -315,7 +511,7 @@ fn run_hdr_analyzer(
 
     println!("{}", "Generating measurements...".green());
     // Use inherit_stderr so indicatif progress bar works correctly (detects TTY)
-    if external::run_command_inherit_stderr(&mut cmd, &temp_dir.join("analyzer.log"))?
+    if external::run_command_inherit_stderr(&mut cmd, &temp_dir.join("analyzer.log"), None, None)?.success()
         && out_path.exists()
     {
         return Ok(Some(out_path));
@@ -342,7 +538,7 @@ fn extract_hdr10plus_metadata(input: &str, temp_dir: &Path) -> Result<Option<Pat
         hevc.to_str().unwrap(),
     ]);
 
-    if !run_command_live(&mut cmd, &temp_dir.join("ffmpeg_extract_hdr10p.log"))? {
+    if !run_command_live(&mut cmd, &temp_dir.join("ffmpeg_extract_hdr10p.log"), None, None)?.success() {
         return Ok(None);
     }
 
@@ -356,7 +552,7 @@ fn extract_hdr10plus_metadata(input: &str, temp_dir: &Path) -> Result<Option<Pat
         json_out.to_str().unwrap(),
     ]);
 
-    if run_command(&mut tool, &temp_dir.join("hdr10plus_tool.log"))?
+    if run_command(&mut tool, &temp_dir.join("hdr10plus_tool.log"), None, None)?.success()
         && json_out.exists()
         && fs::metadata(&json_out)?.len() > 0
     {
@@ -387,10 +583,26 @@ fn convert_hlg_to_pq(input: &str, temp_dir: &Path, args: &Args) -> Result<PathBu
         max_dml_int, min_dml_int
     );
 
-    let x265_params = format!(
+    let mut x265_params = format!(
         "colorprim=bt2020:transfer=smpte2084:colormatrix=bt2020nc:master-display={}:max-cll={},{}:hdr-opt=1:repeat-headers=1",
         master_display, max_cll, max_fall
     );
+    if let Some(aq_mode) = args.hlg_aq_mode {
+        x265_params.push_str(&format!(":aq-mode={}", aq_mode));
+    }
+    if let Some(aq_strength) = args.hlg_aq_strength {
+        x265_params.push_str(&format!(":aq-strength={}", aq_strength));
+    }
+    if let Some(qcomp) = args.hlg_qcomp {
+        x265_params.push_str(&format!(":qcomp={}", qcomp));
+    }
+    if let Some(psy_rd) = args.hlg_psy_rd {
+        x265_params.push_str(&format!(":psy-rd={}", psy_rd));
+    }
+    if let Some(extra) = &args.hlg_x265_params {
+        x265_params.push(':');
+        x265_params.push_str(extra);
+    }
 
     let npl = args.hlg_peak_nits;
     let vf = format!(
@@ -480,7 +692,7 @@ fn convert_hlg_to_pq(input: &str, temp_dir: &Path, args: &Args) -> Result<PathBu
 
     cmd.arg(out_path.to_str().unwrap());
 
-    if run_command_live(&mut cmd, &log_path)? && out_path.exists() {
+    if run_command_live(&mut cmd, &log_path, None, None)?.success() && out_path.exists() {
         println!("{}", "Converted HLG to PQ successfully.".green());
         return Ok(out_path);
     }
@@ -521,7 +733,7 @@ fn generate_rpu(
         _ => return Ok(None),
     }
 
-    if run_command(&mut cmd, &temp_dir.join("dovi_gen.log"))? {
+    if run_command(&mut cmd, &temp_dir.join("dovi_gen.log"), None, None)?.success() {
         Ok(Some(rpu_out))
     } else {
         Ok(None)
@@ -9,7 +9,7 @@ use std::io::IsTerminal;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 
 // --- Global State ---
 
@@ -176,6 +176,86 @@ impl Progress {
     }
 }
 
+// --- Parallel (Multi-Bar) Progress ---
+
+/// A multi-bar progress manager for scene-parallel/chunked frame analysis.
+///
+/// A single `Progress` bar can't represent several workers advancing at once --
+/// they'd race to redraw the same line. This owns an aggregate bar (total frames
+/// across all segments) plus one child bar per worker, all drawn through a shared
+/// `MultiProgress` so concurrent workers render as a grouped job view.
+pub struct ParallelProgress {
+    multi: MultiProgress,
+    aggregate: ProgressBar,
+}
+
+impl ParallelProgress {
+    /// Create a new manager with an aggregate bar over `total` frames.
+    pub fn new(total: u64, message: &str) -> Self {
+        let multi = MultiProgress::new();
+        if !is_tty() || is_verbose() || is_quiet() {
+            multi.set_draw_target(ProgressDrawTarget::hidden());
+        }
+
+        let aggregate = multi.add(ProgressBar::new(total));
+        aggregate.set_style(
+            ProgressStyle::default_bar()
+                .template("  {spinner:.cyan} {msg} [{bar:30.cyan/dim}] {pos}/{len} ({eta})")
+                .expect("Invalid progress template")
+                .progress_chars("━━─"),
+        );
+        aggregate.set_message(message.to_string());
+        aggregate.enable_steady_tick(Duration::from_millis(100));
+
+        Self { multi, aggregate }
+    }
+
+    /// Register a child bar for a worker/scene, drawn below the aggregate bar.
+    pub fn add_worker(&self, total: u64, message: &str) -> WorkerBar {
+        let bar = self.multi.add(ProgressBar::new(total));
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("    {spinner:.dim} {msg} [{bar:20.dim}] {pos}/{len}")
+                .expect("Invalid worker progress template")
+                .progress_chars("━━─"),
+        );
+        bar.set_message(message.to_string());
+        WorkerBar { bar }
+    }
+
+    /// Increment the aggregate bar by `delta` frames.
+    pub fn inc_aggregate(&self, delta: u64) {
+        self.aggregate.inc(delta);
+    }
+
+    /// Finish the aggregate bar and clear the whole group.
+    pub fn finish(&self) {
+        self.aggregate.finish_and_clear();
+    }
+}
+
+/// A single worker's child bar within a `ParallelProgress` group.
+pub struct WorkerBar {
+    bar: ProgressBar,
+}
+
+impl WorkerBar {
+    /// Set this worker's message (e.g. the scene or segment it's processing).
+    pub fn set_message(&self, msg: &str) {
+        self.bar.set_message(msg.to_string());
+    }
+
+    /// Increment this worker's position by 1.
+    pub fn inc(&self) {
+        self.bar.inc(1);
+    }
+
+    /// Finish and remove this worker's bar; the aggregate bar keeps advancing.
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
 // --- Step Printer ---
 
 /// Print a step header (for major pipeline stages)
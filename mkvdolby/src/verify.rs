@@ -37,7 +37,7 @@ pub fn verify_post_mux(
              // Capturing output to check for errors/warnings?
              // Python: just ran it. 'verifier' exits non-zero on error?
              // Assuming yes.
-             if let Err(_) = run_command(&mut cmd, &temp_dir.join("verifier.log")) {
+             if !run_command(&mut cmd, &temp_dir.join("verifier.log"), None, None).map_or(false, |o| o.success()) {
                  println!("{}", "Verifier tool reported issues.".red());
                  ok = false;
              }
@@ -51,7 +51,7 @@ pub fn verify_post_mux(
     let mut dovi = Command::new("dovi_tool");
     dovi.args(["info", "-i", output_file.to_str().unwrap()]);
     // dovi_tool info doesn't fail easily, but if it crashes it's bad.
-    if let Err(_) = run_command(&mut dovi, &temp_dir.join("dovi_info.log")) {
+    if !run_command(&mut dovi, &temp_dir.join("dovi_info.log"), None, None).map_or(false, |o| o.success()) {
         println!("{}", "dovi_tool check failed.".red());
         ok = false;
     }
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use notify::{RecursiveMode, Watcher};
+use rayon::prelude::*;
+
+use crate::cli::Args;
+
+/// How long a candidate file's size must stay unchanged before we treat the copy as finished
+/// and safe to convert. Avoids picking up files that are still being written.
+const DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Watch `dir` for new `.mkv` files and convert them as they settle, processing up to
+/// `args.jobs` files concurrently. Runs until interrupted (Ctrl-C).
+pub fn run_watch(dir: &str, args: &Args) -> Result<()> {
+    let watch_dir = PathBuf::from(dir);
+    if !watch_dir.is_dir() {
+        anyhow::bail!("--watch target is not a directory: {}", dir);
+    }
+
+    println!(
+        "{}",
+        format!("Watching {} for new .mkv files...", watch_dir.display()).cyan()
+    );
+
+    let (tx, rx) = channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).context("Failed to create filesystem watcher")?;
+    watcher
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch directory: {}", watch_dir.display()))?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.max(1))
+        .build()
+        .context("Failed to build conversion thread pool")?;
+
+    // Tracks candidate files seen but not yet confirmed stable: path -> (last size, last change).
+    let mut settling: HashMap<PathBuf, (u64, Instant)> = HashMap::new();
+
+    loop {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                for path in event.paths {
+                    if is_candidate_mkv(&path) {
+                        let len = file_len(&path);
+                        settling.insert(path, (len, Instant::now()));
+                    }
+                }
+            }
+            Ok(Err(e)) => eprintln!("{}", format!("Watch error: {}", e).red()),
+            Err(_) => {
+                // Timed out with no events; fall through and re-check debounce state below.
+            }
+        }
+
+        let now = Instant::now();
+        let mut ready: Vec<PathBuf> = Vec::new();
+        settling.retain(|path, (last_len, last_change)| {
+            let current_len = file_len(path);
+            if current_len != *last_len {
+                *last_len = current_len;
+                *last_change = now;
+                true
+            } else if now.duration_since(*last_change) >= DEBOUNCE {
+                ready.push(path.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        if !ready.is_empty() {
+            pool.install(|| {
+                ready.par_iter().for_each(|path| convert_one(path, args));
+            });
+        }
+    }
+}
+
+fn convert_one(path: &Path, args: &Args) {
+    let file = path.to_string_lossy().to_string();
+    println!("{}", format!("Picked up: {}", file).green());
+    match crate::pipeline::convert_file(&file, args) {
+        Ok(true) => println!("{}", format!("Converted: {}", file).green()),
+        Ok(false) => println!("{}", format!("Conversion reported failure: {}", file).yellow()),
+        Err(e) => println!("{}", format!("Error converting {}: {}", file, e).red()),
+    }
+}
+
+fn is_candidate_mkv(path: &Path) -> bool {
+    path.extension().map_or(false, |e| e == "mkv") && !path.to_string_lossy().ends_with(".DV.mkv")
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
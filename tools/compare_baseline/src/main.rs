@@ -1,10 +1,16 @@
 use anyhow::Result;
-use clap::Parser;
-use itertools::izip;
+use clap::{Parser, ValueEnum};
 use madvr_parse::MadVRMeasurements;
+use serde_json::json;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+#[derive(Clone, Debug, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -15,36 +21,198 @@ struct Args {
     /// Path to the directory with new .bin files to compare
     #[arg(short, long)]
     current: PathBuf,
+
+    /// Output format. "json" is meant for CI: one object per compared file plus an aggregate
+    /// summary, instead of the human-readable report.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Fail (non-zero exit) if the absolute scene-count delta for any file exceeds this.
+    #[arg(long)]
+    max_scene_delta: Option<u64>,
+
+    /// Fail (non-zero exit) if the absolute MaxCLL delta (nits) for any file exceeds this.
+    #[arg(long)]
+    max_maxcll_delta: Option<u32>,
+
+    /// Fail (non-zero exit) if the per-frame target_nits 95th-percentile absolute delta for any
+    /// file exceeds this.
+    #[arg(long)]
+    max_p95_target_delta: Option<f64>,
+}
+
+/// Summary statistics of a set of non-negative absolute deltas. `p50` is the median -- kept as
+/// its own field (rather than just calling it "median") since it sits alongside `p95`/`p99` in
+/// the same percentile family.
+#[derive(Debug)]
+struct Distribution {
+    mean: f64,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    max: f64,
+}
+
+impl Distribution {
+    fn from_deltas(mut deltas: Vec<f64>) -> Option<Self> {
+        if deltas.is_empty() {
+            return None;
+        }
+        deltas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let percentile = |p: f64| -> f64 {
+            let idx = ((deltas.len() as f64 * p).floor() as usize).min(deltas.len() - 1);
+            deltas[idx]
+        };
+        let mean = deltas.iter().sum::<f64>() / deltas.len() as f64;
+        Some(Distribution {
+            mean,
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: *deltas.last().unwrap(),
+        })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "mean": self.mean,
+            "p50": self.p50,
+            "p95": self.p95,
+            "p99": self.p99,
+            "max": self.max,
+        })
+    }
+
+    fn print(&self, label: &str) {
+        println!("\n{label}:");
+        println!("  mean: {:.2}  p50: {:.2}  p95: {:.2}  p99: {:.2}  max: {:.2}",
+            self.mean, self.p50, self.p95, self.p99, self.max);
+    }
+}
+
+/// One file's baseline-vs-current comparison.
+struct FileComparison {
+    file_name: String,
+    scene_delta: i64,
+    maxcll_delta: i32,
+    maxfall_delta: i32,
+    target_nits_delta: Option<Distribution>,
+    scene_avg_pq_delta: Option<Distribution>,
+    scene_peak_nits_delta: Option<Distribution>,
+    scenes_added: usize,
+    scenes_removed: usize,
+    boundary_shifts: usize,
+    frame_count_mismatch: bool,
+}
+
+impl FileComparison {
+    /// Whether this file exceeds any of the caller's configured thresholds.
+    fn regressed(&self, args: &Args) -> bool {
+        if let Some(max) = args.max_scene_delta {
+            if self.scene_delta.unsigned_abs() > max {
+                return true;
+            }
+        }
+        if let Some(max) = args.max_maxcll_delta {
+            if self.maxcll_delta.unsigned_abs() > max {
+                return true;
+            }
+        }
+        if let Some(max) = args.max_p95_target_delta {
+            if self
+                .target_nits_delta
+                .as_ref()
+                .is_some_and(|d| d.p95 > max)
+            {
+                return true;
+            }
+        }
+        false
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    println!(
-        "Comparing baseline measurements in '{}' with current measurements in '{}'",
-        args.baseline.display(),
-        args.current.display()
-    );
+    if matches!(args.format, OutputFormat::Text) {
+        println!(
+            "Comparing baseline measurements in '{}' with current measurements in '{}'",
+            args.baseline.display(),
+            args.current.display()
+        );
+    }
 
     let baseline_files = find_bin_files(&args.baseline)?;
     let current_files = find_bin_files(&args.current)?;
 
+    let mut comparisons = Vec::new();
+    let mut missing_in_current = Vec::new();
+    let mut missing_in_baseline = Vec::new();
+
     for baseline_path in &baseline_files {
         let file_name = baseline_path.file_name().unwrap();
         if let Some(current_path) = current_files
             .iter()
             .find(|p| p.file_name() == Some(file_name))
         {
-            println!("\n--- Comparing {} ---", file_name.to_string_lossy());
-            compare_files(baseline_path, current_path)?;
+            if matches!(args.format, OutputFormat::Text) {
+                println!("\n--- Comparing {} ---", file_name.to_string_lossy());
+            }
+            comparisons.push(compare_files(baseline_path, current_path, &args)?);
         } else {
-            println!(
-                "\n--- Skipping {} (not found in current directory) ---",
-                file_name.to_string_lossy()
-            );
+            missing_in_current.push(file_name.to_string_lossy().to_string());
+            if matches!(args.format, OutputFormat::Text) {
+                println!(
+                    "\n--- Skipping {} (not found in current directory) ---",
+                    file_name.to_string_lossy()
+                );
+            }
+        }
+    }
+    for current_path in &current_files {
+        let file_name = current_path.file_name().unwrap();
+        if !baseline_files
+            .iter()
+            .any(|p| p.file_name() == Some(file_name))
+        {
+            missing_in_baseline.push(file_name.to_string_lossy().to_string());
         }
     }
 
+    let regressed = comparisons.iter().any(|c| c.regressed(&args));
+
+    if matches!(args.format, OutputFormat::Json) {
+        let files: Vec<_> = comparisons
+            .iter()
+            .map(|c| {
+                json!({
+                    "file": c.file_name,
+                    "scene_delta": c.scene_delta,
+                    "maxcll_delta": c.maxcll_delta,
+                    "maxfall_delta": c.maxfall_delta,
+                    "target_nits_delta": c.target_nits_delta.as_ref().map(Distribution::to_json),
+                    "scene_avg_pq_delta": c.scene_avg_pq_delta.as_ref().map(Distribution::to_json),
+                    "scene_peak_nits_delta": c.scene_peak_nits_delta.as_ref().map(Distribution::to_json),
+                    "scenes_added": c.scenes_added,
+                    "scenes_removed": c.scenes_removed,
+                    "boundary_shifts": c.boundary_shifts,
+                    "frame_count_mismatch": c.frame_count_mismatch,
+                    "regressed": c.regressed(&args),
+                })
+            })
+            .collect();
+        let report = json!({
+            "files": files,
+            "missing_in_current": missing_in_current,
+            "missing_in_baseline": missing_in_baseline,
+            "regressed": regressed,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    if regressed {
+        anyhow::bail!("Regression detected: one or more files exceeded the configured thresholds");
+    }
     Ok(())
 }
 
@@ -60,42 +228,43 @@ fn find_bin_files(dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
-fn compare_files(baseline_path: &Path, current_path: &Path) -> Result<()> {
+fn compare_files(baseline_path: &Path, current_path: &Path, args: &Args) -> Result<FileComparison> {
     let baseline_measurements = MadVRMeasurements::parse_file(baseline_path)?;
     let current_measurements = MadVRMeasurements::parse_file(current_path)?;
+    let text = matches!(args.format, OutputFormat::Text);
 
     // Scene Count
-    println!("Scene Count:");
-    println!("  Baseline: {}", baseline_measurements.scenes.len());
-    println!("  Current:  {}", current_measurements.scenes.len());
-    println!(
-        "  Delta:    {}",
-        current_measurements.scenes.len() as isize - baseline_measurements.scenes.len() as isize
-    );
+    let scene_delta =
+        current_measurements.scenes.len() as i64 - baseline_measurements.scenes.len() as i64;
+    if text {
+        println!("Scene Count:");
+        println!("  Baseline: {}", baseline_measurements.scenes.len());
+        println!("  Current:  {}", current_measurements.scenes.len());
+        println!("  Delta:    {}", scene_delta);
+    }
 
     // Overall MaxCLL and MaxFALL
     let baseline_maxcll = baseline_measurements.header.maxcll;
     let current_maxcll = current_measurements.header.maxcll;
     let baseline_maxfall = baseline_measurements.header.maxfall;
     let current_maxfall = current_measurements.header.maxfall;
+    let maxcll_delta = current_maxcll as i32 - baseline_maxcll as i32;
+    let maxfall_delta = current_maxfall as i32 - baseline_maxfall as i32;
+
+    if text {
+        println!("\nOverall MaxCLL:");
+        println!("  Baseline: {}", baseline_maxcll);
+        println!("  Current:  {}", current_maxcll);
+        println!("  Delta:    {}", maxcll_delta);
 
-    println!("\nOverall MaxCLL:");
-    println!("  Baseline: {}", baseline_maxcll);
-    println!("  Current:  {}", current_maxcll);
-    println!(
-        "  Delta:    {}",
-        current_maxcll as i32 - baseline_maxcll as i32
-    );
-
-    println!("\nOverall MaxFALL:");
-    println!("  Baseline: {}", baseline_maxfall);
-    println!("  Current:  {}", current_maxfall);
-    println!(
-        "  Delta:    {}",
-        current_maxfall as i32 - baseline_maxfall as i32
-    );
-
-    // Per-frame target_nits 95th-pct delta
+        println!("\nOverall MaxFALL:");
+        println!("  Baseline: {}", baseline_maxfall);
+        println!("  Current:  {}", current_maxfall);
+        println!("  Delta:    {}", maxfall_delta);
+    }
+
+    // Per-frame target_nits delta distribution. Aligned over the common prefix when frame
+    // counts differ, rather than skipping the comparison entirely.
     let baseline_targets: Vec<u16> = baseline_measurements
         .frames
         .iter()
@@ -107,23 +276,86 @@ fn compare_files(baseline_path: &Path, current_path: &Path) -> Result<()> {
         .map(|m| m.target_nits.unwrap_or(0))
         .collect();
 
-    if baseline_targets.len() == current_targets.len() {
-        let mut deltas: Vec<f64> = izip!(&baseline_targets, &current_targets)
-            .map(|(b, c)| (*c as f64 - *b as f64).abs())
-            .collect();
+    let frame_count_mismatch = baseline_targets.len() != current_targets.len();
+    let common_frames = baseline_targets.len().min(current_targets.len());
+    let target_deltas: Vec<f64> = (0..common_frames)
+        .map(|i| (current_targets[i] as f64 - baseline_targets[i] as f64).abs())
+        .collect();
+    let target_nits_delta = Distribution::from_deltas(target_deltas);
 
-        deltas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    if text {
+        if frame_count_mismatch {
+            println!(
+                "\nFrame counts differ (baseline: {}, current: {}); comparing common prefix of {} frames.",
+                baseline_targets.len(),
+                current_targets.len(),
+                common_frames
+            );
+        }
+        match &target_nits_delta {
+            Some(d) => d.print("Per-frame target_nits absolute delta"),
+            None => println!("\nPer-frame target_nits comparison skipped: no common frames."),
+        }
+    }
 
-        let percentile_index = (deltas.len() as f64 * 0.95).floor() as usize;
-        let p95_delta = deltas.get(percentile_index).unwrap_or(&0.0);
+    // Per-scene avg_pq/peak_nits delta distributions, aligned by scene index over the common
+    // prefix, plus a count of scenes whose start frame shifted.
+    let common_scenes = baseline_measurements
+        .scenes
+        .len()
+        .min(current_measurements.scenes.len());
+    let scene_avg_pq_deltas: Vec<f64> = (0..common_scenes)
+        .map(|i| {
+            (current_measurements.scenes[i].avg_pq - baseline_measurements.scenes[i].avg_pq).abs()
+        })
+        .collect();
+    let scene_peak_nits_deltas: Vec<f64> = (0..common_scenes)
+        .map(|i| {
+            (current_measurements.scenes[i].peak_nits as f64
+                - baseline_measurements.scenes[i].peak_nits as f64)
+                .abs()
+        })
+        .collect();
+    let boundary_shifts = (0..common_scenes)
+        .filter(|&i| baseline_measurements.scenes[i].start != current_measurements.scenes[i].start)
+        .count();
+
+    let scenes_added = current_measurements
+        .scenes
+        .len()
+        .saturating_sub(baseline_measurements.scenes.len());
+    let scenes_removed = baseline_measurements
+        .scenes
+        .len()
+        .saturating_sub(current_measurements.scenes.len());
+
+    let scene_avg_pq_delta = Distribution::from_deltas(scene_avg_pq_deltas);
+    let scene_peak_nits_delta = Distribution::from_deltas(scene_peak_nits_deltas);
 
-        println!("\nPer-frame target_nits 95th-percentile absolute delta:");
-        println!("  Value: {:.2}", p95_delta);
-    } else {
-        println!("\nPer-frame target_nits comparison skipped: frame counts differ.");
-        println!("  Baseline frames: {}", baseline_targets.len());
-        println!("  Current frames:  {}", current_targets.len());
+    if text {
+        if let Some(d) = &scene_avg_pq_delta {
+            d.print("Per-scene avg_pq absolute delta");
+        }
+        if let Some(d) = &scene_peak_nits_delta {
+            d.print("Per-scene peak_nits absolute delta");
+        }
+        println!(
+            "\nScenes added: {}  removed: {}  boundary shifts (common prefix): {}",
+            scenes_added, scenes_removed, boundary_shifts
+        );
     }
 
-    Ok(())
+    Ok(FileComparison {
+        file_name: baseline_path.file_name().unwrap().to_string_lossy().to_string(),
+        scene_delta,
+        maxcll_delta,
+        maxfall_delta,
+        target_nits_delta,
+        scene_avg_pq_delta,
+        scene_peak_nits_delta,
+        scenes_added,
+        scenes_removed,
+        boundary_shifts,
+        frame_count_mismatch,
+    })
 }
@@ -0,0 +1,209 @@
+//! Minimal ISOBMFF box reader for the HDR static-metadata boxes `--source` cross-checks against
+//! a measurement file: `mdcv` (mastering display colour volume), `clli` (content light level),
+//! and `colr`'s transfer characteristics. Scoped to exactly what that cross-check needs, not a
+//! general-purpose box parser (see mkvdolby's `isobmff.rs` for the fuller version this mirrors).
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A single parsed ISOBMFF box header: its four-character-code type, and the (offset, length)
+/// of its payload within the file (after the size+type header).
+struct BoxHeader {
+    box_type: [u8; 4],
+    payload_offset: u64,
+    payload_len: u64,
+}
+
+/// Walks the sibling boxes within `[start, end)` of `file`. Handles both the ordinary 32-bit
+/// size and the 64-bit `largesize` extension (size field == 1), a size of 0 meaning "box
+/// extends to the end of the range", and `uuid` boxes' extra 16-byte extended type.
+fn read_boxes(file: &mut File, start: u64, end: u64) -> std::io::Result<Vec<BoxHeader>> {
+    let mut boxes = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let box_type: [u8; 4] = header[4..8].try_into().unwrap();
+
+        let mut header_len = 8u64;
+        if size == 1 {
+            let mut ext = [0u8; 8];
+            file.read_exact(&mut ext)?;
+            size = u64::from_be_bytes(ext);
+            header_len = 16;
+        } else if size == 0 {
+            size = end - pos;
+        }
+        if &box_type == b"uuid" {
+            header_len += 16;
+        }
+        if size < header_len || pos + size > end {
+            break;
+        }
+
+        boxes.push(BoxHeader {
+            box_type,
+            payload_offset: pos + header_len,
+            payload_len: size - header_len,
+        });
+        pos += size;
+    }
+    Ok(boxes)
+}
+
+fn find_box<'a>(boxes: &'a [BoxHeader], name: &[u8; 4]) -> Option<&'a BoxHeader> {
+    boxes.iter().find(|b| &b.box_type == name)
+}
+
+fn read_payload(file: &mut File, b: &BoxHeader) -> std::io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(b.payload_offset))?;
+    let mut buf = vec![0u8; b.payload_len as usize];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// A HEVC visual sample entry's fixed fields (reserved[6], data_reference_index, then the
+/// video-specific fixed block) occupy 78 bytes before its child boxes (`hvcC`, `colr`, `mdcv`,
+/// `clli`, ...) begin.
+const VISUAL_SAMPLE_ENTRY_FIXED_SIZE: u64 = 78;
+
+/// HDR static metadata read directly from a container's `mdcv`/`clli`/`colr` boxes.
+pub struct SourceHdrMetadata {
+    pub max_cll: Option<u16>,
+    pub max_fall: Option<u16>,
+    /// Mastering display maximum luminance (`mdcv`), in cd/m^2.
+    pub max_display_mastering_luminance: Option<f64>,
+    /// Raw `transfer_characteristics` code from the `colr` box's `nclx` payload (16 = ST 2084 PQ).
+    pub transfer_characteristics: Option<u16>,
+}
+
+/// Reads `moov > trak > mdia > minf > stbl > stsd`'s first `hvc1`/`hev1` sample entry's
+/// `mdcv`/`clli`/`colr` child boxes. Returns `None` for non-ISOBMFF inputs (no `ftyp` box -- e.g.
+/// MPEG-TS or Matroska) or if no HEVC video track with a recognized sample entry is found.
+pub fn read_source_hdr_metadata(input_file: &str) -> Option<SourceHdrMetadata> {
+    let path = Path::new(input_file);
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    let top = read_boxes(&mut file, 0, file_len).ok()?;
+    find_box(&top, b"ftyp")?;
+    let moov = find_box(&top, b"moov")?;
+    let moov_boxes = read_boxes(
+        &mut file,
+        moov.payload_offset,
+        moov.payload_offset + moov.payload_len,
+    )
+    .ok()?;
+
+    for trak in moov_boxes.iter().filter(|b| &b.box_type == b"trak") {
+        if let Some(meta) = read_trak_hdr_metadata(&mut file, trak) {
+            return Some(meta);
+        }
+    }
+    None
+}
+
+fn read_trak_hdr_metadata(file: &mut File, trak: &BoxHeader) -> Option<SourceHdrMetadata> {
+    let trak_boxes = read_boxes(
+        file,
+        trak.payload_offset,
+        trak.payload_offset + trak.payload_len,
+    )
+    .ok()?;
+    let mdia = find_box(&trak_boxes, b"mdia")?;
+    let mdia_boxes = read_boxes(
+        file,
+        mdia.payload_offset,
+        mdia.payload_offset + mdia.payload_len,
+    )
+    .ok()?;
+    let minf = find_box(&mdia_boxes, b"minf")?;
+    let minf_boxes = read_boxes(
+        file,
+        minf.payload_offset,
+        minf.payload_offset + minf.payload_len,
+    )
+    .ok()?;
+    let stbl = find_box(&minf_boxes, b"stbl")?;
+    let stbl_boxes = read_boxes(
+        file,
+        stbl.payload_offset,
+        stbl.payload_offset + stbl.payload_len,
+    )
+    .ok()?;
+    let stsd = find_box(&stbl_boxes, b"stsd")?;
+
+    // stsd is a FullBox (4-byte version/flags) followed by a 4-byte entry_count, then the
+    // sample entries themselves.
+    if stsd.payload_len < 8 {
+        return None;
+    }
+    let entries = read_boxes(
+        file,
+        stsd.payload_offset + 8,
+        stsd.payload_offset + stsd.payload_len,
+    )
+    .ok()?;
+    let sample_entry = entries
+        .iter()
+        .find(|b| &b.box_type == b"hvc1" || &b.box_type == b"hev1")?;
+    if sample_entry.payload_len <= VISUAL_SAMPLE_ENTRY_FIXED_SIZE {
+        return None;
+    }
+
+    let child_start = sample_entry.payload_offset + VISUAL_SAMPLE_ENTRY_FIXED_SIZE;
+    let child_end = sample_entry.payload_offset + sample_entry.payload_len;
+    let children = read_boxes(file, child_start, child_end).ok()?;
+
+    let mut transfer_characteristics = None;
+    if let Some(colr) = find_box(&children, b"colr") {
+        if let Ok(payload) = read_payload(file, colr) {
+            // "nclx" colour_type: 2-byte primaries, 2-byte transfer, 2-byte matrix, 1-byte
+            // full_range flag.
+            if payload.len() >= 11 && &payload[0..4] == b"nclx" {
+                transfer_characteristics = Some(u16::from_be_bytes([payload[6], payload[7]]));
+            }
+        }
+    }
+
+    let mut max_cll = None;
+    let mut max_fall = None;
+    if let Some(clli) = find_box(&children, b"clli") {
+        if let Ok(payload) = read_payload(file, clli) {
+            if payload.len() >= 4 {
+                max_cll = Some(u16::from_be_bytes(payload[0..2].try_into().unwrap()));
+                max_fall = Some(u16::from_be_bytes(payload[2..4].try_into().unwrap()));
+            }
+        }
+    }
+
+    let mut max_display_mastering_luminance = None;
+    if let Some(mdcv) = find_box(&children, b"mdcv") {
+        if let Ok(payload) = read_payload(file, mdcv) {
+            // SMPTE ST 2086: 3x(primary x,y) + white point (x,y) as u16 in 0.00002 units, then
+            // max/min luminance as u32 in 0.0001 cd/m^2 units -- we only need max luminance here.
+            if payload.len() >= 20 {
+                let max_lum = u32::from_be_bytes(payload[16..20].try_into().unwrap());
+                max_display_mastering_luminance = Some(max_lum as f64 * 0.0001);
+            }
+        }
+    }
+
+    if transfer_characteristics.is_none()
+        && max_cll.is_none()
+        && max_fall.is_none()
+        && max_display_mastering_luminance.is_none()
+    {
+        return None;
+    }
+
+    Some(SourceHdrMetadata {
+        max_cll,
+        max_fall,
+        max_display_mastering_luminance,
+        transfer_characteristics,
+    })
+}
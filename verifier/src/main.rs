@@ -3,8 +3,10 @@
 //! This tool can read and validate MadVR measurement files, displaying
 //! their contents and verifying the format integrity.
 
+mod isobmff;
+
 use anyhow::{Context, Result};
-use madvr_parse::{MadVRFrame, MadVRMeasurements, MadVRScene};
+use madvr_parse::{MadVRFrame, MadVRHeader, MadVRMeasurements, MadVRScene};
 use std::env;
 use std::fs;
 
@@ -28,17 +30,94 @@ fn pq_to_nits(pq: f64) -> f64 {
     y * ST2084_Y_MAX
 }
 
+/// Which optional extra modes (if any) were requested on the command line. Unlike a single
+/// enum slot, these can combine freely, e.g. `--compare other.bin --source clip.mkv`.
+#[derive(Default)]
+struct Modes {
+    rewrite: Option<String>,
+    compare: Option<String>,
+    check_scenes: bool,
+    source: Option<String>,
+    /// Group size N and output path for `--decimate <N> <out.bin>`.
+    decimate: Option<(usize, String)>,
+}
+
+// --- Constants for --check-scenes re-derivation ---
+/// Combined histogram-intersection + avg_pq-delta score above which consecutive frames are
+/// declared a scene cut.
+const SCENE_CUT_SCORE_THRESHOLD: f64 = 0.35;
+/// Minimum frames since the last derived cut before another one is accepted (flicker guard).
+const SCENE_CUT_MIN_LENGTH: usize = 24;
+/// Tolerance (in frames) when matching a derived cut against a stored `scene.start` boundary.
+const SCENE_CUT_MATCH_TOLERANCE: i64 = 2;
+
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} <measurement_file.bin>", args[0]);
+    if args.len() < 2 {
+        eprintln!(
+            "Usage: {} <measurement_file.bin> [--rewrite <out.bin>] [--compare <other.bin>] \
+             [--check-scenes] [--source <video.mp4|mkv>] [--decimate <N> <out.bin>]",
+            args[0]
+        );
         std::process::exit(1);
     }
 
     let file_path = &args[1];
+    let mut modes = Modes::default();
+    let mut i = 2;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rewrite" => {
+                i += 1;
+                modes.rewrite = Some(
+                    args.get(i)
+                        .cloned()
+                        .context("--rewrite requires an output path")?,
+                );
+            }
+            "--compare" => {
+                i += 1;
+                modes.compare = Some(
+                    args.get(i)
+                        .cloned()
+                        .context("--compare requires a path to the other measurement file")?,
+                );
+            }
+            "--check-scenes" => modes.check_scenes = true,
+            "--source" => {
+                i += 1;
+                modes.source = Some(
+                    args.get(i)
+                        .cloned()
+                        .context("--source requires a path to the source video file")?,
+                );
+            }
+            "--decimate" => {
+                i += 1;
+                let group_size: usize = args
+                    .get(i)
+                    .context("--decimate requires a group size N")?
+                    .parse()
+                    .context("--decimate group size N must be a positive integer")?;
+                i += 1;
+                let out_path = args
+                    .get(i)
+                    .cloned()
+                    .context("--decimate requires an output path after N")?;
+                modes.decimate = Some((group_size, out_path));
+            }
+            other => anyhow::bail!("Unrecognized argument: {}", other),
+        }
+        i += 1;
+    }
+
     println!("Verifying measurement file: {}", file_path);
 
-    let (scenes, frames, has_optimizer, header) = read_measurement_file(file_path)?;
+    let (file_data, measurements) = parse_measurement_file(file_path)?;
+    let header = &measurements.header;
+    let scenes = &measurements.scenes;
+    let frames = &measurements.frames;
+    let has_optimizer = header.flags == 3;
 
     println!("\n=== FILE SUMMARY ===");
     println!("Scenes: {}", scenes.len());
@@ -111,28 +190,571 @@ fn main() -> Result<()> {
     }
 
     println!("\n=== VALIDATION ===");
-    validate_measurement_data(&scenes, &frames)?;
+    validate_measurement_data(scenes, frames)?;
 
     // Additional validations: FALL coherence and flags/data consistency
     println!("\n=== ADDITIONAL CHECKS ===");
-    validate_fall_consistency(&frames, header.maxfall, header.avgfall);
-    validate_flags_vs_data(has_optimizer, &frames);
+    validate_fall_consistency(frames, header.maxfall, header.avgfall);
+    validate_flags_vs_data(has_optimizer, frames);
     println!("✓ File format is valid");
     println!("✓ All data integrity checks passed");
 
+    if let Some(out_path) = &modes.rewrite {
+        rewrite_and_verify(&file_data, &measurements, out_path)?;
+    }
+    if let Some(other_path) = &modes.compare {
+        compare_measurement_files(&measurements, other_path)?;
+    }
+    if modes.check_scenes {
+        check_scenes(&measurements);
+    }
+    if let Some(source_path) = &modes.source {
+        cross_check_source(&measurements, source_path);
+    }
+    if let Some((group_size, out_path)) = &modes.decimate {
+        decimate_and_write(&measurements, *group_size, out_path)?;
+    }
+
     Ok(())
 }
 
-/// Read and parse a MadVR measurement file using the madvr_parse library
-fn read_measurement_file(
-    file_path: &str,
-) -> Result<(
-    Vec<MadVRScene>,
-    Vec<MadVRFrame>,
-    bool,
-    madvr_parse::MadVRHeader,
-)> {
-    // Read the file as bytes
+/// Re-serialize a parsed [`MadVRMeasurements`] via `write_measurements()` and write it to
+/// `out_path`, then re-parse the result to confirm it round-trips: same scene/frame counts, and
+/// byte-identical to the original file if the original was itself last written by this same
+/// library (a third-party/hand-edited `.bin` may legitimately re-encode to different bytes while
+/// still parsing to equivalent data).
+fn rewrite_and_verify(
+    original_bytes: &[u8],
+    measurements: &MadVRMeasurements,
+    out_path: &str,
+) -> Result<()> {
+    println!("\n=== REWRITE ===");
+    let rewritten_bytes = measurements
+        .write_measurements()
+        .context("Failed to serialize measurements using madvr_parse library")?;
+    fs::write(out_path, &rewritten_bytes)
+        .with_context(|| format!("Failed to write rewritten file to {}", out_path))?;
+    println!("Wrote {} bytes to {}", rewritten_bytes.len(), out_path);
+
+    let reparsed = MadVRMeasurements::parse_measurements(&rewritten_bytes)
+        .context("Failed to re-parse rewritten measurement file")?;
+    println!(
+        "Round-trip re-parse: {} scene(s), {} frame(s)",
+        reparsed.scenes.len(),
+        reparsed.frames.len()
+    );
+
+    if original_bytes == rewritten_bytes.as_slice() {
+        println!("✓ Rewrite is byte-identical to the original file");
+    } else {
+        println!(
+            "⚠️  Rewrite differs from the original file ({} vs {} bytes) -- expected if the \
+             original wasn't itself produced by write_measurements()",
+            original_bytes.len(),
+            rewritten_bytes.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Align this file's frames/scenes against `other_path` and report quantitative divergence:
+/// per-frame PQ/nits/target-nits deltas (max/mean/RMSE), per-frame luminance-histogram EMD and
+/// chi-square distance (max/mean), and scene-level peak/avg-pq deltas for scenes whose frame
+/// ranges overlap. Unlike `--rewrite`, this never writes a file -- it's purely a reporting mode
+/// for quantifying drift between two analyzer runs or tool versions.
+fn compare_measurement_files(measurements: &MadVRMeasurements, other_path: &str) -> Result<()> {
+    println!("\n=== COMPARE ===");
+    println!("Comparing against: {}", other_path);
+    let (_, other) = parse_measurement_file(other_path)?;
+
+    let frames_a = &measurements.frames;
+    let frames_b = &other.frames;
+    let frame_count = frames_a.len().min(frames_b.len());
+    if frames_a.len() != frames_b.len() {
+        println!(
+            "⚠️  Frame count mismatch: {} vs {} -- comparing the first {} frame(s)",
+            frames_a.len(),
+            frames_b.len(),
+            frame_count
+        );
+    }
+
+    let mut peak_pq_deltas = Vec::with_capacity(frame_count);
+    let mut avg_nits_deltas = Vec::with_capacity(frame_count);
+    let mut target_nits_deltas = Vec::new();
+    let mut emd_deltas = Vec::with_capacity(frame_count);
+    let mut chi_square_deltas = Vec::with_capacity(frame_count);
+
+    for i in 0..frame_count {
+        let fa = &frames_a[i];
+        let fb = &frames_b[i];
+
+        peak_pq_deltas.push((fa.peak_pq_2020 - fb.peak_pq_2020).abs());
+        avg_nits_deltas.push((pq_to_nits(fa.avg_pq) - pq_to_nits(fb.avg_pq)).abs());
+        if let (Some(ta), Some(tb)) = (fa.target_nits, fb.target_nits) {
+            target_nits_deltas.push((ta as f64 - tb as f64).abs());
+        }
+
+        emd_deltas.push(histogram_emd(&fa.lum_histogram, &fb.lum_histogram));
+        chi_square_deltas.push(histogram_chi_square(&fa.lum_histogram, &fb.lum_histogram));
+    }
+
+    println!("\n=== FRAME METRIC DELTAS ({} frame(s)) ===", frame_count);
+    print_delta_stats("Peak PQ (2020)", &peak_pq_deltas);
+    print_delta_stats("Avg PQ (nits)", &avg_nits_deltas);
+    if target_nits_deltas.is_empty() {
+        println!("Target nits: no frame pair has target_nits in both files");
+    } else {
+        print_delta_stats(
+            &format!("Target nits ({} pair(s))", target_nits_deltas.len()),
+            &target_nits_deltas,
+        );
+    }
+    print_delta_stats("Histogram EMD", &emd_deltas);
+    print_delta_stats("Histogram chi-square", &chi_square_deltas);
+
+    compare_scenes(&measurements.scenes, &other.scenes);
+
+    Ok(())
+}
+
+/// Print max/mean/RMSE for a set of per-frame deltas, or a "no frames" note if empty.
+fn print_delta_stats(label: &str, deltas: &[f64]) {
+    if deltas.is_empty() {
+        println!("{}: no frames to compare", label);
+        return;
+    }
+    let n = deltas.len() as f64;
+    let max = deltas.iter().cloned().fold(0.0, f64::max);
+    let mean = deltas.iter().sum::<f64>() / n;
+    let rmse = (deltas.iter().map(|d| d * d).sum::<f64>() / n).sqrt();
+    println!(
+        "{}: max={:.4} mean={:.4} rmse={:.4}",
+        label, max, mean, rmse
+    );
+}
+
+/// Normalize a histogram to sum to 1.0 (treated as all-zero if its sum is non-positive).
+fn normalize_histogram(hist: &[f64]) -> Vec<f64> {
+    let sum: f64 = hist.iter().sum();
+    if sum <= 0.0 {
+        return vec![0.0; hist.len()];
+    }
+    hist.iter().map(|v| v / sum).collect()
+}
+
+/// 1-D Earth Mover's Distance between two histograms: the sum of absolute differences between
+/// their cumulative distributions after each is normalized to sum 1.0.
+fn histogram_emd(hist_a: &[f64], hist_b: &[f64]) -> f64 {
+    let a = normalize_histogram(hist_a);
+    let b = normalize_histogram(hist_b);
+    let len = a.len().min(b.len());
+
+    let mut cum_a = 0.0;
+    let mut cum_b = 0.0;
+    let mut emd = 0.0;
+    for i in 0..len {
+        cum_a += a[i];
+        cum_b += b[i];
+        emd += (cum_a - cum_b).abs();
+    }
+    emd
+}
+
+/// Chi-squared distance between two normalized histograms, with a small epsilon to avoid
+/// div-by-zero (same form as the analyzer's own `calculate_histogram_difference`).
+fn histogram_chi_square(hist_a: &[f64], hist_b: &[f64]) -> f64 {
+    let a = normalize_histogram(hist_a);
+    let b = normalize_histogram(hist_b);
+    let len = a.len().min(b.len());
+
+    let mut dist = 0.0;
+    for i in 0..len {
+        let diff = a[i] - b[i];
+        let denom = a[i] + b[i] + 1e-6;
+        dist += (diff * diff) / denom;
+    }
+    dist
+}
+
+/// Match scenes between the two files by overlapping `start..=end` ranges and report per-pair
+/// peak-nits/avg-pq deltas, flagging any scene on either side with no overlapping counterpart.
+fn compare_scenes(scenes_a: &[MadVRScene], scenes_b: &[MadVRScene]) {
+    println!("\n=== SCENE COMPARISON ===");
+    let overlaps = |s1: &MadVRScene, s2: &MadVRScene| s1.start <= s2.end && s2.start <= s1.end;
+
+    let mut matched = 0;
+    for (i, sa) in scenes_a.iter().enumerate() {
+        match scenes_b.iter().find(|sb| overlaps(sa, sb)) {
+            Some(sb) => {
+                matched += 1;
+                let peak_delta = (sa.peak_nits as f64 - sb.peak_nits as f64).abs();
+                let avg_pq_delta = (sa.avg_pq - sb.avg_pq).abs();
+                println!(
+                    "Scene {} ({}..={}): peak Δ={:.1} nits, avg_pq Δ={:.4}",
+                    i + 1,
+                    sa.start,
+                    sa.end,
+                    peak_delta,
+                    avg_pq_delta
+                );
+            }
+            None => println!(
+                "Scene {} ({}..={}): no overlapping match in the other file",
+                i + 1,
+                sa.start,
+                sa.end
+            ),
+        }
+    }
+
+    let unmatched_b = scenes_b
+        .iter()
+        .filter(|sb| !scenes_a.iter().any(|sa| overlaps(sa, sb)))
+        .count();
+    println!(
+        "Matched {}/{} scene(s) from this file; {} scene(s) in the other file unmatched",
+        matched,
+        scenes_a.len(),
+        unmatched_b
+    );
+}
+
+/// Independently re-derive scene cuts from each frame's `lum_histogram` and compare them against
+/// the stored `MadVRScene` boundaries, analogous to how Av1an cross-checks its own scene-change
+/// detector. Cuts are declared from a combined histogram-intersection + avg_pq-delta score, gated
+/// by a minimum-scene-length guard, then matched against `scene.start` within a small frame
+/// tolerance to report precision/recall and flag missed or invented boundaries.
+fn check_scenes(measurements: &MadVRMeasurements) {
+    println!("\n=== SCENE RE-DERIVATION ===");
+    let frames = &measurements.frames;
+    if frames.len() < 2 {
+        println!("Not enough frames to re-derive scene cuts");
+        return;
+    }
+
+    let mut derived_cuts = vec![0u32];
+    let mut last_cut = 0u32;
+    for i in 1..frames.len() {
+        let hist_prev = normalize_histogram(&frames[i - 1].lum_histogram);
+        let hist_cur = normalize_histogram(&frames[i].lum_histogram);
+        let intersection: f64 = hist_prev
+            .iter()
+            .zip(hist_cur.iter())
+            .map(|(a, b)| a.min(*b))
+            .sum();
+        let hist_dist = 1.0 - intersection;
+        let pq_delta = (frames[i].avg_pq - frames[i - 1].avg_pq).abs();
+        let score = hist_dist + pq_delta;
+
+        let frame_idx = i as u32;
+        if score > SCENE_CUT_SCORE_THRESHOLD && frame_idx - last_cut >= SCENE_CUT_MIN_LENGTH as u32
+        {
+            derived_cuts.push(frame_idx);
+            last_cut = frame_idx;
+        }
+    }
+
+    println!(
+        "Derived {} cut(s) (score threshold={}, min scene length={})",
+        derived_cuts.len(),
+        SCENE_CUT_SCORE_THRESHOLD,
+        SCENE_CUT_MIN_LENGTH
+    );
+
+    let stored_starts: Vec<u32> = measurements.scenes.iter().map(|s| s.start).collect();
+    let within_tolerance =
+        |a: u32, b: u32| (a as i64 - b as i64).abs() <= SCENE_CUT_MATCH_TOLERANCE;
+
+    let matched_derived = derived_cuts
+        .iter()
+        .filter(|&&cut| {
+            let hit = stored_starts.iter().any(|&start| within_tolerance(cut, start));
+            if !hit {
+                println!(
+                    "⚠️  Derived cut at frame {} has no stored scene boundary within ±{} frames (invented boundary?)",
+                    cut, SCENE_CUT_MATCH_TOLERANCE
+                );
+            }
+            hit
+        })
+        .count();
+
+    let matched_stored = stored_starts
+        .iter()
+        .filter(|&&start| {
+            let hit = derived_cuts.iter().any(|&cut| within_tolerance(cut, start));
+            if !hit {
+                println!(
+                    "⚠️  Stored scene boundary at frame {} has no corresponding re-derived cut (stale/corrupt scene table?)",
+                    start
+                );
+            }
+            hit
+        })
+        .count();
+
+    let precision = if derived_cuts.is_empty() {
+        1.0
+    } else {
+        matched_derived as f64 / derived_cuts.len() as f64
+    };
+    let recall = if stored_starts.is_empty() {
+        1.0
+    } else {
+        matched_stored as f64 / stored_starts.len() as f64
+    };
+    println!(
+        "Precision: {:.1}% ({}/{} derived cuts matched)",
+        precision * 100.0,
+        matched_derived,
+        derived_cuts.len()
+    );
+    println!(
+        "Recall: {:.1}% ({}/{} stored boundaries matched)",
+        recall * 100.0,
+        matched_stored,
+        stored_starts.len()
+    );
+}
+
+/// Collapses every `group_size` consecutive frames/scenes into one averaged frame via
+/// `average_frame_group`/rescaled scene boundaries, recomputes the header's `frame_count`,
+/// `maxcll`, `maxfall`, and `avgfall` from the decimated frames, and writes the result to
+/// `out_path` with `write_measurements()` -- borrowing mwa_hyperdrive's "average in time before
+/// doing work" approach to produce a smaller file for lighter-weight downstream pipelines.
+fn decimate_and_write(
+    measurements: &MadVRMeasurements,
+    group_size: usize,
+    out_path: &str,
+) -> Result<()> {
+    println!("\n=== DECIMATE ===");
+    if group_size == 0 {
+        anyhow::bail!("--decimate group size N must be >= 1");
+    }
+    if measurements.frames.is_empty() {
+        anyhow::bail!("Cannot decimate a measurement file with no frames");
+    }
+
+    let decimated_frames: Vec<MadVRFrame> = measurements
+        .frames
+        .chunks(group_size)
+        .map(average_frame_group)
+        .collect();
+    let decimated_scenes =
+        decimate_scenes(&measurements.scenes, group_size, decimated_frames.len());
+
+    let maxcll = decimated_frames
+        .iter()
+        .map(|f| pq_to_nits(f.peak_pq_2020))
+        .fold(0.0f64, f64::max)
+        .round() as u32;
+    let falls_nits: Vec<f64> = decimated_frames
+        .iter()
+        .map(|f| pq_to_nits(f.avg_pq))
+        .collect();
+    let maxfall = falls_nits.iter().cloned().fold(0.0, f64::max).round() as u32;
+    let avgfall = (falls_nits.iter().sum::<f64>() / falls_nits.len() as f64).round() as u32;
+
+    let header = &measurements.header;
+    let decimated_header = MadVRHeader {
+        version: header.version,
+        header_size: header.header_size,
+        scene_count: decimated_scenes.len() as u32,
+        frame_count: decimated_frames.len() as u32,
+        flags: header.flags,
+        maxcll,
+        maxfall,
+        avgfall,
+        target_peak_nits: header.target_peak_nits,
+        ..Default::default()
+    };
+
+    let decimated = MadVRMeasurements {
+        header: decimated_header,
+        scenes: decimated_scenes,
+        frames: decimated_frames,
+    };
+
+    let bytes = decimated
+        .write_measurements()
+        .context("Failed to serialize decimated measurements")?;
+    fs::write(out_path, &bytes)
+        .with_context(|| format!("Failed to write decimated file to {}", out_path))?;
+
+    println!(
+        "Wrote {} decimated frame(s) from {} original (group size {}), {} scene(s), to {} ({} bytes)",
+        decimated.frames.len(),
+        measurements.frames.len(),
+        group_size,
+        decimated.scenes.len(),
+        out_path,
+        bytes.len()
+    );
+
+    Ok(())
+}
+
+/// Takes the group *maximum* for `peak_pq_2020` and `target_nits` (a decimated frame's peak
+/// shouldn't be diluted below any original frame's peak), mean-averages `avg_pq` and each
+/// `lum_histogram` bin, then rescales the histogram to sum to exactly 100.0 (the mean of the
+/// group's histograms already sums to the mean of their totals; this removes any drift from 100.0).
+fn average_frame_group(group: &[MadVRFrame]) -> MadVRFrame {
+    let n = group.len() as f64;
+
+    let peak_pq_2020 = group.iter().map(|f| f.peak_pq_2020).fold(0.0, f64::max);
+    let avg_pq = group.iter().map(|f| f.avg_pq).sum::<f64>() / n;
+    let target_nits = group.iter().filter_map(|f| f.target_nits).max();
+
+    let hist_len = group.first().map_or(0, |f| f.lum_histogram.len());
+    let mut lum_histogram = vec![0.0f64; hist_len];
+    for frame in group {
+        for (bin, value) in lum_histogram.iter_mut().zip(frame.lum_histogram.iter()) {
+            *bin += value / n;
+        }
+    }
+    let sum: f64 = lum_histogram.iter().sum();
+    if sum > 0.0 {
+        let scale = 100.0 / sum;
+        for bin in lum_histogram.iter_mut() {
+            *bin *= scale;
+        }
+    }
+
+    MadVRFrame {
+        peak_pq_2020,
+        avg_pq,
+        lum_histogram,
+        target_nits,
+        ..Default::default()
+    }
+}
+
+/// Rescales each scene's `start`/`end` frame indices onto the decimated frame grid (dividing by
+/// `group_size`), clamping `end` to the last valid decimated frame index.
+fn decimate_scenes(
+    scenes: &[MadVRScene],
+    group_size: usize,
+    decimated_frame_count: usize,
+) -> Vec<MadVRScene> {
+    let last_frame = decimated_frame_count.saturating_sub(1) as u32;
+    scenes
+        .iter()
+        .map(|scene| MadVRScene {
+            start: (scene.start as usize / group_size) as u32,
+            end: ((scene.end as usize / group_size) as u32).min(last_frame),
+            peak_nits: scene.peak_nits,
+            avg_pq: scene.avg_pq,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Tolerance (in nits) when comparing a container's `clli`/`mdcv` values against the
+/// measurement file, to absorb integer rounding between the two encoders.
+const SOURCE_METADATA_TOLERANCE_NITS: f64 = 5.0;
+/// ST 2084 (PQ) `transfer_characteristics` code, per ISO/IEC 23001-8.
+const TRANSFER_CHARACTERISTICS_PQ: u16 = 16;
+
+/// Cross-checks the measurement file's `maxcll`/`maxfall`/PQ-derived peak nits against the
+/// source container's `mdcv`/`clli`/`colr` boxes (read directly via [`isobmff`], no external
+/// ISOBMFF parser dependency), so users can confirm a `.bin` file actually corresponds to the
+/// clip it claims to measure.
+fn cross_check_source(measurements: &MadVRMeasurements, source_path: &str) {
+    println!("\n=== SOURCE CROSS-CHECK ===");
+    println!("Source file: {}", source_path);
+
+    let Some(meta) = isobmff::read_source_hdr_metadata(source_path) else {
+        println!(
+            "⚠️  Could not read HDR static metadata from source (not ISOBMFF, or no mdcv/clli/colr box found)"
+        );
+        return;
+    };
+
+    let header = &measurements.header;
+    let computed_peak_nits = measurements
+        .frames
+        .iter()
+        .map(|f| pq_to_nits(f.peak_pq_2020))
+        .fold(0.0f64, f64::max);
+
+    match meta.max_cll {
+        Some(container_max_cll) => {
+            let measured = header.maxcll as f64;
+            let delta_header = (container_max_cll as f64 - measured).abs();
+            let delta_computed = (container_max_cll as f64 - computed_peak_nits).abs();
+            if delta_header <= SOURCE_METADATA_TOLERANCE_NITS {
+                println!(
+                    "✓ MaxCLL matches container clli box ({} vs header maxcll={})",
+                    container_max_cll, header.maxcll
+                );
+            } else {
+                println!(
+                    "⚠️  MaxCLL mismatch: container clli={} vs header maxcll={} (Δ{:.0} nits)",
+                    container_max_cll, header.maxcll, delta_header
+                );
+            }
+            if delta_computed > SOURCE_METADATA_TOLERANCE_NITS {
+                println!(
+                    "⚠️  MaxCLL mismatch: container clli={} vs PQ-derived peak={:.0} nits (Δ{:.0} nits)",
+                    container_max_cll, computed_peak_nits, delta_computed
+                );
+            }
+        }
+        None => println!("Source has no clli box; skipping MaxCLL cross-check"),
+    }
+
+    match meta.max_fall {
+        Some(container_max_fall) => {
+            let delta = (container_max_fall as f64 - header.maxfall as f64).abs();
+            if delta <= SOURCE_METADATA_TOLERANCE_NITS {
+                println!(
+                    "✓ MaxFALL matches container clli box ({} vs header maxfall={})",
+                    container_max_fall, header.maxfall
+                );
+            } else {
+                println!(
+                    "⚠️  MaxFALL mismatch: container clli={} vs header maxfall={} (Δ{:.0} nits)",
+                    container_max_fall, header.maxfall, delta
+                );
+            }
+        }
+        None => println!("Source has no clli box; skipping MaxFALL cross-check"),
+    }
+
+    match meta.transfer_characteristics {
+        Some(TRANSFER_CHARACTERISTICS_PQ) => {
+            println!("✓ Container signals ST 2084 (PQ) transfer characteristics, as assumed")
+        }
+        Some(other) => println!(
+            "⚠️  Container signals transfer_characteristics={} (not ST 2084/PQ), but this tool \
+             assumes PQ throughout -- measurements may be meaningless for this source",
+            other
+        ),
+        None => println!("Source has no colr box; skipping transfer characteristics check"),
+    }
+
+    if let Some(max_dml) = meta.max_display_mastering_luminance {
+        let delta = (max_dml - header.target_peak_nits as f64).abs();
+        if delta <= SOURCE_METADATA_TOLERANCE_NITS {
+            println!(
+                "✓ Mastering display peak luminance matches header target_peak_nits ({:.0} vs {})",
+                max_dml, header.target_peak_nits
+            );
+        } else {
+            println!(
+                "⚠️  Mastering display peak luminance {:.0} nits vs header target_peak_nits={} (Δ{:.0} nits)",
+                max_dml, header.target_peak_nits, delta
+            );
+        }
+    }
+}
+
+/// Read and parse a MadVR measurement file using the madvr_parse library. Returns the raw bytes
+/// alongside the parsed measurements so `--rewrite` can compare its re-encoded output against
+/// the original file without a second read.
+fn parse_measurement_file(file_path: &str) -> Result<(Vec<u8>, MadVRMeasurements)> {
     let file_data = fs::read(file_path).context("Failed to read measurement file")?;
 
     // Parse using the madvr_parse library
@@ -147,14 +769,7 @@ fn read_measurement_file(
     println!("Flags: {}", measurements.header.flags);
     println!("MaxCLL: {} nits", measurements.header.maxcll);
 
-    let has_optimizer = measurements.header.flags == 3;
-
-    Ok((
-        measurements.scenes,
-        measurements.frames,
-        has_optimizer,
-        measurements.header,
-    ))
+    Ok((file_data, measurements))
 }
 
 /// Validate the measurement data for consistency
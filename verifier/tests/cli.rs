@@ -1,5 +1,6 @@
 use assert_cmd::cargo::cargo_bin;
 use assert_cmd::prelude::*;
+use madvr_parse::{MadVRFrame, MadVRHeader, MadVRMeasurements, MadVRScene};
 use predicates::prelude::*;
 use std::fs;
 use std::process::Command;
@@ -9,6 +10,38 @@ fn verifier_cmd() -> Command {
     Command::new(cargo_bin("verifier"))
 }
 
+fn sample_measurements() -> MadVRMeasurements {
+    let mut lum_histogram = vec![0.0; 256];
+    lum_histogram[128] = 100.0;
+
+    MadVRMeasurements {
+        header: MadVRHeader {
+            version: 5,
+            header_size: 32,
+            scene_count: 1,
+            frame_count: 1,
+            flags: 2,
+            maxcll: 500,
+            maxfall: 100,
+            avgfall: 80,
+            ..Default::default()
+        },
+        scenes: vec![MadVRScene {
+            start: 0,
+            end: 0,
+            peak_nits: 500,
+            avg_pq: 0.4,
+            ..Default::default()
+        }],
+        frames: vec![MadVRFrame {
+            peak_pq_2020: 0.6,
+            avg_pq: 0.4,
+            lum_histogram,
+            ..Default::default()
+        }],
+    }
+}
+
 #[test]
 fn test_missing_input_shows_usage() {
     verifier_cmd()
@@ -19,7 +52,10 @@ fn test_missing_input_shows_usage() {
 
 #[test]
 fn test_nonexistent_file() {
-    verifier_cmd().arg("nonexistent_file.bin").assert().failure();
+    verifier_cmd()
+        .arg("nonexistent_file.bin")
+        .assert()
+        .failure();
 }
 
 #[test]
@@ -30,3 +66,154 @@ fn test_invalid_file_content() {
 
     verifier_cmd().arg(&invalid_file).assert().failure();
 }
+
+#[test]
+fn test_rewrite_round_trips_byte_identical() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let input_path = temp_dir.path().join("input.bin");
+    let output_path = temp_dir.path().join("output.bin");
+
+    let bytes = sample_measurements()
+        .write_measurements()
+        .expect("Failed to serialize fixture measurements");
+    fs::write(&input_path, &bytes).expect("Failed to write fixture file");
+
+    verifier_cmd()
+        .arg(&input_path)
+        .arg("--rewrite")
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("byte-identical"));
+
+    let rewritten = fs::read(&output_path).expect("Failed to read rewritten file");
+    assert_eq!(bytes, rewritten);
+}
+
+#[test]
+fn test_compare_reports_frame_and_scene_deltas() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let file_a = temp_dir.path().join("a.bin");
+    let file_b = temp_dir.path().join("b.bin");
+
+    let mut measurements_b = sample_measurements();
+    measurements_b.frames[0].peak_pq_2020 = 0.5;
+    measurements_b.scenes[0].peak_nits = 400;
+
+    fs::write(
+        &file_a,
+        sample_measurements()
+            .write_measurements()
+            .expect("Failed to serialize fixture A"),
+    )
+    .expect("Failed to write fixture A");
+    fs::write(
+        &file_b,
+        measurements_b
+            .write_measurements()
+            .expect("Failed to serialize fixture B"),
+    )
+    .expect("Failed to write fixture B");
+
+    verifier_cmd()
+        .arg(&file_a)
+        .arg("--compare")
+        .arg(&file_b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FRAME METRIC DELTAS"))
+        .stdout(predicate::str::contains("SCENE COMPARISON"));
+}
+
+#[test]
+fn test_check_scenes_runs_re_derivation_pass() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let input_path = temp_dir.path().join("input.bin");
+
+    let bytes = sample_measurements()
+        .write_measurements()
+        .expect("Failed to serialize fixture measurements");
+    fs::write(&input_path, &bytes).expect("Failed to write fixture file");
+
+    verifier_cmd()
+        .arg(&input_path)
+        .arg("--check-scenes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SCENE RE-DERIVATION"));
+}
+
+#[test]
+fn test_source_cross_check_warns_on_non_isobmff_source() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let input_path = temp_dir.path().join("input.bin");
+    let source_path = temp_dir.path().join("source.mkv");
+
+    let bytes = sample_measurements()
+        .write_measurements()
+        .expect("Failed to serialize fixture measurements");
+    fs::write(&input_path, &bytes).expect("Failed to write fixture file");
+    fs::write(&source_path, b"not an ISOBMFF file").expect("Failed to write fixture source file");
+
+    verifier_cmd()
+        .arg(&input_path)
+        .arg("--source")
+        .arg(&source_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SOURCE CROSS-CHECK"))
+        .stdout(predicate::str::contains(
+            "Could not read HDR static metadata",
+        ));
+}
+
+#[test]
+fn test_decimate_halves_frame_count_and_renormalizes_histogram() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let input_path = temp_dir.path().join("input.bin");
+    let output_path = temp_dir.path().join("decimated.bin");
+
+    let mut measurements = sample_measurements();
+    let mut second_hist = vec![0.0; 256];
+    second_hist[64] = 100.0;
+    measurements.frames.push(MadVRFrame {
+        peak_pq_2020: 0.8,
+        avg_pq: 0.6,
+        lum_histogram: second_hist,
+        ..Default::default()
+    });
+    measurements.scenes[0].end = 1;
+    measurements.header.frame_count = 2;
+
+    fs::write(
+        &input_path,
+        measurements
+            .write_measurements()
+            .expect("Failed to serialize fixture measurements"),
+    )
+    .expect("Failed to write fixture file");
+
+    verifier_cmd()
+        .arg(&input_path)
+        .arg("--decimate")
+        .arg("2")
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("DECIMATE"));
+
+    let decimated_bytes = fs::read(&output_path).expect("Failed to read decimated file");
+    let decimated = MadVRMeasurements::parse_measurements(&decimated_bytes)
+        .expect("Failed to parse decimated file");
+
+    assert_eq!(decimated.frames.len(), 1);
+    assert_eq!(decimated.header.frame_count, 1);
+    let histogram_sum: f64 = decimated.frames[0].lum_histogram.iter().sum();
+    assert!(
+        (histogram_sum - 100.0).abs() < 1.0,
+        "expected histogram to renormalize to ~100.0, got {:.2}",
+        histogram_sum
+    );
+    assert_eq!(decimated.scenes[0].start, 0);
+    assert_eq!(decimated.scenes[0].end, 0);
+}